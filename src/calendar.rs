@@ -0,0 +1,477 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    proto::grpc::tradeapi::v1::assets::{ScheduleRequest, ScheduleResponse, schedule_response},
+    symbol::Symbol,
+};
+
+/// Срок, после которого расписание запрашивается заново.
+const DEFAULT_TTL: Duration = Duration::from_secs(3600);
+
+/// Календари по символам вместе со временем загрузки.
+type CalendarCache = HashMap<String, (SystemTime, Arc<TradingCalendar>)>;
+
+/// Тип торговой сессии.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SessionKind {
+    /// Утренняя сессия до основной (`EARLY_TRADING`).
+    PreOpen,
+    /// Аукцион открытия (`OPENING_AUCTION`).
+    OpeningAuction,
+    /// Основная сессия (`CORE_TRADING`).
+    Main,
+    /// Аукцион закрытия (`CLOSING_AUCTION`).
+    ClosingAuction,
+    /// Вечерняя сессия (`LATE_TRADING`).
+    Evening,
+    /// Торги выходного дня.
+    Weekend,
+    /// Клиринг или технический перерыв.
+    Clearing,
+    /// Торги не проводятся (`CLOSED`).
+    Closed,
+    /// Неизвестный тип сессии.
+    Other(String),
+}
+
+impl SessionKind {
+    /// Разбирает тип сессии из ответа `schedule`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `value` - Тип сессии, например `CORE_TRADING`.
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim().to_ascii_uppercase();
+
+        match value.as_str() {
+            "EARLY_TRADING" | "PRE_OPEN" | "PRE_TRADING" | "MORNING_TRADING" => Self::PreOpen,
+            "OPENING_AUCTION" => Self::OpeningAuction,
+            "CORE_TRADING" | "MAIN_TRADING" => Self::Main,
+            "CLOSING_AUCTION" => Self::ClosingAuction,
+            "LATE_TRADING" | "EVENING_TRADING" | "POST_TRADING" => Self::Evening,
+            "CLOSED" | "NON_TRADING" => Self::Closed,
+            _ if value.contains("WEEKEND") => Self::Weekend,
+            _ if value.contains("CLEARING") || value.contains("BREAK") => Self::Clearing,
+            _ => Self::Other(value),
+        }
+    }
+
+    /// Проверяет, можно ли в этой сессии совершать сделки в непрерывном режиме.
+    ///
+    /// Неизвестные сессии считаются торговыми, если их тип содержит `TRADING`.
+    pub fn is_open(&self) -> bool {
+        match self {
+            Self::PreOpen | Self::Main | Self::Evening | Self::Weekend => true,
+            Self::OpeningAuction | Self::ClosingAuction | Self::Clearing | Self::Closed => false,
+            Self::Other(value) => value.contains("TRADING"),
+        }
+    }
+}
+
+impl schedule_response::Sessions {
+    /// Возвращает тип сессии.
+    pub fn kind(&self) -> SessionKind {
+        SessionKind::parse(&self.r#type)
+    }
+}
+
+/// Торговая сессия с разобранным типом.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    /// Тип сессии.
+    pub kind: SessionKind,
+    /// Начало сессии.
+    pub start: SystemTime,
+    /// Окончание сессии.
+    pub end: SystemTime,
+}
+
+impl Session {
+    /// Проверяет, приходится ли момент на сессию.
+    ///
+    /// # Аргументы
+    ///
+    /// * `at` - Момент времени.
+    pub fn contains(&self, at: SystemTime) -> bool {
+        self.start <= at && at < self.end
+    }
+}
+
+/// Торговый календарь инструмента, построенный по ответу `schedule`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TradingCalendar {
+    symbol: String,
+    sessions: Vec<Session>,
+}
+
+impl TradingCalendar {
+    /// Строит календарь по расписанию.
+    ///
+    /// Сессии без корректного интервала пропускаются.
+    ///
+    /// # Аргументы
+    ///
+    /// * `schedule` - Расписание инструмента.
+    pub fn from_schedule(schedule: &ScheduleResponse) -> Self {
+        let mut sessions: Vec<_> = schedule
+            .sessions
+            .iter()
+            .filter_map(|session| {
+                let interval = session.interval.as_ref()?;
+
+                Some(Session {
+                    kind: session.kind(),
+                    start: SystemTime::try_from(interval.start_time?).ok()?,
+                    end: SystemTime::try_from(interval.end_time?).ok()?,
+                })
+            })
+            .filter(|session| session.start < session.end)
+            .collect();
+
+        sessions.sort_by_key(|session| session.start);
+
+        Self {
+            symbol: schedule.symbol.clone(),
+            sessions,
+        }
+    }
+
+    /// Возвращает символ инструмента.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Возвращает сессии, отсортированные по времени начала.
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// Возвращает окончание последней известной сессии.
+    pub fn covered_until(&self) -> Option<SystemTime> {
+        self.sessions.iter().map(|session| session.end).max()
+    }
+
+    /// Возвращает сессию, на которую приходится момент.
+    ///
+    /// # Аргументы
+    ///
+    /// * `at` - Момент времени.
+    pub fn current_session(&self, at: SystemTime) -> Option<&Session> {
+        self.sessions.iter().find(|session| session.contains(at))
+    }
+
+    /// Проверяет, идут ли торги в момент `at`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `at` - Момент времени.
+    pub fn is_open(&self, at: SystemTime) -> bool {
+        self.current_session(at)
+            .is_some_and(|session| session.kind.is_open())
+    }
+
+    /// Возвращает ближайший момент начала торгов.
+    ///
+    /// Если торги уже идут, возвращает `at`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `at` - Момент, от которого ищется открытие.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Option<SystemTime>` - Момент открытия или `None`, если в расписании нет будущих торговых сессий.
+    pub fn next_open(&self, at: SystemTime) -> Option<SystemTime> {
+        self.open_periods()
+            .find(|(_, end)| at < *end)
+            .map(|(start, _)| start.max(at))
+    }
+
+    /// Возвращает ближайший момент окончания торгов.
+    ///
+    /// Примыкающие друг к другу торговые сессии считаются одним периодом.
+    ///
+    /// # Аргументы
+    ///
+    /// * `at` - Момент, от которого ищется закрытие.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Option<SystemTime>` - Момент закрытия или `None`, если в расписании нет будущих торговых сессий.
+    pub fn next_close(&self, at: SystemTime) -> Option<SystemTime> {
+        self.open_periods()
+            .find(|(_, end)| at < *end)
+            .map(|(_, end)| end)
+    }
+
+    /// Возвращает непрерывные периоды торгов, объединяя примыкающие сессии.
+    fn open_periods(&self) -> impl Iterator<Item = (SystemTime, SystemTime)> + '_ {
+        let mut sessions = self
+            .sessions
+            .iter()
+            .filter(|session| session.kind.is_open())
+            .peekable();
+
+        std::iter::from_fn(move || {
+            let first = sessions.next()?;
+            let mut end = first.end;
+
+            while let Some(next) = sessions.next_if(|next| next.start <= end) {
+                end = end.max(next.end);
+            }
+
+            Some((first.start, end))
+        })
+    }
+}
+
+/// Кэш торговых календарей по инструментам.
+///
+/// Расписание запрашивается через `schedule` при первом обращении и повторно,
+/// когда истек срок кэша или расписание закончилось. Текущее время берется
+/// из [`FinamSdk::server_now`].
+#[derive(Clone, Debug)]
+pub struct TradingCalendars {
+    sdk: FinamSdk,
+    ttl: Duration,
+    calendars: Arc<Mutex<CalendarCache>>,
+}
+
+impl TradingCalendars {
+    /// Создает кэш со сроком хранения расписания в час.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент API.
+    pub fn new(sdk: FinamSdk) -> Self {
+        Self {
+            sdk,
+            ttl: DEFAULT_TTL,
+            calendars: Arc::default(),
+        }
+    }
+
+    /// Задает срок хранения расписания.
+    ///
+    /// # Аргументы
+    ///
+    /// * `ttl` - Срок хранения.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Возвращает календарь инструмента, загружая расписание при необходимости.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Arc<TradingCalendar>, FinamSdkError>` - Календарь или ошибку запроса.
+    pub async fn calendar(
+        &self,
+        symbol: impl Into<Symbol>,
+    ) -> Result<Arc<TradingCalendar>, FinamSdkError> {
+        let symbol = symbol.into();
+        let now = self.sdk.server_now();
+
+        if let Some((loaded_at, calendar)) = self.lock().get(symbol.as_str())
+            && is_fresh(*loaded_at, calendar, self.ttl, now)
+        {
+            return Ok(calendar.clone());
+        }
+
+        let calendar = Arc::new(self.sdk.trading_calendar(symbol.clone()).await?);
+        self.lock()
+            .insert(symbol.into(), (self.sdk.server_now(), calendar.clone()));
+
+        Ok(calendar)
+    }
+
+    /// Проверяет, идут ли сейчас торги инструментом.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    pub async fn is_open(&self, symbol: impl Into<Symbol>) -> Result<bool, FinamSdkError> {
+        Ok(self.calendar(symbol).await?.is_open(self.sdk.server_now()))
+    }
+
+    /// Ожидает начала торгов инструментом.
+    ///
+    /// Возвращается сразу, если торги уже идут.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Успех или ошибку, если в расписании нет будущих торговых сессий.
+    pub async fn wait_open(&self, symbol: impl Into<Symbol>) -> Result<(), FinamSdkError> {
+        let symbol = symbol.into();
+
+        loop {
+            let now = self.sdk.server_now();
+            let calendar = self.calendar(&symbol).await?;
+            let next_open = calendar.next_open(now).ok_or_else(|| {
+                FinamSdkError::InvalidArgument(format!("no upcoming trading sessions for {symbol}"))
+            })?;
+
+            let Ok(delay) = next_open.duration_since(now) else {
+                return Ok(());
+            };
+            if delay.is_zero() {
+                return Ok(());
+            }
+
+            // The schedule may change, so long waits wake up to refresh it
+            tokio::time::sleep(delay.min(self.ttl)).await;
+        }
+    }
+
+    /// Удаляет все расписания из кэша.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CalendarCache> {
+        self.calendars
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl FinamSdk {
+    /// Загружает торговый календарь инструмента.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<TradingCalendar, FinamSdkError>` - Календарь или ошибку запроса.
+    pub async fn trading_calendar(
+        &self,
+        symbol: impl Into<Symbol>,
+    ) -> Result<TradingCalendar, FinamSdkError> {
+        let schedule = self
+            .assets()
+            .schedule(ScheduleRequest::new(symbol))
+            .await?
+            .into_inner();
+
+        Ok(TradingCalendar::from_schedule(&schedule))
+    }
+}
+
+fn is_fresh(
+    loaded_at: SystemTime,
+    calendar: &TradingCalendar,
+    ttl: Duration,
+    now: SystemTime,
+) -> bool {
+    now.duration_since(loaded_at).is_ok_and(|age| age < ttl)
+        && calendar.covered_until().is_some_and(|end| now < end)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use prost_types::Timestamp;
+
+    use super::*;
+    use crate::proto::google::r#type::Interval;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn session(kind: &str, start: u64, end: u64) -> schedule_response::Sessions {
+        schedule_response::Sessions {
+            r#type: kind.to_string(),
+            interval: Some(Interval {
+                start_time: Some(Timestamp::from(at(start))),
+                end_time: Some(Timestamp::from(at(end))),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_session_kind_parsing() {
+        assert_eq!(SessionKind::parse("early_trading"), SessionKind::PreOpen);
+        assert_eq!(SessionKind::parse("CORE_TRADING"), SessionKind::Main);
+        assert_eq!(SessionKind::parse("LATE_TRADING"), SessionKind::Evening);
+        assert_eq!(SessionKind::parse("WEEKEND_TRADING"), SessionKind::Weekend);
+        assert_eq!(
+            SessionKind::parse("INTRADAY_CLEARING"),
+            SessionKind::Clearing
+        );
+        assert!(!SessionKind::parse("CLOSING_AUCTION").is_open());
+        assert!(SessionKind::parse("DISCRETE_TRADING").is_open());
+        assert!(!SessionKind::parse("UNKNOWN").is_open());
+    }
+
+    #[test]
+    fn test_trading_calendar_queries() {
+        let calendar = TradingCalendar::from_schedule(&ScheduleResponse {
+            symbol: "SBER@MISX".to_string(),
+            sessions: vec![
+                session("CLOSING_AUCTION", 3_000, 3_100),
+                session("OPENING_AUCTION", 900, 1_000),
+                session("EARLY_TRADING", 500, 900),
+                session("CORE_TRADING", 1_000, 2_000),
+                session("CLEARING", 2_000, 2_100),
+                session("CORE_TRADING", 2_100, 3_000),
+                session("LATE_TRADING", 3_100, 4_000),
+            ],
+        });
+
+        assert_eq!(calendar.sessions()[0].kind, SessionKind::PreOpen);
+        assert_eq!(
+            calendar.current_session(at(2_050)).unwrap().kind,
+            SessionKind::Clearing
+        );
+        assert!(calendar.is_open(at(1_500)));
+        assert!(!calendar.is_open(at(950)));
+        assert!(!calendar.is_open(at(4_000)));
+
+        assert_eq!(calendar.next_open(at(100)), Some(at(500)));
+        assert_eq!(calendar.next_open(at(950)), Some(at(1_000)));
+        assert_eq!(calendar.next_open(at(1_500)), Some(at(1_500)));
+        assert_eq!(calendar.next_open(at(4_000)), None);
+
+        assert_eq!(calendar.next_close(at(600)), Some(at(900)));
+        assert_eq!(calendar.next_close(at(1_500)), Some(at(2_000)));
+        assert_eq!(calendar.next_close(at(3_050)), Some(at(4_000)));
+        assert_eq!(calendar.covered_until(), Some(at(4_000)));
+
+        assert!(is_fresh(
+            at(0),
+            &calendar,
+            Duration::from_secs(3_600),
+            at(3_500)
+        ));
+        assert!(!is_fresh(
+            at(0),
+            &calendar,
+            Duration::from_secs(3_600),
+            at(4_000)
+        ));
+        assert!(!is_fresh(
+            at(0),
+            &calendar,
+            Duration::from_secs(60),
+            at(100)
+        ));
+    }
+}
@@ -0,0 +1,629 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use prost::Message;
+use prost_types::Timestamp;
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    proto::grpc::tradeapi::v1::assets::{
+        AllAssetsRequest, Asset, GetAssetRequest, GetAssetResponse,
+    },
+    search::{self, SearchKey, SearchMatch, SearchOptions},
+    symbol::Symbol,
+};
+
+/// Срок годности сохраненного каталога по умолчанию.
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// Содержимое файла каталога.
+#[derive(Clone, PartialEq, Message)]
+struct CatalogFile {
+    /// Время загрузки списка инструментов.
+    #[prost(message, optional, tag = "1")]
+    loaded_at: Option<Timestamp>,
+    /// Список инструментов.
+    #[prost(message, repeated, tag = "2")]
+    assets: Vec<Asset>,
+    /// Загруженные подробности об инструментах.
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<GetAssetResponse>,
+    /// Загружены ли только активные инструменты.
+    #[prost(bool, tag = "4")]
+    only_active: bool,
+    /// Аккаунт, для которого загружены подробности.
+    #[prost(string, tag = "5")]
+    account_id: String,
+}
+
+impl CatalogFile {
+    /// Проверяет, что файл сохранен с теми же параметрами и не устарел.
+    fn is_usable(&self, options: &CatalogOptions) -> bool {
+        self.only_active == options.only_active
+            && self.account_id == options.account_id
+            && !is_expired(self.loaded_at, options.ttl)
+    }
+}
+
+/// Параметры каталога инструментов.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogOptions {
+    /// Аккаунт, для которого запрашиваются подробности об инструментах.
+    pub account_id: String,
+    /// Путь к файлу каталога. `None` отключает сохранение.
+    pub path: Option<PathBuf>,
+    /// Срок, после которого сохраненный список инструментов загружается заново.
+    pub ttl: Duration,
+    /// Загружать только активные (неархивные) инструменты.
+    pub only_active: bool,
+}
+
+impl CatalogOptions {
+    /// Создает параметры со сроком годности в сутки, только для активных инструментов и без сохранения.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Аккаунт, для которого запрашиваются подробности об инструментах.
+    pub fn new(account_id: &str) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            path: None,
+            ttl: DEFAULT_TTL,
+            only_active: true,
+        }
+    }
+
+    /// Задает путь к файлу каталога.
+    ///
+    /// # Аргументы
+    ///
+    /// * `path` - Путь к файлу.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Задает срок годности сохраненного каталога.
+    ///
+    /// # Аргументы
+    ///
+    /// * `ttl` - Срок годности.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+/// Индекс инструментов каталога.
+#[derive(Debug, Default)]
+struct CatalogIndex {
+    loaded_at: Option<Timestamp>,
+    assets: Vec<Asset>,
+    by_symbol: HashMap<String, usize>,
+    by_id: HashMap<String, usize>,
+    by_ticker: HashMap<String, Vec<usize>>,
+    by_isin: HashMap<String, Vec<usize>>,
+    by_mic: HashMap<String, Vec<usize>>,
+    keys: Vec<SearchKey>,
+    details: HashMap<String, GetAssetResponse>,
+}
+
+impl CatalogIndex {
+    fn new(loaded_at: Option<Timestamp>, assets: Vec<Asset>) -> Self {
+        let mut index = Self {
+            loaded_at,
+            ..Default::default()
+        };
+
+        for (position, asset) in assets.iter().enumerate() {
+            index.by_symbol.insert(key(&asset.symbol), position);
+            index.by_id.insert(asset.id.clone(), position);
+            for (map, value) in [
+                (&mut index.by_ticker, &asset.ticker),
+                (&mut index.by_isin, &asset.isin),
+                (&mut index.by_mic, &asset.mic),
+            ] {
+                if !value.is_empty() {
+                    map.entry(key(value)).or_default().push(position);
+                }
+            }
+        }
+
+        index.keys = assets.iter().map(SearchKey::new).collect();
+        index.assets = assets;
+        index
+    }
+
+    fn collect(&self, positions: Option<&Vec<usize>>) -> Vec<Asset> {
+        positions
+            .into_iter()
+            .flatten()
+            .map(|position| self.assets[*position].clone())
+            .collect()
+    }
+
+    fn resolve(&self, positions: Option<&Vec<usize>>, mic: Option<&str>) -> Option<Symbol> {
+        positions
+            .into_iter()
+            .flatten()
+            .map(|position| &self.assets[*position])
+            .filter(|asset| mic.is_none_or(|mic| key(&asset.mic) == key(mic)))
+            .min_by_key(|asset| asset.is_archived)
+            .and_then(|asset| asset.symbol.parse().ok())
+    }
+}
+
+/// Локальный каталог инструментов.
+///
+/// Загружает полный список инструментов постранично через `all_assets` и
+/// индексирует его по символу, тикеру, ISIN, идентификатору и MIC. Подробности
+/// об инструменте загружаются через `get_asset` при первом обращении и
+/// кэшируются. Каталог сохраняется в файл и при следующем запуске читается
+/// из него, если срок годности не истек.
+#[derive(Clone, Debug)]
+pub struct InstrumentCatalog {
+    sdk: FinamSdk,
+    options: CatalogOptions,
+    index: Arc<RwLock<CatalogIndex>>,
+}
+
+impl InstrumentCatalog {
+    /// Открывает каталог из файла или загружает его с сервера.
+    ///
+    /// Поврежденный, устаревший или сохраненный с другими `only_active`
+    /// и `account_id` файл загружается заново.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    /// * `options` - Параметры каталога.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Каталог или ошибку загрузки.
+    pub async fn open(sdk: FinamSdk, options: CatalogOptions) -> Result<Self, FinamSdkError> {
+        let catalog = Self {
+            sdk,
+            options,
+            index: Arc::new(RwLock::new(CatalogIndex::default())),
+        };
+
+        match catalog.read_file().await? {
+            Some(file) if file.is_usable(&catalog.options) => {
+                let mut index = CatalogIndex::new(file.loaded_at, file.assets);
+                index.details = file
+                    .details
+                    .into_iter()
+                    .map(|details| (key(&details_symbol(&details)), details))
+                    .collect();
+                *catalog.write() = index;
+            }
+            _ => catalog.refresh().await?,
+        }
+
+        Ok(catalog)
+    }
+
+    /// Загружает список инструментов с сервера и сохраняет каталог.
+    ///
+    /// Подробности об инструментах, оставшихся в списке, сохраняются.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку загрузки.
+    pub async fn refresh(&self) -> Result<(), FinamSdkError> {
+        let assets = self
+            .sdk
+            .load_all_assets(self.options.only_active, false)
+            .await?;
+
+        {
+            let mut current = self.write();
+            let mut index = CatalogIndex::new(Some(Timestamp::from(SystemTime::now())), assets);
+            index.details = std::mem::take(&mut current.details)
+                .into_iter()
+                .filter(|(symbol, _)| index.by_symbol.contains_key(symbol))
+                .collect();
+            *current = index;
+        }
+
+        self.save().await
+    }
+
+    /// Сохраняет каталог вместе с загруженными подробностями в файл.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку записи.
+    pub async fn save(&self) -> Result<(), FinamSdkError> {
+        let Some(path) = &self.options.path else {
+            return Ok(());
+        };
+
+        let content = {
+            let index = self.read();
+            CatalogFile {
+                loaded_at: index.loaded_at,
+                assets: index.assets.clone(),
+                details: index.details.values().cloned().collect(),
+                only_active: self.options.only_active,
+                account_id: self.options.account_id.clone(),
+            }
+            .encode_to_vec()
+        };
+
+        let temporary = path.with_extension("tmp");
+        tokio::fs::write(&temporary, content).await?;
+        tokio::fs::rename(&temporary, path).await?;
+
+        Ok(())
+    }
+
+    /// Проверяет, истек ли срок годности списка инструментов.
+    pub fn is_expired(&self) -> bool {
+        is_expired(self.read().loaded_at, self.options.ttl)
+    }
+
+    /// Возвращает количество инструментов в каталоге.
+    pub fn len(&self) -> usize {
+        self.read().assets.len()
+    }
+
+    /// Проверяет, пуст ли каталог.
+    pub fn is_empty(&self) -> bool {
+        self.read().assets.is_empty()
+    }
+
+    /// Возвращает все инструменты каталога.
+    pub fn assets(&self) -> Vec<Asset> {
+        self.read().assets.clone()
+    }
+
+    /// Находит инструмент по символу `ticker@mic`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    pub fn by_symbol(&self, symbol: &str) -> Option<Asset> {
+        let index = self.read();
+        index
+            .by_symbol
+            .get(&key(symbol))
+            .map(|position| index.assets[*position].clone())
+    }
+
+    /// Находит инструмент по идентификатору.
+    ///
+    /// # Аргументы
+    ///
+    /// * `id` - Идентификатор инструмента.
+    pub fn by_id(&self, id: &str) -> Option<Asset> {
+        let index = self.read();
+        index
+            .by_id
+            .get(id)
+            .map(|position| index.assets[*position].clone())
+    }
+
+    /// Находит инструменты по тикеру на всех биржах.
+    ///
+    /// # Аргументы
+    ///
+    /// * `ticker` - Тикер инструмента.
+    pub fn by_ticker(&self, ticker: &str) -> Vec<Asset> {
+        let index = self.read();
+        index.collect(index.by_ticker.get(&key(ticker)))
+    }
+
+    /// Находит инструменты по ISIN.
+    ///
+    /// # Аргументы
+    ///
+    /// * `isin` - ISIN идентификатор инструмента.
+    pub fn by_isin(&self, isin: &str) -> Vec<Asset> {
+        let index = self.read();
+        index.collect(index.by_isin.get(&key(isin)))
+    }
+
+    /// Возвращает инструменты биржи.
+    ///
+    /// # Аргументы
+    ///
+    /// * `mic` - MIC идентификатор биржи.
+    pub fn by_mic(&self, mic: &str) -> Vec<Asset> {
+        let index = self.read();
+        index.collect(index.by_mic.get(&key(mic)))
+    }
+
+    /// Ищет инструменты по тикеру, названию или ISIN без обращения к серверу.
+    ///
+    /// Поиск нечувствителен к регистру и раскладке: `сбер` находит и тикер
+    /// `SBER`, и название «Сбербанк». Результаты отсортированы по релевантности.
+    ///
+    /// # Аргументы
+    ///
+    /// * `query` - Строка поиска.
+    pub fn search(&self, query: &str) -> Vec<SearchMatch> {
+        self.search_with(query, &SearchOptions::default())
+    }
+
+    /// Ищет инструменты с фильтрами по бирже, архивности и типу.
+    ///
+    /// # Аргументы
+    ///
+    /// * `query` - Строка поиска.
+    /// * `options` - Параметры поиска.
+    pub fn search_with(&self, query: &str, options: &SearchOptions) -> Vec<SearchMatch> {
+        let index = self.read();
+        search::rank(&index.assets, &index.keys, query, options)
+    }
+
+    /// Ищет инструменты по полному ISIN или его началу.
+    ///
+    /// # Аргументы
+    ///
+    /// * `isin` - ISIN или его начало.
+    pub fn search_by_isin(&self, isin: &str) -> Vec<SearchMatch> {
+        self.search_by_isin_with(isin, &SearchOptions::default())
+    }
+
+    /// Ищет инструменты по ISIN с фильтрами по бирже, архивности и типу.
+    ///
+    /// # Аргументы
+    ///
+    /// * `isin` - ISIN или его начало.
+    /// * `options` - Параметры поиска.
+    pub fn search_by_isin_with(&self, isin: &str, options: &SearchOptions) -> Vec<SearchMatch> {
+        let index = self.read();
+        search::rank_isin(&index.assets, &index.keys, isin, options)
+    }
+
+    /// Определяет символ инструмента по ISIN.
+    ///
+    /// Действующие инструменты предпочитаются архивным.
+    ///
+    /// # Аргументы
+    ///
+    /// * `isin` - ISIN идентификатор инструмента.
+    /// * `mic` - MIC идентификатор биржи или `None` для любой биржи.
+    pub fn resolve_isin(&self, isin: &str, mic: Option<&str>) -> Option<Symbol> {
+        let index = self.read();
+        index.resolve(index.by_isin.get(&key(isin)), mic)
+    }
+
+    /// Определяет символ инструмента по тикеру и бирже.
+    ///
+    /// Действующие инструменты предпочитаются архивным.
+    ///
+    /// # Аргументы
+    ///
+    /// * `ticker` - Тикер инструмента.
+    /// * `mic` - MIC идентификатор биржи.
+    pub fn resolve_ticker(&self, ticker: &str, mic: &str) -> Option<Symbol> {
+        let index = self.read();
+        index.resolve(index.by_ticker.get(&key(ticker)), Some(mic))
+    }
+
+    /// Возвращает подробности об инструменте, загружая их при первом обращении.
+    ///
+    /// Загруженные подробности попадают в файл при следующем [`InstrumentCatalog::save`].
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<GetAssetResponse, FinamSdkError>` - Подробности или ошибку запроса.
+    pub async fn details(
+        &self,
+        symbol: impl AsRef<str>,
+    ) -> Result<GetAssetResponse, FinamSdkError> {
+        let symbol = symbol.as_ref();
+        if let Some(details) = self.read().details.get(&key(symbol)) {
+            return Ok(details.clone());
+        }
+
+        let details = self
+            .sdk
+            .assets()
+            .get_asset(GetAssetRequest {
+                symbol: symbol.to_string(),
+                account_id: self.options.account_id.clone(),
+            })
+            .await?
+            .into_inner();
+
+        self.write().details.insert(key(symbol), details.clone());
+
+        Ok(details)
+    }
+
+    async fn read_file(&self) -> Result<Option<CatalogFile>, FinamSdkError> {
+        let Some(path) = &self.options.path else {
+            return Ok(None);
+        };
+
+        if !tokio::fs::try_exists(path).await? {
+            return Ok(None);
+        }
+
+        match CatalogFile::decode(tokio::fs::read(path).await?.as_slice()) {
+            Ok(file) => Ok(Some(file)),
+            Err(error) => {
+                log::warn!(
+                    "Instrument catalog {} is corrupted, reloading: {:?}",
+                    path.display(),
+                    error
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, CatalogIndex> {
+        self.index.read().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, CatalogIndex> {
+        self.index
+            .write()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl FinamSdk {
+    /// Загружает полный список инструментов, проходя по всем страницам `all_assets`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `only_active` - Только активные (неархивные) инструменты.
+    /// * `only_disabled` - Только неактивные (архивные) инструменты.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Vec<Asset>, FinamSdkError>` - Инструменты или ошибку запроса.
+    pub async fn load_all_assets(
+        &self,
+        only_active: bool,
+        only_disabled: bool,
+    ) -> Result<Vec<Asset>, FinamSdkError> {
+        let mut assets = Vec::new();
+        let mut cursor = 0;
+
+        loop {
+            let response = self
+                .assets()
+                .all_assets(AllAssetsRequest {
+                    cursor,
+                    only_active,
+                    only_disabled,
+                })
+                .await?
+                .into_inner();
+
+            assets.extend(response.assets);
+
+            // A repeated cursor would otherwise loop forever
+            if response.next_cursor == 0 || response.next_cursor == cursor {
+                break;
+            }
+            cursor = response.next_cursor;
+        }
+
+        Ok(assets)
+    }
+}
+
+fn is_expired(loaded_at: Option<Timestamp>, ttl: Duration) -> bool {
+    loaded_at
+        .and_then(|timestamp| SystemTime::try_from(timestamp).ok())
+        .and_then(|loaded_at| loaded_at.elapsed().ok())
+        .is_none_or(|age| age >= ttl)
+}
+
+/// Возвращает символ инструмента из подробностей: `ticker@mic`.
+fn details_symbol(details: &GetAssetResponse) -> String {
+    format!("{}@{}", details.ticker, details.mic)
+}
+
+fn key(value: &str) -> String {
+    value.trim().to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(id: &str, ticker: &str, mic: &str, isin: &str) -> Asset {
+        Asset {
+            symbol: format!("{ticker}@{mic}"),
+            id: id.to_string(),
+            ticker: ticker.to_string(),
+            mic: mic.to_string(),
+            isin: isin.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_catalog_index_lookups() {
+        let index = CatalogIndex::new(
+            None,
+            vec![
+                asset("1", "SBER", "MISX", "RU0009029540"),
+                asset("2", "SBER", "RUSX", "RU0009029540"),
+                asset("3", "GAZP", "MISX", "RU0007661625"),
+            ],
+        );
+
+        assert_eq!(index.by_symbol["SBER@RUSX"], 1);
+        assert_eq!(index.by_id["3"], 2);
+        assert_eq!(index.collect(index.by_ticker.get("SBER")).len(), 2);
+        assert_eq!(
+            index.collect(index.by_isin.get(&key("ru0009029540"))).len(),
+            2
+        );
+        assert_eq!(index.collect(index.by_mic.get("MISX"))[1].ticker, "GAZP");
+        assert!(index.collect(index.by_ticker.get("LKOH")).is_empty());
+    }
+
+    #[test]
+    fn test_catalog_index_resolve() {
+        let mut archived = asset("1", "SBER", "MISX", "RU0009029540");
+        archived.is_archived = true;
+        let index = CatalogIndex::new(
+            None,
+            vec![
+                archived,
+                asset("2", "SBER", "RUSX", "RU0009029540"),
+                asset("3", "SBER", "MISX", "RU0009029540"),
+            ],
+        );
+
+        let isin = index.by_isin.get(&key("RU0009029540"));
+        assert_eq!(index.resolve(isin, None).unwrap().mic(), "RUSX");
+        assert_eq!(
+            index.resolve(isin, Some("misx")).unwrap().to_string(),
+            "SBER@MISX"
+        );
+        assert_eq!(
+            index.resolve(index.by_ticker.get("SBER"), Some("XNYS")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_catalog_file_round_trip_and_expiry() {
+        let file = CatalogFile {
+            loaded_at: Some(Timestamp::from(SystemTime::now())),
+            assets: vec![asset("1", "SBER", "MISX", "RU0009029540")],
+            details: vec![GetAssetResponse {
+                ticker: "SBER".to_string(),
+                mic: "MISX".to_string(),
+                decimals: 2,
+                ..Default::default()
+            }],
+            only_active: true,
+            account_id: "A1".to_string(),
+        };
+
+        let decoded = CatalogFile::decode(file.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, file);
+        assert_eq!(details_symbol(&decoded.details[0]), "SBER@MISX");
+
+        assert!(!is_expired(file.loaded_at, DEFAULT_TTL));
+        assert!(is_expired(file.loaded_at, Duration::ZERO));
+        assert!(is_expired(None, DEFAULT_TTL));
+
+        let mut options = CatalogOptions::new("A1");
+        assert!(decoded.is_usable(&options));
+        options.only_active = false;
+        assert!(!decoded.is_usable(&options));
+        assert!(!decoded.is_usable(&CatalogOptions::new("A2")));
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{HashMap, HashSet};
+
+use tokio::{
+    sync::watch,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    decimal::{Decimal, opt_decimal},
+    execution::ExecutionStatus,
+    proto::grpc::tradeapi::v1::{
+        AccountTrade, Side,
+        orders::{Order, OrderState},
+    },
+    tracker::OrderTracker,
+};
+
+/// Максимальное время ожидания отмены дочерней заявки.
+const CHILD_CANCEL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Прогресс алгоритма, публикуемый по состоянию дочерних заявок.
+pub(crate) trait ChildProgress {
+    /// Возвращает статус исполнения.
+    fn status(&self) -> &ExecutionStatus;
+
+    /// Обновляет прогресс по состоянию дочерних заявок.
+    fn update(&mut self, status: ExecutionStatus, children: &ChildOrders);
+}
+
+/// Дочерняя заявка, ожидающая исполнения.
+#[derive(Clone, Debug)]
+pub(crate) struct ActiveChild {
+    pub order_id: String,
+    pub quantity: Decimal,
+    pub placed_at: Instant,
+}
+
+/// Количество и исполнение дочерней заявки.
+#[derive(Clone, Copy, Debug)]
+struct ChildFill {
+    quantity: Decimal,
+    executed: Decimal,
+}
+
+/// Дочерние заявки алгоритма исполнения и их исполнение.
+///
+/// Учитывает исполненное количество по каждой выставленной заявке и среднюю
+/// цену по сделкам. Одновременно активна не более одной дочерней заявки.
+pub(crate) struct ChildOrders {
+    sdk: FinamSdk,
+    tracker: OrderTracker,
+    account_id: String,
+    side: Side,
+    children: HashMap<String, ChildFill>,
+    active: Option<ActiveChild>,
+    trade_ids: HashSet<String>,
+    notional: Decimal,
+    traded: Decimal,
+}
+
+impl ChildOrders {
+    /// Создает пустой набор дочерних заявок.
+    pub fn new(sdk: FinamSdk, tracker: OrderTracker, account_id: &str, side: Side) -> Self {
+        Self {
+            sdk,
+            tracker,
+            account_id: account_id.to_string(),
+            side,
+            children: HashMap::new(),
+            active: None,
+            trade_ids: HashSet::new(),
+            notional: Decimal::ZERO,
+            traded: Decimal::ZERO,
+        }
+    }
+
+    /// Возвращает клиент SDK.
+    pub fn sdk(&self) -> &FinamSdk {
+        &self.sdk
+    }
+
+    /// Возвращает трекер заявок аккаунта.
+    pub fn tracker(&self) -> &OrderTracker {
+        &self.tracker
+    }
+
+    /// Возвращает сторону дочерних заявок.
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    /// Возвращает количество выставленных дочерних заявок.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Возвращает исполненное количество по всем дочерним заявкам.
+    pub fn filled(&self) -> Decimal {
+        self.children.values().map(|child| child.executed).sum()
+    }
+
+    /// Возвращает среднюю цену исполнения по сделкам.
+    pub fn average_price(&self) -> Option<Decimal> {
+        (!self.traded.is_zero()).then(|| self.notional / self.traded)
+    }
+
+    /// Возвращает активную дочернюю заявку.
+    pub fn active(&self) -> Option<&ActiveChild> {
+        self.active.as_ref()
+    }
+
+    /// Снимает отметку активной заявки, если она удовлетворяет условию.
+    pub fn take_active_if(
+        &mut self,
+        predicate: impl FnOnce(&mut ActiveChild) -> bool,
+    ) -> Option<ActiveChild> {
+        self.active.take_if(predicate)
+    }
+
+    /// Возвращает отметку активной заявки, снятую [`ChildOrders::take_active_if`].
+    pub fn restore_active(&mut self, active: ActiveChild) {
+        self.active = Some(active);
+    }
+
+    /// Выставляет дочернюю заявку и делает ее активной.
+    pub async fn place(&mut self, order: Order) -> Result<OrderState, FinamSdkError> {
+        let quantity = opt_decimal(order.quantity.as_ref())?;
+        let placed = self.sdk.place_order(order).await?;
+        self.track(placed.clone(), quantity);
+
+        Ok(placed)
+    }
+
+    /// Учитывает выставленную дочернюю заявку и делает ее активной.
+    pub fn track(&mut self, placed: OrderState, quantity: Decimal) {
+        self.children.insert(
+            placed.order_id.clone(),
+            ChildFill {
+                quantity,
+                executed: Decimal::ZERO,
+            },
+        );
+        self.active = Some(ActiveChild {
+            order_id: placed.order_id.clone(),
+            quantity,
+            placed_at: Instant::now(),
+        });
+
+        self.tracker.record(placed.clone());
+        self.on_update(&placed);
+    }
+
+    /// Задает исполненное количество заявки, замененной другой заявкой.
+    pub fn set_executed(&mut self, order_id: &str, executed: Decimal) {
+        if let Some(child) = self.children.get_mut(order_id) {
+            child.executed = executed;
+        }
+    }
+
+    /// Учитывает обновление состояния заявки.
+    ///
+    /// Возвращает `true`, если обновление относится к дочерней заявке.
+    pub fn on_update(&mut self, state: &OrderState) -> bool {
+        let Some(child) = self.children.get_mut(&state.order_id) else {
+            return false;
+        };
+
+        let status = state.status();
+        if status.is_filled() {
+            child.executed = child.quantity;
+        } else if let Ok(quantity) = opt_decimal(state.executed_quantity.as_ref()) {
+            child.executed = quantity.max(child.executed);
+        }
+
+        if status.is_final()
+            && self
+                .active
+                .as_ref()
+                .is_some_and(|active| active.order_id == state.order_id)
+        {
+            self.active = None;
+        }
+
+        true
+    }
+
+    /// Перечитывает состояния дочерних заявок из трекера после пропуска обновлений.
+    pub fn resync(&mut self) {
+        let states: Vec<_> = self
+            .children
+            .keys()
+            .filter_map(|id| self.tracker.get(id))
+            .collect();
+
+        for state in states {
+            self.on_update(&state);
+        }
+    }
+
+    /// Учитывает сделку по дочерней заявке в средней цене исполнения.
+    pub fn on_trade(&mut self, trade: &AccountTrade) {
+        if !self.children.contains_key(&trade.order_id)
+            || !self.trade_ids.insert(trade.trade_id.clone())
+        {
+            return;
+        }
+
+        match (
+            opt_decimal(trade.price.as_ref()),
+            opt_decimal(trade.size.as_ref()),
+        ) {
+            (Ok(price), Ok(size)) => {
+                self.notional += price * size;
+                self.traded += size;
+            }
+            (Err(error), _) | (_, Err(error)) => {
+                log::warn!("Skipping trade {}: {:?}", trade.trade_id, error);
+            }
+        }
+    }
+
+    /// Отменяет активную дочернюю заявку и дожидается ее конечного состояния.
+    ///
+    /// Исполнение заявки до отмены учитывается.
+    pub async fn cancel_active(&mut self) -> Result<(), FinamSdkError> {
+        let Some(active) = self.active.take() else {
+            return Ok(());
+        };
+
+        let state = match self
+            .sdk
+            .cancel_order(&self.account_id, &active.order_id)
+            .await
+        {
+            Ok(state) => state,
+            // The order may have been filled or canceled concurrently
+            Err(error) => match self.tracker.get(&active.order_id) {
+                Some(state) if state.status().is_final() => state,
+                _ => return Err(error),
+            },
+        };
+        self.tracker.record(state);
+
+        let state = self
+            .tracker
+            .wait_for_final(&active.order_id, CHILD_CANCEL_TIMEOUT)
+            .await?;
+        self.on_update(&state);
+
+        Ok(())
+    }
+
+    /// Публикует прогресс с заданным статусом.
+    pub fn publish<P: ChildProgress>(&self, progress: &watch::Sender<P>, status: ExecutionStatus) {
+        progress.send_modify(|progress| progress.update(status, self));
+    }
+}
+
+/// Ожидает отмены алгоритма пользователем.
+pub(crate) async fn wait_canceled(cancel: &mut watch::Receiver<bool>) {
+    // A dropped sender means every handle is gone, which also stops the algorithm
+    let _ = cancel.wait_for(|canceled| *canceled).await;
+}
+
+/// Ожидает публикации итогового прогресса алгоритма.
+pub(crate) async fn wait_finished<P: ChildProgress + Clone>(progress: &watch::Receiver<P>) -> P {
+    let mut progress = progress.clone();
+    // The sender is dropped only after the final status is published
+    let _ = progress
+        .wait_for(|progress| *progress.status() != ExecutionStatus::Running)
+        .await;
+    progress.borrow().clone()
+}
@@ -0,0 +1,307 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use tokio::{sync::oneshot, time::Instant};
+
+use crate::{
+    FinamAssetsServiceClient, FinamSdk, FinamSdkError, ShutdownGuard,
+    proto::grpc::tradeapi::v1::assets::ClockRequest,
+};
+
+/// Количество последних замеров, из которых выбирается оценка смещения.
+const FILTER_SIZE: usize = 8;
+
+/// Количество замеров при первой синхронизации.
+const INITIAL_SAMPLES: usize = 4;
+
+/// Замер смещения часов сервера.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockSample {
+    /// Смещение часов сервера относительно локальных в наносекундах (`server - local`).
+    pub offset_nanos: i64,
+    /// Время прохождения запроса туда и обратно.
+    pub round_trip: Duration,
+}
+
+impl ClockSample {
+    /// Вычисляет замер по времени отправки запроса и ответу сервера.
+    ///
+    /// Как и в NTP, считается, что сервер зафиксировал время в середине
+    /// интервала между отправкой запроса и получением ответа.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sent_at` - Локальное время отправки запроса.
+    /// * `round_trip` - Время прохождения запроса туда и обратно.
+    /// * `server_time` - Время сервера из ответа.
+    pub fn new(sent_at: SystemTime, round_trip: Duration, server_time: SystemTime) -> Self {
+        Self {
+            offset_nanos: signed_nanos(server_time, sent_at + round_trip / 2),
+            round_trip,
+        }
+    }
+}
+
+/// Окно последних замеров.
+#[derive(Debug, Default)]
+struct ClockFilter {
+    samples: VecDeque<ClockSample>,
+}
+
+impl ClockFilter {
+    fn push(&mut self, sample: ClockSample) {
+        if self.samples.len() == FILTER_SIZE {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Возвращает замер с наименьшим временем прохождения: его смещение
+    /// меньше всего искажено задержками сети.
+    fn estimate(&self) -> Option<ClockSample> {
+        self.samples
+            .iter()
+            .min_by_key(|sample| sample.round_trip)
+            .copied()
+    }
+}
+
+/// Оценка времени сервера по замерам метода `clock`.
+///
+/// До первого замера смещение считается нулевым, и [`ServerClock::now`]
+/// возвращает локальное время.
+#[derive(Clone, Debug, Default)]
+pub struct ServerClock {
+    filter: Arc<RwLock<ClockFilter>>,
+    /// Удерживает фоновую задачу замеров. При уничтожении последней ссылки
+    /// на часы отправляет сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Option<Arc<ShutdownGuard>>,
+}
+
+impl ServerClock {
+    /// Создает часы без замеров.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Создает часы и запускает фоновые замеры.
+    ///
+    /// Сразу выполняется несколько замеров подряд, затем по одному с интервалом `interval`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `assets` - Клиент сервиса инструментов.
+    /// * `interval` - Интервал между замерами.
+    pub fn start(assets: FinamAssetsServiceClient, interval: Duration) -> Self {
+        let (shutdown_sender, mut shutdown_receiver) = oneshot::channel();
+        let clock = Self {
+            filter: Arc::default(),
+            shutdown_guard: Some(Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            })),
+        };
+
+        let sampling = Self {
+            filter: clock.filter.clone(),
+            shutdown_guard: None,
+        };
+        tokio::spawn(async move {
+            let mut assets = assets;
+            let mut pending = INITIAL_SAMPLES;
+
+            loop {
+                if let Err(error) = sampling.sample(&mut assets).await {
+                    log::warn!("Failed to sample server clock: {:?}", error);
+                }
+
+                let delay = if pending > 1 {
+                    pending -= 1;
+                    Duration::ZERO
+                } else {
+                    interval
+                };
+
+                tokio::select! {
+                    _ = &mut shutdown_receiver => {
+                        log::info!("Server clock task shutting down");
+                        return;
+                    }
+                    _ = tokio::time::sleep(delay) => {}
+                }
+            }
+        });
+
+        clock
+    }
+
+    /// Выполняет один замер и добавляет его в оценку.
+    ///
+    /// # Аргументы
+    ///
+    /// * `assets` - Клиент сервиса инструментов.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<ClockSample, FinamSdkError>` - Замер или ошибку запроса.
+    pub async fn sample(
+        &self,
+        assets: &mut FinamAssetsServiceClient,
+    ) -> Result<ClockSample, FinamSdkError> {
+        let sent_at = SystemTime::now();
+        let started = Instant::now();
+        let response = assets.clock(ClockRequest {}).await?.into_inner();
+        let round_trip = started.elapsed();
+
+        let server_time = response
+            .timestamp
+            .and_then(|timestamp| SystemTime::try_from(timestamp).ok())
+            .ok_or_else(|| {
+                FinamSdkError::InvalidArgument("clock response has no timestamp".to_string())
+            })?;
+
+        let sample = ClockSample::new(sent_at, round_trip, server_time);
+        self.record(sample);
+
+        Ok(sample)
+    }
+
+    /// Добавляет замер в оценку.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sample` - Замер.
+    pub fn record(&self, sample: ClockSample) {
+        self.filter
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(sample);
+    }
+
+    /// Возвращает текущую оценку: замер с наименьшим временем прохождения среди последних.
+    pub fn estimate(&self) -> Option<ClockSample> {
+        self.filter
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .estimate()
+    }
+
+    /// Проверяет, есть ли хотя бы один замер.
+    pub fn is_synced(&self) -> bool {
+        self.estimate().is_some()
+    }
+
+    /// Возвращает смещение часов сервера относительно локальных в наносекундах.
+    pub fn offset_nanos(&self) -> i64 {
+        self.estimate().map_or(0, |sample| sample.offset_nanos)
+    }
+
+    /// Возвращает оценку текущего времени сервера.
+    pub fn now(&self) -> SystemTime {
+        self.to_server(SystemTime::now())
+    }
+
+    /// Переводит локальное время во время сервера.
+    ///
+    /// # Аргументы
+    ///
+    /// * `local` - Локальное время.
+    pub fn to_server(&self, local: SystemTime) -> SystemTime {
+        shift(local, self.offset_nanos())
+    }
+
+    /// Переводит время сервера в локальное, например для ожидания до момента по расписанию.
+    ///
+    /// # Аргументы
+    ///
+    /// * `server` - Время сервера.
+    pub fn to_local(&self, server: SystemTime) -> SystemTime {
+        shift(server, self.offset_nanos().saturating_neg())
+    }
+}
+
+impl FinamSdk {
+    /// Возвращает часы сервера, используемые SDK для проверок времени.
+    pub fn clock(&self) -> &ServerClock {
+        &self.clock
+    }
+
+    /// Возвращает оценку текущего времени сервера.
+    ///
+    /// Без синхронизации совпадает с локальным временем.
+    pub fn server_now(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    /// Выполняет замер смещения часов сервера.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<ClockSample, FinamSdkError>` - Замер или ошибку запроса.
+    pub async fn sync_clock(&self) -> Result<ClockSample, FinamSdkError> {
+        self.clock.sample(&mut self.assets()).await
+    }
+}
+
+fn signed_nanos(a: SystemTime, b: SystemTime) -> i64 {
+    match a.duration_since(b) {
+        Ok(duration) => i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX),
+        Err(error) => i64::try_from(error.duration().as_nanos()).map_or(i64::MIN, |nanos| -nanos),
+    }
+}
+
+fn shift(time: SystemTime, nanos: i64) -> SystemTime {
+    let duration = Duration::from_nanos(nanos.unsigned_abs());
+
+    if nanos >= 0 {
+        time + duration
+    } else {
+        time - duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    fn at(millis: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_clock_sample_offset() {
+        let sample = ClockSample::new(at(10_000), Duration::from_millis(100), at(10_550));
+        assert_eq!(sample.offset_nanos, 500_000_000);
+
+        let behind = ClockSample::new(at(10_000), Duration::from_millis(40), at(9_020));
+        assert_eq!(behind.offset_nanos, -1_000_000_000);
+    }
+
+    #[test]
+    fn test_server_clock_uses_fastest_recent_sample() {
+        let clock = ServerClock::new();
+        assert!(!clock.is_synced());
+        assert_eq!(clock.to_server(at(1_000)), at(1_000));
+
+        let sample = |offset_millis: i64, round_trip_millis: u64| ClockSample {
+            offset_nanos: offset_millis * 1_000_000,
+            round_trip: Duration::from_millis(round_trip_millis),
+        };
+
+        clock.record(sample(-200, 10));
+        clock.record(sample(300, 80));
+        assert_eq!(clock.offset_nanos(), -200_000_000);
+        assert_eq!(clock.to_server(at(1_000)), at(800));
+        assert_eq!(clock.to_local(at(800)), at(1_000));
+
+        for _ in 0..FILTER_SIZE {
+            clock.record(sample(50, 20));
+        }
+        assert_eq!(clock.estimate(), Some(sample(50, 20)));
+    }
+}
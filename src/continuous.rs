@@ -0,0 +1,784 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::future::try_join_all;
+use prost_types::Timestamp;
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    catalog::InstrumentCatalog,
+    decimal::{Decimal, from_decimal, opt_decimal},
+    proto::{
+        google::r#type::{Date, Decimal as ProtoDecimal, Interval},
+        grpc::tradeapi::v1::{
+            assets::{GetAssetResponse, get_asset_response::AssetDetails},
+            marketdata::{Bar, BarsRequest, Quote, QuoteRequest, TimeFrame},
+        },
+    },
+    symbol::Symbol,
+    util::{SECONDS_PER_DAY, days_from_date},
+};
+
+/// Месячные коды фьючерсов: январь — `F`, декабрь — `Z`.
+const MONTH_CODES: &str = "FGHJKMNQUVXZ";
+
+/// Фьючерсный контракт серии.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuturesContract {
+    /// Символ контракта, например `SiM5@RTSX`.
+    pub symbol: Symbol,
+    /// Тикер контракта.
+    pub ticker: String,
+    /// Дата и время экспирации.
+    pub expiration: SystemTime,
+}
+
+impl FuturesContract {
+    /// Возвращает момент, до которого контракт считается основным при переходе
+    /// за `days` дней до экспирации.
+    ///
+    /// # Аргументы
+    ///
+    /// * `days` - Количество дней до экспирации.
+    pub fn roll_deadline(&self, days: u32) -> SystemTime {
+        self.expiration
+            .checked_sub(Duration::from_secs(SECONDS_PER_DAY * u64::from(days)))
+            .unwrap_or(UNIX_EPOCH)
+    }
+}
+
+/// Правило перехода на следующий контракт.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RollRule {
+    /// Переход за заданное количество дней до экспирации.
+    DaysBeforeExpiry(u32),
+    /// Переход, когда дневной объем следующего контракта превысил объем текущего,
+    /// но не позже чем за заданное количество дней до экспирации.
+    Volume {
+        /// Крайний срок перехода в днях до экспирации.
+        days_before_expiry: u32,
+    },
+    /// Переход, когда открытый интерес следующего контракта превысил интерес
+    /// текущего, но не позже чем за заданное количество дней до экспирации.
+    ///
+    /// Свечи не содержат открытого интереса, поэтому при склейке истории
+    /// используется только крайний срок.
+    OpenInterest {
+        /// Крайний срок перехода в днях до экспирации.
+        days_before_expiry: u32,
+    },
+}
+
+impl Default for RollRule {
+    fn default() -> Self {
+        Self::DaysBeforeExpiry(5)
+    }
+}
+
+impl RollRule {
+    /// Возвращает крайний срок перехода в днях до экспирации.
+    pub fn days_before_expiry(&self) -> u32 {
+        match *self {
+            Self::DaysBeforeExpiry(days)
+            | Self::Volume {
+                days_before_expiry: days,
+            }
+            | Self::OpenInterest {
+                days_before_expiry: days,
+            } => days,
+        }
+    }
+}
+
+/// Способ устранения разрывов цены при склейке контрактов.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Adjustment {
+    /// Цены не корректируются.
+    None,
+    /// Цены предыдущих контрактов сдвигаются на разницу цен в момент перехода.
+    #[default]
+    BackAdjusted,
+    /// Цены предыдущих контрактов умножаются на отношение цен в момент перехода.
+    RatioAdjusted,
+}
+
+/// Переход с одного контракта на другой.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Roll {
+    /// Момент перехода.
+    pub at: SystemTime,
+    /// Символ контракта, с которого выполняется переход.
+    pub from: Symbol,
+    /// Символ контракта, на который выполняется переход.
+    pub to: Symbol,
+}
+
+/// Свечи одного контракта серии.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractBars {
+    /// Контракт.
+    pub contract: FuturesContract,
+    /// Свечи, отсортированные по времени.
+    pub bars: Vec<Bar>,
+}
+
+/// Серия фьючерсных контрактов на один базовый актив, упорядоченная по экспирации.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuturesChain {
+    root: String,
+    contracts: Vec<FuturesContract>,
+}
+
+impl FuturesChain {
+    /// Создает серию из контрактов.
+    ///
+    /// # Аргументы
+    ///
+    /// * `root` - Код базового актива, например `Si`.
+    /// * `contracts` - Контракты в любом порядке.
+    pub fn new(root: &str, mut contracts: Vec<FuturesContract>) -> Self {
+        contracts.sort_by(|a, b| {
+            a.expiration
+                .cmp(&b.expiration)
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        contracts.dedup_by(|a, b| a.symbol == b.symbol);
+
+        Self {
+            root: root.to_string(),
+            contracts,
+        }
+    }
+
+    /// Находит контракты серии в каталоге инструментов.
+    ///
+    /// Контрактами серии считаются инструменты с тикером вида `<root><месяц><год>`,
+    /// например `SiM5`. Даты экспирации загружаются через [`InstrumentCatalog::details`].
+    ///
+    /// # Аргументы
+    ///
+    /// * `catalog` - Каталог инструментов.
+    /// * `root` - Код базового актива, например `Si`.
+    /// * `mic` - MIC идентификатор биржи или `None` для любой биржи.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Серию или ошибку загрузки подробностей.
+    pub async fn discover(
+        catalog: &InstrumentCatalog,
+        root: &str,
+        mic: Option<&str>,
+    ) -> Result<Self, FinamSdkError> {
+        let assets: Vec<_> = catalog
+            .assets()
+            .into_iter()
+            .filter(|asset| is_contract_of(&asset.ticker, root))
+            .filter(|asset| mic.is_none_or(|mic| asset.mic.eq_ignore_ascii_case(mic)))
+            .collect();
+
+        let details =
+            try_join_all(assets.iter().map(|asset| catalog.details(&asset.symbol))).await?;
+
+        let contracts = assets
+            .into_iter()
+            .zip(details)
+            .filter_map(|(asset, details)| {
+                Some(FuturesContract {
+                    expiration: expiration_of(&details)?,
+                    symbol: asset.symbol.parse().ok()?,
+                    ticker: asset.ticker,
+                })
+            })
+            .collect();
+
+        Ok(Self::new(root, contracts))
+    }
+
+    /// Возвращает код базового актива.
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Возвращает контракты, упорядоченные по экспирации.
+    pub fn contracts(&self) -> &[FuturesContract] {
+        &self.contracts
+    }
+
+    /// Возвращает контракты, не исполненные к моменту `at`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `at` - Момент времени.
+    pub fn active(&self, at: SystemTime) -> &[FuturesContract] {
+        let first = self
+            .contracts
+            .partition_point(|contract| contract.expiration <= at);
+        &self.contracts[first..]
+    }
+
+    /// Выбирает основной контракт на момент `at`.
+    ///
+    /// Для правил по объему и открытому интересу используются котировки
+    /// ближайшего и следующего контрактов. Отсутствующая котировка считается нулевой.
+    ///
+    /// # Аргументы
+    ///
+    /// * `at` - Момент времени.
+    /// * `rule` - Правило перехода.
+    /// * `quotes` - Котировки контрактов.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Option<&FuturesContract>, FinamSdkError>` - Основной контракт, `None`, если все контракты исполнены, или ошибку разбора котировок.
+    pub fn front_month(
+        &self,
+        at: SystemTime,
+        rule: RollRule,
+        quotes: &[Quote],
+    ) -> Result<Option<&FuturesContract>, FinamSdkError> {
+        let active = self.active(at);
+        let days = rule.days_before_expiry();
+        let Some(front) = active
+            .iter()
+            .position(|contract| at < contract.roll_deadline(days))
+            .or_else(|| active.len().checked_sub(1))
+        else {
+            return Ok(None);
+        };
+
+        let Some(next) = active.get(front + 1) else {
+            return Ok(active.get(front));
+        };
+
+        let metric = |contract: &FuturesContract| {
+            let quote = quotes
+                .iter()
+                .find(|quote| quote.symbol == contract.symbol.as_str());
+            match rule {
+                RollRule::DaysBeforeExpiry(_) => Ok(Decimal::ZERO),
+                RollRule::Volume { .. } => {
+                    opt_decimal(quote.and_then(|quote| quote.volume.as_ref()))
+                }
+                RollRule::OpenInterest { .. } => {
+                    opt_decimal(quote.and_then(|quote| quote.open_interest.as_ref()))
+                }
+            }
+        };
+
+        if metric(next)? > metric(&active[front])? {
+            Ok(Some(next))
+        } else {
+            Ok(Some(&active[front]))
+        }
+    }
+
+    /// Возвращает календарь переходов при переходе за `days` дней до экспирации.
+    ///
+    /// # Аргументы
+    ///
+    /// * `days` - Количество дней до экспирации.
+    pub fn roll_calendar(&self, days: u32) -> Vec<Roll> {
+        self.contracts
+            .windows(2)
+            .map(|pair| Roll {
+                at: pair[0].roll_deadline(days),
+                from: pair[0].symbol.clone(),
+                to: pair[1].symbol.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Проверяет, является ли тикер контрактом серии вида `<root><месяц><год>`.
+///
+/// # Аргументы
+///
+/// * `ticker` - Тикер инструмента, например `SiM5`.
+/// * `root` - Код базового актива, например `Si`.
+pub fn is_contract_of(ticker: &str, root: &str) -> bool {
+    let Some(suffix) = ticker
+        .get(..root.len())
+        .filter(|prefix| !root.is_empty() && prefix.eq_ignore_ascii_case(root))
+        .and_then(|_| ticker.get(root.len()..))
+    else {
+        return false;
+    };
+
+    let mut chars = suffix.chars();
+    let month = chars
+        .next()
+        .is_some_and(|month| MONTH_CODES.contains(month));
+    let year = chars.as_str();
+
+    month && (1..=2).contains(&year.len()) && year.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Определяет моменты переходов между соседними контрактами по их свечам.
+///
+/// Для правила по объему переход выполняется со следующей свечи после той,
+/// в которой объем следующего контракта превысил объем текущего, но не позже
+/// крайнего срока. Остальные правила используют только крайний срок.
+///
+/// # Аргументы
+///
+/// * `series` - Свечи контрактов, упорядоченных по экспирации.
+/// * `rule` - Правило перехода.
+///
+/// # Возвращает
+///
+/// * `Result<Vec<SystemTime>, FinamSdkError>` - Моменты переходов (на один меньше, чем контрактов) или ошибку разбора свечей.
+pub fn roll_times(
+    series: &[ContractBars],
+    rule: RollRule,
+) -> Result<Vec<SystemTime>, FinamSdkError> {
+    let mut rolls: Vec<SystemTime> = Vec::new();
+
+    for pair in series.windows(2) {
+        let (current, next) = (&pair[0], &pair[1]);
+        let deadline = current.contract.roll_deadline(rule.days_before_expiry());
+        let mut roll = deadline;
+
+        if let RollRule::Volume { .. } = rule {
+            let mut crossed = false;
+
+            for bar in &next.bars {
+                let Some(at) = bar_time(bar) else { continue };
+                if at >= deadline {
+                    break;
+                }
+                if crossed {
+                    roll = at;
+                    break;
+                }
+
+                let current_volume = current
+                    .bars
+                    .iter()
+                    .find(|candidate| bar_time(candidate) == Some(at))
+                    .map(|candidate| opt_decimal(candidate.volume.as_ref()))
+                    .transpose()?
+                    .unwrap_or_default();
+                crossed = opt_decimal(bar.volume.as_ref())? > current_volume;
+            }
+        }
+
+        if let Some(previous) = rolls.last() {
+            roll = roll.max(*previous);
+        }
+        rolls.push(roll);
+    }
+
+    Ok(rolls)
+}
+
+/// Склеивает свечи контрактов в непрерывный ряд.
+///
+/// Каждая свеча берется из контракта, основного на момент ее начала. Разрыв
+/// цены в момент перехода измеряется по последней свече старого контракта до
+/// перехода и свече нового контракта в то же время (или последней до перехода).
+///
+/// # Аргументы
+///
+/// * `series` - Свечи контрактов, упорядоченных по экспирации.
+/// * `rolls` - Моменты переходов, см. [`roll_times`].
+/// * `adjustment` - Способ корректировки цен.
+///
+/// # Возвращает
+///
+/// * `Result<Vec<Bar>, FinamSdkError>` - Непрерывный ряд или ошибку разбора свечей.
+pub fn stitch(
+    series: &[ContractBars],
+    rolls: &[SystemTime],
+    adjustment: Adjustment,
+) -> Result<Vec<Bar>, FinamSdkError> {
+    if rolls.len() + 1 != series.len() && !series.is_empty() {
+        return Err(FinamSdkError::InvalidArgument(format!(
+            "{} contracts require {} roll times, got {}",
+            series.len(),
+            series.len() - 1,
+            rolls.len()
+        )));
+    }
+
+    let mut gaps = Vec::with_capacity(rolls.len());
+    for (pair, roll) in series.windows(2).zip(rolls) {
+        gaps.push(roll_gap(&pair[0].bars, &pair[1].bars, *roll)?);
+    }
+
+    let mut continuous = Vec::new();
+    for (index, contract) in series.iter().enumerate() {
+        let later = &gaps[index..];
+        let shift: Decimal = later.iter().flatten().map(|(old, new)| new - old).sum();
+        let ratio: Decimal = later
+            .iter()
+            .flatten()
+            .filter(|(old, _)| !old.is_zero())
+            .map(|(old, new)| new / old)
+            .product();
+
+        for bar in &contract.bars {
+            let Some(at) = bar_time(bar) else { continue };
+            let after_start = index == 0 || at >= rolls[index - 1];
+            let before_end = rolls.get(index).is_none_or(|roll| at < *roll);
+            if !(after_start && before_end) {
+                continue;
+            }
+
+            let adjust = |price: Option<&ProtoDecimal>| -> Result<_, FinamSdkError> {
+                let Some(price) = price else { return Ok(None) };
+                let value = opt_decimal(Some(price))?;
+
+                Ok(Some(from_decimal(match adjustment {
+                    Adjustment::None => value,
+                    Adjustment::BackAdjusted => value + shift,
+                    Adjustment::RatioAdjusted => value * ratio,
+                })))
+            };
+
+            continuous.push(Bar {
+                timestamp: bar.timestamp,
+                open: adjust(bar.open.as_ref())?,
+                high: adjust(bar.high.as_ref())?,
+                low: adjust(bar.low.as_ref())?,
+                close: adjust(bar.close.as_ref())?,
+                volume: bar.volume.clone(),
+            });
+        }
+    }
+
+    Ok(continuous)
+}
+
+impl FinamSdk {
+    /// Выбирает текущий основной контракт серии.
+    ///
+    /// Для правил по объему и открытому интересу запрашиваются котировки
+    /// двух ближайших контрактов.
+    ///
+    /// # Аргументы
+    ///
+    /// * `chain` - Серия контрактов.
+    /// * `rule` - Правило перехода.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Option<FuturesContract>, FinamSdkError>` - Основной контракт, `None`, если все контракты исполнены, или ошибку запроса.
+    pub async fn front_month(
+        &self,
+        chain: &FuturesChain,
+        rule: RollRule,
+    ) -> Result<Option<FuturesContract>, FinamSdkError> {
+        let now = self.server_now();
+        let quotes = match rule {
+            RollRule::DaysBeforeExpiry(_) => Vec::new(),
+            RollRule::Volume { .. } | RollRule::OpenInterest { .. } => {
+                let requests = chain.active(now).iter().take(2).map(|contract| async {
+                    Ok::<_, FinamSdkError>(
+                        self.market_data()
+                            .last_quote(QuoteRequest::new(&contract.symbol))
+                            .await?
+                            .into_inner()
+                            .quote,
+                    )
+                });
+                try_join_all(requests)
+                    .await?
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+        };
+
+        Ok(chain.front_month(now, rule, &quotes)?.cloned())
+    }
+
+    /// Загружает непрерывный ряд свечей серии.
+    ///
+    /// Свечи запрашиваются по каждому контракту, не исполненному к началу
+    /// периода, одним запросом `bars`, поэтому период должен укладываться в
+    /// ограничения глубины истории для таймфрейма.
+    ///
+    /// # Аргументы
+    ///
+    /// * `chain` - Серия контрактов.
+    /// * `timeframe` - Таймфрейм свечей.
+    /// * `interval` - Период.
+    /// * `rule` - Правило перехода.
+    /// * `adjustment` - Способ корректировки цен.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Vec<Bar>, FinamSdkError>` - Непрерывный ряд или ошибку запроса.
+    pub async fn continuous_bars(
+        &self,
+        chain: &FuturesChain,
+        timeframe: TimeFrame,
+        interval: Interval,
+        rule: RollRule,
+        adjustment: Adjustment,
+    ) -> Result<Vec<Bar>, FinamSdkError> {
+        let (Some(start), Some(end)) = (interval.start_time, interval.end_time) else {
+            return Err(FinamSdkError::InvalidArgument(
+                "continuous bars require a bounded interval".to_string(),
+            ));
+        };
+        let start_at = SystemTime::try_from(start)
+            .map_err(|_| FinamSdkError::InvalidArgument("invalid interval start".to_string()))?;
+        let end_at = SystemTime::try_from(end)
+            .map_err(|_| FinamSdkError::InvalidArgument("invalid interval end".to_string()))?;
+
+        let active = chain.active(start_at);
+        let last = active
+            .iter()
+            .position(|contract| contract.expiration >= end_at)
+            .map_or(active.len(), |position| position + 1);
+        let contracts = &active[..last];
+
+        let requests = contracts.iter().map(|contract| async move {
+            let request = BarsRequest::new(
+                &contract.symbol,
+                timeframe,
+                Interval {
+                    start_time: Some(start),
+                    end_time: Some(Timestamp::from(contract.expiration.min(end_at))),
+                },
+            );
+            let bars = self.market_data().bars(request).await?.into_inner().bars;
+
+            Ok::<_, FinamSdkError>(ContractBars {
+                contract: contract.clone(),
+                bars,
+            })
+        });
+        let series = try_join_all(requests).await?;
+
+        let rolls = roll_times(&series, rule)?;
+        stitch(&series, &rolls, adjustment)
+    }
+}
+
+/// Возвращает закрытие старого и нового контрактов в момент перехода.
+fn roll_gap(
+    old: &[Bar],
+    new: &[Bar],
+    roll: SystemTime,
+) -> Result<Option<(Decimal, Decimal)>, FinamSdkError> {
+    let before = |bars: &[Bar]| {
+        bars.iter()
+            .rfind(|bar| bar_time(bar).is_some_and(|at| at < roll))
+            .cloned()
+    };
+
+    let Some(old_bar) = before(old) else {
+        return Ok(None);
+    };
+    let Some(new_bar) = new
+        .iter()
+        .find(|bar| bar.timestamp == old_bar.timestamp)
+        .cloned()
+        .or_else(|| before(new))
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some((
+        opt_decimal(old_bar.close.as_ref())?,
+        opt_decimal(new_bar.close.as_ref())?,
+    )))
+}
+
+fn bar_time(bar: &Bar) -> Option<SystemTime> {
+    SystemTime::try_from(bar.timestamp?).ok()
+}
+
+/// Возвращает дату экспирации фьючерса из подробностей об инструменте.
+fn expiration_of(details: &GetAssetResponse) -> Option<SystemTime> {
+    if let Some(AssetDetails::FutureDetails(future)) = &details.asset_details
+        && let Some(expiration) = future.expiration_date
+    {
+        return SystemTime::try_from(expiration).ok();
+    }
+
+    #[allow(deprecated)]
+    let date = details.expiration_date.as_ref()?;
+
+    date_to_time(date)
+}
+
+/// Переводит календарную дату в начало суток UTC.
+fn date_to_time(date: &Date) -> Option<SystemTime> {
+    let days = days_from_date(date)?;
+
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(days).ok()? * SECONDS_PER_DAY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::grpc::tradeapi::v1::assets::get_asset_response::FutureDetails;
+
+    const DAY: u64 = SECONDS_PER_DAY;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn contract(ticker: &str, expiration: u64) -> FuturesContract {
+        FuturesContract {
+            symbol: Symbol::new(ticker, "RTSX").unwrap(),
+            ticker: ticker.to_string(),
+            expiration: at(expiration),
+        }
+    }
+
+    fn bar(seconds: u64, close: i64, volume: i64) -> Bar {
+        Bar {
+            timestamp: Some(Timestamp::from(at(seconds))),
+            open: Some(from_decimal(Decimal::from(close))),
+            high: Some(from_decimal(Decimal::from(close))),
+            low: Some(from_decimal(Decimal::from(close))),
+            close: Some(from_decimal(Decimal::from(close))),
+            volume: Some(from_decimal(Decimal::from(volume))),
+        }
+    }
+
+    fn closes(bars: &[Bar]) -> Vec<Decimal> {
+        bars.iter()
+            .map(|bar| opt_decimal(bar.close.as_ref()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_chain_front_month_and_roll_calendar() {
+        assert!(is_contract_of("SiM5", "Si"));
+        assert!(is_contract_of("SIZ25", "si"));
+        assert!(!is_contract_of("SiM5", "S"));
+        assert!(!is_contract_of("SBER", "SB"));
+
+        let chain = FuturesChain::new(
+            "Si",
+            vec![contract("SiU5", 60 * DAY), contract("SiM5", 30 * DAY)],
+        );
+        assert_eq!(chain.contracts()[0].ticker, "SiM5");
+        assert_eq!(chain.active(at(30 * DAY)).len(), 1);
+
+        let rule = RollRule::DaysBeforeExpiry(5);
+        let front = |seconds, rule, quotes: &[Quote]| {
+            chain
+                .front_month(at(seconds), rule, quotes)
+                .unwrap()
+                .map(|contract| contract.ticker.clone())
+        };
+        assert_eq!(front(10 * DAY, rule, &[]).as_deref(), Some("SiM5"));
+        assert_eq!(front(26 * DAY, rule, &[]).as_deref(), Some("SiU5"));
+        assert_eq!(front(61 * DAY, rule, &[]), None);
+
+        let quotes = [
+            Quote {
+                symbol: "SiM5@RTSX".to_string(),
+                open_interest: Some(from_decimal(Decimal::from(100))),
+                ..Default::default()
+            },
+            Quote {
+                symbol: "SiU5@RTSX".to_string(),
+                open_interest: Some(from_decimal(Decimal::from(150))),
+                ..Default::default()
+            },
+        ];
+        let by_interest = RollRule::OpenInterest {
+            days_before_expiry: 5,
+        };
+        assert_eq!(
+            front(10 * DAY, by_interest, &quotes).as_deref(),
+            Some("SiU5")
+        );
+        assert_eq!(
+            front(
+                10 * DAY,
+                RollRule::Volume {
+                    days_before_expiry: 5
+                },
+                &quotes
+            )
+            .as_deref(),
+            Some("SiM5")
+        );
+
+        assert_eq!(
+            chain.roll_calendar(5),
+            vec![Roll {
+                at: at(25 * DAY),
+                from: "SiM5@RTSX".parse().unwrap(),
+                to: "SiU5@RTSX".parse().unwrap(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_stitch_adjusts_prices_at_rolls() {
+        let series = vec![
+            ContractBars {
+                contract: contract("SiM5", 10 * DAY),
+                bars: vec![
+                    bar(DAY, 100, 50),
+                    bar(2 * DAY, 110, 40),
+                    bar(3 * DAY, 120, 10),
+                ],
+            },
+            ContractBars {
+                contract: contract("SiU5", 20 * DAY),
+                bars: vec![
+                    bar(DAY, 104, 5),
+                    bar(2 * DAY, 121, 60),
+                    bar(3 * DAY, 132, 70),
+                ],
+            },
+        ];
+
+        let by_volume = RollRule::Volume {
+            days_before_expiry: 5,
+        };
+        assert_eq!(roll_times(&series, by_volume).unwrap(), vec![at(3 * DAY)]);
+        assert_eq!(
+            roll_times(&series, RollRule::DaysBeforeExpiry(8)).unwrap(),
+            vec![at(2 * DAY)]
+        );
+
+        let rolls = [at(3 * DAY)];
+        assert_eq!(
+            closes(&stitch(&series, &rolls, Adjustment::None).unwrap()),
+            [100, 110, 132].map(Decimal::from)
+        );
+        assert_eq!(
+            closes(&stitch(&series, &rolls, Adjustment::BackAdjusted).unwrap()),
+            [111, 121, 132].map(Decimal::from)
+        );
+        assert_eq!(
+            closes(&stitch(&series, &rolls, Adjustment::RatioAdjusted).unwrap()),
+            [110, 121, 132].map(Decimal::from)
+        );
+        assert!(stitch(&series, &[], Adjustment::None).is_err());
+    }
+
+    #[test]
+    fn test_expiration_of_prefers_future_details() {
+        let mut details = GetAssetResponse {
+            asset_details: Some(AssetDetails::FutureDetails(FutureDetails {
+                expiration_date: Some(Timestamp::from(at(7 * DAY))),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        assert_eq!(expiration_of(&details), Some(at(7 * DAY)));
+
+        details.asset_details = None;
+        #[allow(deprecated)]
+        {
+            details.expiration_date = Some(Date {
+                year: 2025,
+                month: 6,
+                day: 19,
+            });
+        }
+        assert_eq!(expiration_of(&details), Some(at(1_750_291_200)));
+    }
+}
@@ -0,0 +1,196 @@
+use std::str::FromStr;
+
+pub use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::{
+    FinamSdkError,
+    proto::{
+        google::r#type::{Decimal as ProtoDecimal, Money},
+        grpc::tradeapi::v1::assets::GetAssetResponse,
+    },
+};
+
+/// Преобразует десятичное значение API Финам в `rust_decimal::Decimal`.
+///
+/// Поддерживает экспоненциальную запись (`2.5e8`). Пустая строка считается нулем.
+///
+/// # Аргументы
+///
+/// * `value` - Десятичное значение в формате `google.type.Decimal`.
+///
+/// # Возвращает
+///
+/// * `Result<Decimal, FinamSdkError>` - Число при успешном разборе или ошибку.
+pub fn to_decimal(value: &ProtoDecimal) -> Result<Decimal, FinamSdkError> {
+    let raw = value.value.trim();
+
+    if raw.is_empty() {
+        return Ok(Decimal::ZERO);
+    }
+
+    let parsed = if raw.contains(['e', 'E']) {
+        Decimal::from_scientific(raw)
+    } else {
+        Decimal::from_str(raw)
+    };
+
+    parsed.map_err(|_| FinamSdkError::InvalidDecimal(value.value.clone()))
+}
+
+/// Преобразует необязательное десятичное поле ответа API в число.
+///
+/// Отсутствующее значение считается нулем.
+///
+/// # Аргументы
+///
+/// * `value` - Необязательное поле в формате `google.type.Decimal`.
+///
+/// # Возвращает
+///
+/// * `Result<Decimal, FinamSdkError>` - Число при успешном разборе или ошибку.
+pub fn opt_decimal(value: Option<&ProtoDecimal>) -> Result<Decimal, FinamSdkError> {
+    value.map_or(Ok(Decimal::ZERO), to_decimal)
+}
+
+/// Преобразует число в десятичное значение API Финам.
+///
+/// # Аргументы
+///
+/// * `value` - Число для передачи в запросе.
+///
+/// # Возвращает
+///
+/// * `ProtoDecimal` - Значение в формате `google.type.Decimal`.
+pub fn from_decimal(value: Decimal) -> ProtoDecimal {
+    ProtoDecimal {
+        value: value.normalize().to_string(),
+    }
+}
+
+/// Преобразует денежную сумму API Финам в число без учета валюты.
+///
+/// # Аргументы
+///
+/// * `value` - Сумма в формате `google.type.Money`.
+///
+/// # Возвращает
+///
+/// * `Decimal` - Сумма: `units + nanos / 10^9`.
+pub fn money_amount(value: &Money) -> Decimal {
+    Decimal::from(value.units) + Decimal::new(i64::from(value.nanos), 9)
+}
+
+/// Округляет цену до ближайшего кратного шагу цены.
+///
+/// # Аргументы
+///
+/// * `price` - Исходная цена.
+/// * `step` - Минимальный шаг цены. Нулевой шаг оставляет цену без изменений.
+/// * `strategy` - Способ округления.
+///
+/// # Возвращает
+///
+/// * `Decimal` - Цена, кратная шагу.
+pub fn round_to_step(price: Decimal, step: Decimal, strategy: RoundingStrategy) -> Decimal {
+    if step.is_zero() {
+        return price;
+    }
+
+    ((price / step).round_dp_with_strategy(0, strategy) * step).normalize()
+}
+
+impl GetAssetResponse {
+    /// Возвращает минимальный шаг цены инструмента: `min_step / 10^decimals`.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Decimal` - Шаг цены или ноль, если параметры не заполнены.
+    pub fn price_step(&self) -> Decimal {
+        Decimal::try_from_i128_with_scale(i128::from(self.min_step), self.decimals.max(0) as u32)
+            .unwrap_or_default()
+    }
+
+    /// Округляет цену до ближайшего шага цены инструмента.
+    ///
+    /// # Аргументы
+    ///
+    /// * `price` - Исходная цена.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Decimal` - Цена, кратная шагу цены.
+    pub fn round_price(&self, price: Decimal) -> Decimal {
+        round_to_step(
+            price,
+            self.price_step(),
+            RoundingStrategy::MidpointAwayFromZero,
+        )
+    }
+}
+
+impl TryFrom<&ProtoDecimal> for Decimal {
+    type Error = FinamSdkError;
+
+    fn try_from(value: &ProtoDecimal) -> Result<Self, Self::Error> {
+        to_decimal(value)
+    }
+}
+
+impl From<Decimal> for ProtoDecimal {
+    fn from(value: Decimal) -> Self {
+        from_decimal(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proto(value: &str) -> ProtoDecimal {
+        ProtoDecimal {
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_to_decimal_parses_plain_and_scientific() {
+        assert_eq!(to_decimal(&proto("2.5")).unwrap(), Decimal::new(25, 1));
+        assert_eq!(to_decimal(&proto("-0.75")).unwrap(), Decimal::new(-75, 2));
+        assert_eq!(to_decimal(&proto("2.5e2")).unwrap(), Decimal::new(250, 0));
+        assert_eq!(to_decimal(&proto("")).unwrap(), Decimal::ZERO);
+        assert!(to_decimal(&proto("abc")).is_err());
+    }
+
+    #[test]
+    fn test_from_decimal_normalizes_trailing_zeros() {
+        assert_eq!(from_decimal(Decimal::new(1500, 2)).value, "15");
+        assert_eq!(opt_decimal(None).unwrap(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_price_step_and_rounding() {
+        let asset = GetAssetResponse {
+            min_step: 5,
+            decimals: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(asset.price_step(), Decimal::new(5, 2));
+        assert_eq!(
+            asset.round_price(Decimal::new(10012, 2)),
+            Decimal::new(10010, 2)
+        );
+        assert_eq!(
+            asset.round_price(Decimal::new(10013, 2)),
+            Decimal::new(10015, 2)
+        );
+        assert_eq!(
+            round_to_step(
+                Decimal::new(1019, 1),
+                Decimal::ONE,
+                RoundingStrategy::ToZero
+            ),
+            Decimal::from(101)
+        );
+    }
+}
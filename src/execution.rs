@@ -0,0 +1,1022 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use futures::StreamExt;
+use prost_types::Timestamp;
+use tokio::{
+    sync::{broadcast, oneshot, watch},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    FinamSdk, FinamSdkError, ShutdownGuard,
+    child::{ChildOrders, ChildProgress, wait_canceled, wait_finished},
+    decimal::{Decimal, RoundingStrategy, from_decimal, opt_decimal, round_to_step, to_decimal},
+    proto::{
+        google::r#type::Interval,
+        grpc::tradeapi::v1::{
+            Side,
+            assets::{GetAssetRequest, ScheduleRequest, ScheduleResponse, schedule_response},
+            marketdata::{Bar, BarsRequest, QuoteRequest, TimeFrame},
+            orders::{Order, OrderState, OrderType, TimeInForce},
+        },
+    },
+    tracker::OrderTracker,
+    trading::TradeStream,
+    util::{SECONDS_PER_DAY, next_id},
+};
+
+/// Длительность интервала кривой объемов VWAP, совпадающая с таймфреймом `TimeFrame::M5`.
+const VOLUME_BUCKET: Duration = Duration::from_secs(5 * 60);
+
+/// Максимальная глубина истории для кривой объемов VWAP в днях.
+const MAX_LOOKBACK_DAYS: u32 = 30;
+
+/// Алгоритм распределения объема родительской заявки во времени.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionStyle {
+    /// Равные части через равные промежутки торгового времени.
+    Twap {
+        /// Количество частей.
+        slices: usize,
+    },
+    /// Части пропорциональны историческому внутридневному профилю объема.
+    Vwap {
+        /// Глубина истории для построения профиля объема в днях (не более 30).
+        lookback_days: u32,
+    },
+}
+
+/// Способ выставления дочерних заявок.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChildPricing {
+    /// Рыночные заявки.
+    Market,
+    /// Лимитные заявки по лучшей цене своей стороны стакана.
+    Limit {
+        /// Сдвиг цены в шагах цены в сторону встречной стороны стакана.
+        offset_ticks: u32,
+        /// Время, после которого неисполненная заявка переставляется по новой цене.
+        reprice_after: Duration,
+    },
+}
+
+/// Родительская заявка алгоритмического исполнения.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParentOrder {
+    /// Идентификатор аккаунта.
+    pub account_id: String,
+    /// Символ инструмента.
+    pub symbol: String,
+    /// Сторона заявки.
+    pub side: Side,
+    /// Общее количество в шт.
+    pub quantity: Decimal,
+    /// Начало окна исполнения.
+    pub start: SystemTime,
+    /// Окончание окна исполнения.
+    pub end: SystemTime,
+    /// Алгоритм распределения объема.
+    pub style: ExecutionStyle,
+    /// Способ выставления дочерних заявок.
+    pub pricing: ChildPricing,
+}
+
+/// Интервал торговой сессии.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TradingWindow {
+    /// Начало интервала.
+    pub start: SystemTime,
+    /// Окончание интервала.
+    pub end: SystemTime,
+}
+
+impl TradingWindow {
+    fn duration(&self) -> Duration {
+        self.end.duration_since(self.start).unwrap_or_default()
+    }
+}
+
+/// Запланированная дочерняя заявка.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Slice {
+    /// Время выставления.
+    pub at: SystemTime,
+    /// Количество в шт.
+    pub quantity: Decimal,
+}
+
+/// Внутридневной профиль объема, построенный по историческим свечам.
+///
+/// Хранит долю объема для каждого пятиминутного интервала суток (UTC).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VolumeCurve {
+    weights: Vec<Decimal>,
+}
+
+impl VolumeCurve {
+    /// Строит профиль объема по пятиминутным свечам.
+    ///
+    /// # Аргументы
+    ///
+    /// * `bars` - Исторические свечи таймфрейма `TimeFrame::M5`.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Профиль объема или ошибку разбора объема свечи.
+    pub fn from_bars(bars: &[Bar]) -> Result<Self, FinamSdkError> {
+        let mut weights = vec![Decimal::ZERO; bucket_count()];
+
+        for bar in bars {
+            let Some(at) = bar.timestamp.and_then(|ts| SystemTime::try_from(ts).ok()) else {
+                continue;
+            };
+
+            weights[bucket_of_day(at)] += opt_decimal(bar.volume.as_ref())?;
+        }
+
+        Ok(Self { weights })
+    }
+
+    /// Возвращает исторический объем интервала, в который попадает время.
+    pub fn weight_at(&self, at: SystemTime) -> Decimal {
+        self.weights[bucket_of_day(at)]
+    }
+}
+
+/// Статус алгоритмического исполнения.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    /// Исполнение продолжается.
+    Running,
+    /// Весь объем исполнен.
+    Completed,
+    /// Окно исполнения закончилось до исполнения всего объема.
+    Expired,
+    /// Исполнение отменено пользователем.
+    Canceled,
+    /// Исполнение остановлено из-за ошибки.
+    Failed(String),
+}
+
+/// Прогресс алгоритмического исполнения.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionProgress {
+    /// Статус исполнения.
+    pub status: ExecutionStatus,
+    /// Общее количество родительской заявки.
+    pub target: Decimal,
+    /// Исполненное количество.
+    pub filled: Decimal,
+    /// Средняя цена исполнения по сделкам.
+    pub average_price: Option<Decimal>,
+    /// Цена на момент запуска: середина спреда или цена последней сделки.
+    pub arrival_price: Option<Decimal>,
+    /// Проскальзывание средней цены относительно цены запуска в базисных пунктах.
+    /// Положительное значение означает исполнение хуже цены запуска.
+    pub slippage_bps: Option<Decimal>,
+    /// Количество выставленных дочерних заявок.
+    pub children: usize,
+}
+
+impl ExecutionProgress {
+    /// Возвращает неисполненное количество.
+    pub fn remaining(&self) -> Decimal {
+        (self.target - self.filled).max(Decimal::ZERO)
+    }
+}
+
+impl ChildProgress for ExecutionProgress {
+    fn status(&self) -> &ExecutionStatus {
+        &self.status
+    }
+
+    fn update(&mut self, status: ExecutionStatus, children: &ChildOrders) {
+        self.status = status;
+        self.filled = children.filled();
+        self.children = children.len();
+        self.average_price = children.average_price();
+        self.slippage_bps = match (self.average_price, self.arrival_price) {
+            (Some(average), Some(arrival)) => slippage_bps(children.side(), average, arrival),
+            _ => None,
+        };
+    }
+}
+
+/// Алгоритмическое исполнение родительской заявки TWAP или VWAP.
+///
+/// Делит объем на дочерние заявки, выставляемые только в торговые сессии
+/// инструмента внутри окна исполнения. Неисполненный остаток дочерней заявки
+/// отменяется и переносится в следующую часть. Исполнение останавливается
+/// при вызове `cancel` или уничтожении последнего клона, активная дочерняя
+/// заявка при этом отменяется.
+#[derive(Clone, Debug)]
+pub struct ExecutionAlgo {
+    id: String,
+    slices: Arc<Vec<Slice>>,
+    progress: watch::Receiver<ExecutionProgress>,
+    cancel: Arc<watch::Sender<bool>>,
+    /// Удерживает фоновую задачу исполнения. При уничтожении последней ссылки
+    /// отправляет сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Arc<ShutdownGuard>,
+}
+
+impl ExecutionAlgo {
+    /// Планирует и запускает исполнение родительской заявки.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    /// * `tracker` - Трекер заявок аккаунта родительской заявки.
+    /// * `parent` - Родительская заявка.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Запущенное исполнение или ошибку планирования.
+    pub async fn start(
+        sdk: FinamSdk,
+        tracker: OrderTracker,
+        parent: ParentOrder,
+    ) -> Result<Self, FinamSdkError> {
+        validate(&parent, &tracker)?;
+
+        let asset = sdk
+            .assets()
+            .get_asset(GetAssetRequest {
+                symbol: parent.symbol.clone(),
+                account_id: parent.account_id.clone(),
+            })
+            .await?
+            .into_inner();
+        let lot = opt_decimal(asset.lot_size.as_ref())?.max(Decimal::ONE);
+
+        if !(parent.quantity % lot).is_zero() {
+            return Err(FinamSdkError::InvalidArgument(format!(
+                "quantity {} is not a multiple of lot size {lot}",
+                parent.quantity
+            )));
+        }
+
+        let schedule = sdk
+            .assets()
+            .schedule(ScheduleRequest {
+                symbol: parent.symbol.clone(),
+            })
+            .await?
+            .into_inner();
+        let windows = trading_windows(&schedule, parent.start, parent.end);
+
+        if windows.is_empty() {
+            return Err(FinamSdkError::InvalidArgument(format!(
+                "no trading sessions for {} in the execution window",
+                parent.symbol
+            )));
+        }
+
+        let slices = match parent.style {
+            ExecutionStyle::Twap { slices } => {
+                twap_schedule(parent.quantity, lot, &windows, slices)
+            }
+            ExecutionStyle::Vwap { lookback_days } => {
+                let curve = load_volume_curve(&sdk, &parent.symbol, lookback_days).await?;
+                vwap_schedule(parent.quantity, lot, &windows, &curve)
+            }
+        };
+
+        let arrival_price = arrival_price(&sdk, &parent.symbol).await?;
+        let id = next_id("");
+        let (progress_sender, progress) = watch::channel(ExecutionProgress {
+            status: ExecutionStatus::Running,
+            target: parent.quantity,
+            filled: Decimal::ZERO,
+            average_price: None,
+            arrival_price,
+            slippage_bps: None,
+            children: 0,
+        });
+        let (cancel, cancel_receiver) = watch::channel(false);
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+
+        let trades = sdk.subscribe_trades(&parent.account_id).await?;
+        let updates = tracker.subscribe();
+        let runner = ExecutionRunner {
+            id: id.clone(),
+            children: ChildOrders::new(sdk, tracker, &parent.account_id, parent.side),
+            parent,
+            lot,
+            price_step: asset.price_step(),
+            slices: slices.clone(),
+            progress: progress_sender,
+        };
+        tokio::spawn(runner.run(updates, trades, cancel_receiver, shutdown_receiver));
+
+        Ok(Self {
+            id,
+            slices: Arc::new(slices),
+            progress,
+            cancel: Arc::new(cancel),
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        })
+    }
+
+    /// Возвращает идентификатор исполнения, указываемый в комментарии дочерних заявок.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Возвращает запланированные дочерние заявки.
+    pub fn slices(&self) -> &[Slice] {
+        &self.slices
+    }
+
+    /// Возвращает текущий прогресс исполнения.
+    pub fn progress(&self) -> ExecutionProgress {
+        self.progress.borrow().clone()
+    }
+
+    /// Подписывается на изменения прогресса исполнения.
+    pub fn subscribe(&self) -> watch::Receiver<ExecutionProgress> {
+        self.progress.clone()
+    }
+
+    /// Останавливает исполнение и отменяет активную дочернюю заявку.
+    pub fn cancel(&self) {
+        let _ = self.cancel.send(true);
+    }
+
+    /// Ожидает завершения исполнения.
+    ///
+    /// # Возвращает
+    ///
+    /// * `ExecutionProgress` - Итоговый прогресс исполнения.
+    pub async fn wait(&self) -> ExecutionProgress {
+        wait_finished(&self.progress).await
+    }
+}
+
+/// Возвращает торговые интервалы расписания, пересекающиеся с окном исполнения.
+///
+/// Торговыми считаются сессии, тип которых содержит `TRADING`.
+///
+/// # Аргументы
+///
+/// * `schedule` - Расписание инструмента.
+/// * `start` - Начало окна исполнения.
+/// * `end` - Окончание окна исполнения.
+///
+/// # Возвращает
+///
+/// * `Vec<TradingWindow>` - Отсортированные торговые интервалы внутри окна.
+pub fn trading_windows(
+    schedule: &ScheduleResponse,
+    start: SystemTime,
+    end: SystemTime,
+) -> Vec<TradingWindow> {
+    let mut windows: Vec<_> = schedule
+        .sessions
+        .iter()
+        .filter(|session| session.is_trading())
+        .filter_map(|session| session.window())
+        .filter_map(|window| {
+            let window = TradingWindow {
+                start: window.start.max(start),
+                end: window.end.min(end),
+            };
+            (window.start < window.end).then_some(window)
+        })
+        .collect();
+
+    windows.sort_by_key(|window| window.start);
+    windows
+}
+
+/// Планирует TWAP: равные части через равные промежутки торгового времени.
+///
+/// # Аргументы
+///
+/// * `quantity` - Общее количество в шт.
+/// * `lot` - Размер лота. Части кратны лоту.
+/// * `windows` - Торговые интервалы.
+/// * `slices` - Количество частей.
+///
+/// # Возвращает
+///
+/// * `Vec<Slice>` - Запланированные части без нулевых.
+pub fn twap_schedule(
+    quantity: Decimal,
+    lot: Decimal,
+    windows: &[TradingWindow],
+    slices: usize,
+) -> Vec<Slice> {
+    let total: Duration = windows.iter().map(TradingWindow::duration).sum();
+    let slices = slices.max(1);
+    let step = total / slices as u32;
+
+    let times = (0..slices).filter_map(|index| at_trading_offset(windows, step * index as u32));
+    let times: Vec<_> = times.collect();
+    let quantities = allocate(quantity, lot, &vec![Decimal::ONE; times.len()]);
+
+    build_slices(times, quantities)
+}
+
+/// Планирует VWAP: по части на каждый пятиминутный интервал торгового времени
+/// с объемом, пропорциональным историческому объему интервала.
+///
+/// Если исторический объем торговых интервалов нулевой, объем делится поровну.
+///
+/// # Аргументы
+///
+/// * `quantity` - Общее количество в шт.
+/// * `lot` - Размер лота. Части кратны лоту.
+/// * `windows` - Торговые интервалы.
+/// * `curve` - Профиль объема.
+///
+/// # Возвращает
+///
+/// * `Vec<Slice>` - Запланированные части без нулевых.
+pub fn vwap_schedule(
+    quantity: Decimal,
+    lot: Decimal,
+    windows: &[TradingWindow],
+    curve: &VolumeCurve,
+) -> Vec<Slice> {
+    let mut times = Vec::new();
+
+    for window in windows {
+        let mut at = window.start;
+        while at < window.end {
+            times.push(at);
+            at = next_bucket_start(at);
+        }
+    }
+
+    let mut weights: Vec<_> = times.iter().map(|at| curve.weight_at(*at)).collect();
+    if weights.iter().all(Decimal::is_zero) {
+        weights.fill(Decimal::ONE);
+    }
+
+    let quantities = allocate(quantity, lot, &weights);
+    build_slices(times, quantities)
+}
+
+impl schedule_response::Sessions {
+    /// Проверяет, является ли сессия торговой.
+    ///
+    /// Совпадает с [`crate::calendar::SessionKind::is_open`] для типа сессии.
+    pub fn is_trading(&self) -> bool {
+        self.kind().is_open()
+    }
+
+    fn window(&self) -> Option<TradingWindow> {
+        let interval = self.interval.as_ref()?;
+
+        Some(TradingWindow {
+            start: SystemTime::try_from(interval.start_time?).ok()?,
+            end: SystemTime::try_from(interval.end_time?).ok()?,
+        })
+    }
+}
+
+/// Состояние фоновой задачи исполнения.
+struct ExecutionRunner {
+    id: String,
+    parent: ParentOrder,
+    lot: Decimal,
+    price_step: Decimal,
+    slices: Vec<Slice>,
+    progress: watch::Sender<ExecutionProgress>,
+    children: ChildOrders,
+}
+
+impl ExecutionRunner {
+    async fn run(
+        mut self,
+        mut updates: broadcast::Receiver<OrderState>,
+        mut trades: TradeStream,
+        mut cancel: watch::Receiver<bool>,
+        mut shutdown: oneshot::Receiver<()>,
+    ) {
+        let mut next_slice = 0;
+
+        let status = loop {
+            if self.filled() >= self.parent.quantity {
+                break ExecutionStatus::Completed;
+            }
+
+            let deadline = self.deadline(next_slice);
+
+            tokio::select! {
+                _ = &mut shutdown => {
+                    log::info!("Execution {} shutting down", self.id);
+                    break ExecutionStatus::Canceled;
+                }
+                _ = wait_canceled(&mut cancel) => {
+                    break ExecutionStatus::Canceled;
+                }
+                update = updates.recv() => match update {
+                    Ok(state) => {
+                        self.children.on_update(&state);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.children.resync(),
+                    Err(broadcast::error::RecvError::Closed) => {
+                        break ExecutionStatus::Failed("order tracker stopped".to_string());
+                    }
+                },
+                trade = trades.next() => match trade {
+                    Some(Ok(trade)) => self.children.on_trade(&trade),
+                    Some(Err(error)) => {
+                        log::error!("Trade stream for execution {} failed: {:?}", self.id, error);
+                        trades = futures::stream::pending().boxed();
+                    }
+                    None => trades = futures::stream::pending().boxed(),
+                },
+                _ = tokio::time::sleep_until(deadline) => {
+                    let result = if next_slice < self.slices.len()
+                        && self.slices[next_slice].at <= self.children.sdk().server_now()
+                    {
+                        next_slice += 1;
+                        self.on_slice(next_slice - 1).await
+                    } else if next_slice == self.slices.len() && self.parent.end <= self.children.sdk().server_now() {
+                        break ExecutionStatus::Expired;
+                    } else {
+                        self.reprice().await
+                    };
+
+                    if let Err(error) = result {
+                        log::error!("Execution {} failed: {:?}", self.id, error);
+                        break ExecutionStatus::Failed(error.to_string());
+                    }
+                }
+            }
+
+            self.publish(ExecutionStatus::Running);
+        };
+
+        if let Err(error) = self.children.cancel_active().await {
+            log::error!(
+                "Failed to cancel child order of execution {}: {:?}",
+                self.id,
+                error
+            );
+        }
+
+        let status = match status {
+            ExecutionStatus::Expired | ExecutionStatus::Canceled
+                if self.filled() >= self.parent.quantity =>
+            {
+                ExecutionStatus::Completed
+            }
+            status => status,
+        };
+
+        log::info!(
+            "Execution {} finished with {:?}, filled {} of {}",
+            self.id,
+            status,
+            self.filled(),
+            self.parent.quantity
+        );
+        self.publish(status);
+    }
+
+    /// Ближайший момент, когда требуется действие: следующая часть, перестановка
+    /// лимитной заявки или окончание окна исполнения.
+    fn deadline(&self, next_slice: usize) -> Instant {
+        let now = self.children.sdk().server_now();
+        let mut deadline = match self.slices.get(next_slice) {
+            Some(slice) => instant_at(slice.at, now),
+            None => instant_at(self.parent.end, now),
+        };
+
+        if let (ChildPricing::Limit { reprice_after, .. }, Some(active)) =
+            (self.parent.pricing, self.children.active())
+        {
+            deadline = deadline.min(active.placed_at + reprice_after);
+        }
+
+        deadline
+    }
+
+    fn filled(&self) -> Decimal {
+        self.children.filled()
+    }
+
+    /// Выставляет часть, перенося в нее неисполненный остаток предыдущих частей.
+    async fn on_slice(&mut self, index: usize) -> Result<(), FinamSdkError> {
+        self.children.cancel_active().await?;
+
+        let remaining = self.parent.quantity - self.filled();
+        let quantity = if index + 1 == self.slices.len() {
+            remaining
+        } else {
+            let target: Decimal = self.slices[..=index]
+                .iter()
+                .map(|slice| slice.quantity)
+                .sum();
+            let due = (target - self.filled()).min(remaining);
+            (due / self.lot).floor() * self.lot
+        };
+
+        if quantity <= Decimal::ZERO {
+            return Ok(());
+        }
+
+        let mut order = Order {
+            account_id: self.parent.account_id.clone(),
+            symbol: self.parent.symbol.clone(),
+            quantity: Some(from_decimal(quantity)),
+            side: self.parent.side as i32,
+            r#type: OrderType::Market as i32,
+            time_in_force: TimeInForce::Day as i32,
+            comment: format!("exec {} {}", self.id, index + 1),
+            ..Default::default()
+        };
+
+        if let Some(price) = self.limit_price().await? {
+            order.r#type = OrderType::Limit as i32;
+            order.limit_price = Some(from_decimal(price));
+        }
+
+        self.children.place(order).await?;
+
+        Ok(())
+    }
+
+    /// Переставляет неисполненную лимитную заявку по текущей цене.
+    async fn reprice(&mut self) -> Result<(), FinamSdkError> {
+        let ChildPricing::Limit { reprice_after, .. } = self.parent.pricing else {
+            return Ok(());
+        };
+
+        let Some(active) = self
+            .children
+            .take_active_if(|active| active.placed_at + reprice_after <= Instant::now())
+        else {
+            return Ok(());
+        };
+
+        let Some(price) = self.limit_price().await? else {
+            self.children.restore_active(active);
+            return Ok(());
+        };
+
+        match self
+            .children
+            .sdk()
+            .replace_order(
+                &self.parent.account_id,
+                &active.order_id,
+                Some(price),
+                active.quantity,
+            )
+            .await
+        {
+            Ok(result) => {
+                self.children
+                    .set_executed(&active.order_id, result.executed_quantity);
+
+                if let Some(placed) = result.placed {
+                    self.children
+                        .track(placed, active.quantity - result.executed_quantity);
+                }
+
+                Ok(())
+            }
+            Err(FinamSdkError::OrderFilled(_) | FinamSdkError::OrderNotActive { .. }) => {
+                self.children.resync();
+                Ok(())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    async fn limit_price(&self) -> Result<Option<Decimal>, FinamSdkError> {
+        let ChildPricing::Limit { offset_ticks, .. } = self.parent.pricing else {
+            return Ok(None);
+        };
+
+        let quote = self
+            .children
+            .sdk()
+            .market_data()
+            .last_quote(QuoteRequest {
+                symbol: self.parent.symbol.clone(),
+            })
+            .await?
+            .into_inner()
+            .quote
+            .unwrap_or_default();
+
+        let buys = self.parent.side == Side::Buy;
+        let best = if buys { &quote.bid } else { &quote.ask };
+        let Some(best) = best.as_ref().or(quote.last.as_ref()) else {
+            return Ok(None);
+        };
+
+        let offset = self.price_step * Decimal::from(offset_ticks);
+        let price = if buys {
+            to_decimal(best)? + offset
+        } else {
+            to_decimal(best)? - offset
+        };
+
+        Ok(Some(round_to_step(
+            price,
+            self.price_step,
+            RoundingStrategy::MidpointAwayFromZero,
+        )))
+    }
+
+    fn publish(&self, status: ExecutionStatus) {
+        self.children.publish(&self.progress, status);
+    }
+}
+
+/// Вычисляет проскальзывание средней цены исполнения относительно цены запуска.
+///
+/// # Аргументы
+///
+/// * `side` - Сторона заявки.
+/// * `average_price` - Средняя цена исполнения.
+/// * `arrival_price` - Цена на момент запуска.
+///
+/// # Возвращает
+///
+/// * `Option<Decimal>` - Проскальзывание в базисных пунктах, положительное при
+///   исполнении хуже цены запуска, или `None` при нулевой цене запуска.
+pub fn slippage_bps(side: Side, average_price: Decimal, arrival_price: Decimal) -> Option<Decimal> {
+    if arrival_price.is_zero() {
+        return None;
+    }
+
+    let bps = (average_price - arrival_price) / arrival_price * Decimal::from(10_000);
+    Some(if side == Side::Sell { -bps } else { bps }.round_dp(2))
+}
+
+fn validate(parent: &ParentOrder, tracker: &OrderTracker) -> Result<(), FinamSdkError> {
+    if tracker.account_id() != parent.account_id {
+        return Err(FinamSdkError::InvalidArgument(
+            "order tracker belongs to another account".to_string(),
+        ));
+    }
+
+    if parent.side == Side::Unspecified || parent.quantity <= Decimal::ZERO {
+        return Err(FinamSdkError::InvalidArgument(
+            "parent order requires side and positive quantity".to_string(),
+        ));
+    }
+
+    if parent.start >= parent.end {
+        return Err(FinamSdkError::InvalidArgument(
+            "execution window start must be before its end".to_string(),
+        ));
+    }
+
+    match parent.style {
+        ExecutionStyle::Twap { slices: 0 } => Err(FinamSdkError::InvalidArgument(
+            "TWAP requires at least one slice".to_string(),
+        )),
+        ExecutionStyle::Vwap { lookback_days }
+            if !(1..=MAX_LOOKBACK_DAYS).contains(&lookback_days) =>
+        {
+            Err(FinamSdkError::InvalidArgument(format!(
+                "VWAP lookback must be from 1 to {MAX_LOOKBACK_DAYS} days"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn load_volume_curve(
+    sdk: &FinamSdk,
+    symbol: &str,
+    lookback_days: u32,
+) -> Result<VolumeCurve, FinamSdkError> {
+    let end = sdk.server_now();
+    let start = end - Duration::from_secs(SECONDS_PER_DAY * u64::from(lookback_days));
+
+    let bars = sdk
+        .market_data()
+        .bars(BarsRequest {
+            symbol: symbol.to_string(),
+            timeframe: TimeFrame::M5 as i32,
+            interval: Some(Interval {
+                start_time: Some(Timestamp::from(start)),
+                end_time: Some(Timestamp::from(end)),
+            }),
+        })
+        .await?
+        .into_inner()
+        .bars;
+
+    VolumeCurve::from_bars(&bars)
+}
+
+async fn arrival_price(sdk: &FinamSdk, symbol: &str) -> Result<Option<Decimal>, FinamSdkError> {
+    let Some(quote) = sdk
+        .market_data()
+        .last_quote(QuoteRequest {
+            symbol: symbol.to_string(),
+        })
+        .await?
+        .into_inner()
+        .quote
+    else {
+        return Ok(None);
+    };
+
+    let bid = opt_decimal(quote.bid.as_ref())?;
+    let ask = opt_decimal(quote.ask.as_ref())?;
+
+    if bid > Decimal::ZERO && ask > Decimal::ZERO {
+        return Ok(Some((bid + ask) / Decimal::TWO));
+    }
+
+    let last = opt_decimal(quote.last.as_ref())?;
+    Ok((last > Decimal::ZERO).then_some(last))
+}
+
+/// Распределяет количество по весам целыми лотами методом наибольшего остатка.
+fn allocate(quantity: Decimal, lot: Decimal, weights: &[Decimal]) -> Vec<Decimal> {
+    let total_weight: Decimal = weights.iter().sum();
+
+    if weights.is_empty() || total_weight <= Decimal::ZERO {
+        return vec![Decimal::ZERO; weights.len()];
+    }
+
+    let lots = (quantity / lot).floor();
+    let shares: Vec<_> = weights
+        .iter()
+        .map(|weight| lots * *weight / total_weight)
+        .collect();
+    let mut allocated: Vec<_> = shares.iter().map(|share| share.floor()).collect();
+
+    let mut order: Vec<_> = (0..shares.len()).collect();
+    order.sort_by(|a, b| {
+        (shares[*b] - allocated[*b])
+            .cmp(&(shares[*a] - allocated[*a]))
+            .then(a.cmp(b))
+    });
+
+    let mut left = lots - allocated.iter().sum::<Decimal>();
+    for index in order {
+        if left <= Decimal::ZERO {
+            break;
+        }
+        allocated[index] += Decimal::ONE;
+        left -= Decimal::ONE;
+    }
+
+    allocated.into_iter().map(|lots| lots * lot).collect()
+}
+
+fn build_slices(times: Vec<SystemTime>, quantities: Vec<Decimal>) -> Vec<Slice> {
+    times
+        .into_iter()
+        .zip(quantities)
+        .filter(|(_, quantity)| *quantity > Decimal::ZERO)
+        .map(|(at, quantity)| Slice { at, quantity })
+        .collect()
+}
+
+/// Находит момент, отстоящий от начала первого интервала на заданное торговое время.
+fn at_trading_offset(windows: &[TradingWindow], mut offset: Duration) -> Option<SystemTime> {
+    for window in windows {
+        let duration = window.duration();
+        if offset < duration {
+            return Some(window.start + offset);
+        }
+        offset -= duration;
+    }
+
+    None
+}
+
+fn bucket_count() -> usize {
+    (SECONDS_PER_DAY / VOLUME_BUCKET.as_secs()) as usize
+}
+
+fn bucket_of_day(at: SystemTime) -> usize {
+    let seconds = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+
+    ((seconds % SECONDS_PER_DAY) / VOLUME_BUCKET.as_secs()) as usize
+}
+
+fn next_bucket_start(at: SystemTime) -> SystemTime {
+    let seconds = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let bucket = VOLUME_BUCKET.as_secs();
+
+    UNIX_EPOCH + Duration::from_secs((seconds / bucket + 1) * bucket)
+}
+
+/// Переводит время сервера `at` в момент таймера по текущему времени сервера `now`.
+fn instant_at(at: SystemTime, now: SystemTime) -> Instant {
+    Instant::now() + at.duration_since(now).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn window(start: u64, end: u64) -> TradingWindow {
+        TradingWindow {
+            start: at(start),
+            end: at(end),
+        }
+    }
+
+    fn session(kind: &str, start: u64, end: u64) -> schedule_response::Sessions {
+        schedule_response::Sessions {
+            r#type: kind.to_string(),
+            interval: Some(Interval {
+                start_time: Some(Timestamp::from(at(start))),
+                end_time: Some(Timestamp::from(at(end))),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_trading_windows_clip_to_execution_window() {
+        let schedule = ScheduleResponse {
+            symbol: "SBER@MISX".to_string(),
+            sessions: vec![
+                session("CORE_TRADING", 1_000, 2_000),
+                session("CLOSED", 2_000, 3_000),
+                session("LATE_TRADING", 3_000, 4_000),
+            ],
+        };
+
+        assert_eq!(
+            trading_windows(&schedule, at(1_500), at(3_500)),
+            vec![window(1_500, 2_000), window(3_000, 3_500)]
+        );
+    }
+
+    #[test]
+    fn test_twap_schedule_spans_sessions_in_whole_lots() {
+        let windows = [window(0, 600), window(1_200, 1_800)];
+        let slices = twap_schedule(Decimal::from(100), Decimal::from(10), &windows, 4);
+
+        assert_eq!(
+            slices.iter().map(|slice| slice.at).collect::<Vec<_>>(),
+            vec![at(0), at(300), at(1_200), at(1_500)]
+        );
+        assert_eq!(
+            slices
+                .iter()
+                .map(|slice| slice.quantity)
+                .collect::<Vec<_>>(),
+            vec![
+                Decimal::from(30),
+                Decimal::from(30),
+                Decimal::from(20),
+                Decimal::from(20)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vwap_schedule_follows_volume_curve() {
+        let bar = |seconds: u64, volume: i64| Bar {
+            timestamp: Some(Timestamp::from(at(seconds))),
+            volume: Some(from_decimal(Decimal::from(volume))),
+            ..Default::default()
+        };
+        let curve =
+            VolumeCurve::from_bars(&[bar(0, 300), bar(SECONDS_PER_DAY + 300, 100)]).unwrap();
+
+        let slices = vwap_schedule(
+            Decimal::from(40),
+            Decimal::ONE,
+            &[window(2 * SECONDS_PER_DAY, 2 * SECONDS_PER_DAY + 600)],
+            &curve,
+        );
+
+        assert_eq!(
+            slices
+                .iter()
+                .map(|slice| slice.quantity)
+                .collect::<Vec<_>>(),
+            vec![Decimal::from(30), Decimal::from(10)]
+        );
+    }
+
+    #[test]
+    fn test_slippage_sign_depends_on_side() {
+        let arrival = Decimal::from(100);
+        let worse_buy = slippage_bps(Side::Buy, Decimal::new(1001, 1), arrival);
+        let better_sell = slippage_bps(Side::Sell, Decimal::new(1001, 1), arrival);
+
+        assert_eq!(worse_buy, Some(Decimal::from(10)));
+        assert_eq!(better_sell, Some(Decimal::from(-10)));
+    }
+}
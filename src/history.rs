@@ -0,0 +1,441 @@
+use std::{collections::HashSet, future::Future, sync::Arc};
+
+use futures::{StreamExt, stream::BoxStream};
+use prost_types::Timestamp;
+use tokio::time::Duration;
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    proto::{
+        google::r#type::Interval,
+        grpc::tradeapi::v1::{
+            AccountTrade,
+            accounts::{TradesRequest, Transaction, TransactionsRequest},
+        },
+    },
+    rate_limit::RateLimiter,
+    trading::TradeStream,
+};
+
+/// Количество наносекунд в секунде.
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// Поток транзакций аккаунта.
+pub type TransactionStream = BoxStream<'static, Result<Transaction, FinamSdkError>>;
+
+/// Параметры постраничной загрузки истории.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HistoryOptions {
+    /// Лимит записей в одном запросе.
+    pub page_size: i32,
+    /// Длина окна, на которые делится запрошенный период.
+    pub window: Duration,
+    /// Максимальное количество окон, загружаемых одновременно.
+    pub concurrency: usize,
+    /// Максимальная частота запросов в секунду. Ноль отключает ограничение.
+    pub requests_per_second: u32,
+}
+
+impl Default for HistoryOptions {
+    fn default() -> Self {
+        Self {
+            page_size: 1000,
+            window: Duration::from_secs(30 * 24 * 3600),
+            concurrency: 4,
+            requests_per_second: 10,
+        }
+    }
+}
+
+impl FinamSdk {
+    /// Загружает сделки аккаунта за произвольный период с параметрами по умолчанию.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `interval` - Период истории с заданными началом и окончанием.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<TradeStream, FinamSdkError>` - Поток сделок или ошибку, если период не задан.
+    pub fn trades_history(
+        &self,
+        account_id: &str,
+        interval: Interval,
+    ) -> Result<TradeStream, FinamSdkError> {
+        self.trades_history_with(account_id, interval, HistoryOptions::default())
+    }
+
+    /// Загружает сделки аккаунта за произвольный период.
+    ///
+    /// Период делится на окна, которые загружаются параллельно. Внутри окна
+    /// запросы повторяются со сдвигом границы к времени последней полученной
+    /// сделки, пока страница заполнена полностью. Сделки выдаются по
+    /// возрастанию времени без повторов по `trade_id`. В режиме бумажной
+    /// торговли возвращаются сделки симулятора.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `interval` - Период истории с заданными началом и окончанием.
+    /// * `options` - Параметры загрузки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<TradeStream, FinamSdkError>` - Поток сделок или ошибку, если период не задан.
+    pub fn trades_history_with(
+        &self,
+        account_id: &str,
+        interval: Interval,
+        options: HistoryOptions,
+    ) -> Result<TradeStream, FinamSdkError> {
+        if let Some(paper) = &self.paper {
+            return Ok(futures::stream::iter(paper.trades(account_id, &interval))
+                .map(Ok)
+                .boxed());
+        }
+
+        let sdk = self.clone();
+        let account_id = account_id.to_string();
+
+        paginate(
+            interval,
+            options,
+            move |interval, limit| {
+                let sdk = sdk.clone();
+                let account_id = account_id.clone();
+                async move {
+                    Ok(sdk
+                        .accounts()
+                        .trades(TradesRequest {
+                            account_id,
+                            limit,
+                            interval: Some(interval),
+                        })
+                        .await?
+                        .into_inner()
+                        .trades)
+                }
+            },
+            |trade: &AccountTrade| (trade.trade_id.clone(), trade.timestamp),
+        )
+    }
+
+    /// Загружает транзакции аккаунта за произвольный период с параметрами по умолчанию.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `interval` - Период истории с заданными началом и окончанием.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<TransactionStream, FinamSdkError>` - Поток транзакций или ошибку, если период не задан.
+    pub fn transactions_history(
+        &self,
+        account_id: &str,
+        interval: Interval,
+    ) -> Result<TransactionStream, FinamSdkError> {
+        self.transactions_history_with(account_id, interval, HistoryOptions::default())
+    }
+
+    /// Загружает транзакции аккаунта за произвольный период.
+    ///
+    /// Работает так же, как [`FinamSdk::trades_history_with`], повторы
+    /// отбрасываются по `id`. Симулятор бумажной торговли не начисляет
+    /// комиссий и не ведет движения средств, поэтому в этом режиме поток пуст.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `interval` - Период истории с заданными началом и окончанием.
+    /// * `options` - Параметры загрузки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<TransactionStream, FinamSdkError>` - Поток транзакций или ошибку, если период не задан.
+    pub fn transactions_history_with(
+        &self,
+        account_id: &str,
+        interval: Interval,
+        options: HistoryOptions,
+    ) -> Result<TransactionStream, FinamSdkError> {
+        if self.paper.is_some() {
+            return Ok(futures::stream::empty().boxed());
+        }
+
+        let sdk = self.clone();
+        let account_id = account_id.to_string();
+
+        paginate(
+            interval,
+            options,
+            move |interval, limit| {
+                let sdk = sdk.clone();
+                let account_id = account_id.clone();
+                async move {
+                    Ok(sdk
+                        .accounts()
+                        .transactions(TransactionsRequest {
+                            account_id,
+                            limit,
+                            interval: Some(interval),
+                        })
+                        .await?
+                        .into_inner()
+                        .transactions)
+                }
+            },
+            |transaction: &Transaction| (transaction.id.clone(), transaction.timestamp),
+        )
+    }
+}
+
+/// Загружает записи за период окнами и страницами.
+///
+/// # Аргументы
+///
+/// * `interval` - Период истории.
+/// * `options` - Параметры загрузки.
+/// * `fetch` - Запрос одной страницы: интервал и лимит.
+/// * `identify` - Идентификатор и время записи.
+///
+/// # Возвращает
+///
+/// * `Result<BoxStream<...>, FinamSdkError>` - Поток записей или ошибку, если период не задан.
+fn paginate<T, F, Fut>(
+    interval: Interval,
+    options: HistoryOptions,
+    fetch: F,
+    identify: fn(&T) -> (String, Option<Timestamp>),
+) -> Result<BoxStream<'static, Result<T, FinamSdkError>>, FinamSdkError>
+where
+    T: Send + 'static,
+    F: Fn(Interval, i32) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Vec<T>, FinamSdkError>> + Send + 'static,
+{
+    let (Some(start), Some(end)) = (interval.start_time, interval.end_time) else {
+        return Err(FinamSdkError::InvalidArgument(
+            "history interval must have both start and end time".to_string(),
+        ));
+    };
+
+    if options.page_size <= 0 || options.window.is_zero() {
+        return Err(FinamSdkError::InvalidArgument(
+            "history page size and window must be positive".to_string(),
+        ));
+    }
+
+    let limiter = Arc::new(RateLimiter::new(options.requests_per_second));
+    let fetch = Arc::new(fetch);
+    let window = options.window.as_nanos() as i128;
+    let end = to_nanos(&end);
+    let windows: Vec<_> = (to_nanos(&start)..end)
+        .step_by(usize::try_from(window).unwrap_or(usize::MAX))
+        .map(|from| Interval {
+            start_time: Some(from_nanos(from)),
+            end_time: Some(from_nanos((from + window).min(end))),
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+
+    Ok(futures::stream::iter(windows)
+        .map(move |window| {
+            fetch_window(
+                window,
+                options.page_size,
+                fetch.clone(),
+                limiter.clone(),
+                identify,
+            )
+        })
+        .buffered(options.concurrency.max(1))
+        .flat_map(|result| {
+            let items: Vec<_> = match result {
+                Ok(records) => records.into_iter().map(Ok).collect(),
+                Err(error) => vec![Err(error)],
+            };
+            futures::stream::iter(items)
+        })
+        .filter(move |record| {
+            let fresh = match record {
+                Ok(record) => {
+                    let (id, _) = identify(record);
+                    id.is_empty() || seen.insert(id)
+                }
+                Err(_) => true,
+            };
+            futures::future::ready(fresh)
+        })
+        .boxed())
+}
+
+/// Загружает все страницы одного окна и сортирует записи по времени.
+async fn fetch_window<T, F, Fut>(
+    mut window: Interval,
+    page_size: i32,
+    fetch: Arc<F>,
+    limiter: Arc<RateLimiter>,
+    identify: fn(&T) -> (String, Option<Timestamp>),
+) -> Result<Vec<T>, FinamSdkError>
+where
+    F: Fn(Interval, i32) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, FinamSdkError>>,
+{
+    let mut records = Vec::new();
+
+    loop {
+        limiter.acquire().await;
+        let page = fetch(window, page_size).await?;
+        let full = page.len() >= page_size as usize;
+        let first = page.first().and_then(|record| identify(record).1);
+        let last = page.last().and_then(|record| identify(record).1);
+        records.extend(page);
+
+        let (true, Some(first), Some(last)) = (full, first, last) else {
+            break;
+        };
+
+        // The server may return pages in either order, so the truncated side is
+        // the one the last record of the page is closest to
+        let (first, last) = (to_nanos(&first), to_nanos(&last));
+        if first <= last {
+            let start = window.start_time.as_ref().map_or(i128::MIN, to_nanos);
+            window.start_time = Some(from_nanos(next_bound(last, start, 1)));
+        } else {
+            let end = window.end_time.as_ref().map_or(i128::MAX, to_nanos);
+            window.end_time = Some(from_nanos(next_bound(last, end, -1)));
+        }
+
+        if window.start_time.as_ref().map(to_nanos) > window.end_time.as_ref().map(to_nanos) {
+            break;
+        }
+    }
+
+    records.sort_by_key(|record| identify(record).1.as_ref().map(to_nanos));
+
+    Ok(records)
+}
+
+/// Возвращает новую границу окна по времени последней записи страницы.
+///
+/// Если граница не сдвинулась, значит страница целиком состоит из записей с
+/// одним временем. Граница сдвигается на наносекунду, чтобы загрузка не зациклилась.
+fn next_bound(last: i128, current: i128, step: i128) -> i128 {
+    if last == current {
+        log::warn!(
+            "History page is filled with records sharing one timestamp, some records may be skipped"
+        );
+        last + step
+    } else {
+        last
+    }
+}
+
+fn to_nanos(timestamp: &Timestamp) -> i128 {
+    i128::from(timestamp.seconds) * NANOS_PER_SECOND + i128::from(timestamp.nanos)
+}
+
+fn from_nanos(nanos: i128) -> Timestamp {
+    Timestamp {
+        seconds: nanos.div_euclid(NANOS_PER_SECOND) as i64,
+        nanos: nanos.rem_euclid(NANOS_PER_SECOND) as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    fn trade(id: u32) -> AccountTrade {
+        AccountTrade {
+            trade_id: id.to_string(),
+            timestamp: Some(Timestamp {
+                seconds: i64::from(id),
+                nanos: 0,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn interval(start: i64, end: i64) -> Interval {
+        Interval {
+            start_time: Some(Timestamp {
+                seconds: start,
+                nanos: 0,
+            }),
+            end_time: Some(Timestamp {
+                seconds: end,
+                nanos: 0,
+            }),
+        }
+    }
+
+    async fn collect(descending: bool) -> (Vec<String>, usize) {
+        let requests = Arc::new(Mutex::new(0));
+        let counter = requests.clone();
+        let options = HistoryOptions {
+            page_size: 3,
+            window: Duration::from_secs(10),
+            concurrency: 2,
+            requests_per_second: 0,
+        };
+
+        let stream = paginate(
+            interval(0, 25),
+            options,
+            move |interval: Interval, limit| {
+                *counter.lock().unwrap() += 1;
+                let start = interval.start_time.unwrap().seconds;
+                let end = interval.end_time.unwrap().seconds;
+                let mut page: Vec<_> = (0..25u32)
+                    .step_by(2)
+                    .map(trade)
+                    .filter(|trade| (start..=end).contains(&trade.timestamp.unwrap().seconds))
+                    .collect();
+                if descending {
+                    page.reverse();
+                }
+                page.truncate(limit as usize);
+                async move { Ok(page) }
+            },
+            |trade: &AccountTrade| (trade.trade_id.clone(), trade.timestamp),
+        )
+        .unwrap();
+
+        let ids = stream
+            .map(|trade| trade.unwrap().trade_id)
+            .collect::<Vec<_>>()
+            .await;
+        let requests = *requests.lock().unwrap();
+
+        (ids, requests)
+    }
+
+    #[tokio::test]
+    async fn test_paginate_dedupes_across_pages_and_windows() {
+        let expected: Vec<_> = (0..25).step_by(2).map(|id| id.to_string()).collect();
+
+        let (ids, requests) = collect(false).await;
+        assert_eq!(ids, expected);
+        assert_eq!(requests, 8);
+
+        let (ids, _) = collect(true).await;
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn test_paginate_requires_bounded_interval() {
+        let result = paginate(
+            Interval::default(),
+            HistoryOptions::default(),
+            |_, _| async { Ok(Vec::<AccountTrade>::new()) },
+            |trade: &AccountTrade| (trade.trade_id.clone(), trade.timestamp),
+        );
+
+        assert!(matches!(result, Err(FinamSdkError::InvalidArgument(_))));
+    }
+}
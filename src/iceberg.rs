@@ -0,0 +1,471 @@
+use std::sync::Arc;
+
+use tokio::sync::{broadcast, oneshot, watch};
+
+use crate::{
+    FinamSdk, FinamSdkError, ShutdownGuard,
+    child::{ChildOrders, ChildProgress, wait_canceled, wait_finished},
+    decimal::{Decimal, from_decimal, opt_decimal},
+    execution::ExecutionStatus,
+    proto::grpc::tradeapi::v1::{
+        Side,
+        assets::GetAssetRequest,
+        orders::{Order, OrderState, OrderStatus, OrderType, TimeInForce},
+    },
+    tracker::OrderTracker,
+    util::next_id,
+};
+
+/// Параметры айсберг заявки.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IcebergOrder {
+    /// Идентификатор аккаунта.
+    pub account_id: String,
+    /// Символ инструмента.
+    pub symbol: String,
+    /// Сторона заявки.
+    pub side: Side,
+    /// Общее количество в шт.
+    pub quantity: Decimal,
+    /// Лимитная цена.
+    pub limit_price: Decimal,
+    /// Видимое количество в шт.
+    pub display_quantity: Decimal,
+    /// Допустимое случайное отклонение видимого количества в долях (от 0 до 1).
+    /// Например, `0.2` дает видимое количество от 80% до 120% заданного.
+    pub display_variance: Option<Decimal>,
+    /// Срок действия видимых частей.
+    pub time_in_force: TimeInForce,
+}
+
+/// Прогресс исполнения айсберг заявки.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IcebergProgress {
+    /// Статус исполнения.
+    pub status: ExecutionStatus,
+    /// Общее количество.
+    pub target: Decimal,
+    /// Исполненное количество.
+    pub filled: Decimal,
+    /// Идентификатор текущей видимой заявки.
+    pub active_order_id: Option<String>,
+    /// Количество выставленных видимых частей.
+    pub slices: usize,
+}
+
+impl ChildProgress for IcebergProgress {
+    fn status(&self) -> &ExecutionStatus {
+        &self.status
+    }
+
+    fn update(&mut self, status: ExecutionStatus, children: &ChildOrders) {
+        self.status = status;
+        self.filled = children.filled();
+        self.active_order_id = children.active().map(|active| active.order_id.clone());
+        self.slices = children.len();
+    }
+}
+
+/// Эмуляция айсберг заявки на стороне клиента.
+///
+/// Выставляет лимитную заявку только на видимое количество и после ее полного
+/// исполнения выставляет следующую, пока не будет исполнен весь объем. Частично
+/// исполненная видимая заявка остается в стакане до полного исполнения.
+/// Исполнение останавливается при вызове `cancel`, уничтожении последнего клона
+/// или отмене видимой заявки вне айсберга.
+#[derive(Clone, Debug)]
+pub struct Iceberg {
+    id: String,
+    progress: watch::Receiver<IcebergProgress>,
+    cancel: Arc<watch::Sender<bool>>,
+    /// Удерживает фоновую задачу. При уничтожении последней ссылки отправляет
+    /// сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Arc<ShutdownGuard>,
+}
+
+impl Iceberg {
+    /// Выставляет первую видимую часть и запускает сопровождение айсберг заявки.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    /// * `tracker` - Трекер заявок аккаунта.
+    /// * `iceberg` - Параметры айсберг заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Запущенная айсберг заявка или ошибку выставления.
+    pub async fn start(
+        sdk: FinamSdk,
+        tracker: OrderTracker,
+        iceberg: IcebergOrder,
+    ) -> Result<Self, FinamSdkError> {
+        validate(&iceberg, &tracker)?;
+
+        let lot = opt_decimal(
+            sdk.assets()
+                .get_asset(GetAssetRequest {
+                    symbol: iceberg.symbol.clone(),
+                    account_id: iceberg.account_id.clone(),
+                })
+                .await?
+                .into_inner()
+                .lot_size
+                .as_ref(),
+        )?
+        .max(Decimal::ONE);
+
+        if !(iceberg.quantity % lot).is_zero() || iceberg.display_quantity < lot {
+            return Err(FinamSdkError::InvalidArgument(format!(
+                "iceberg quantities must be multiples of lot size {lot}"
+            )));
+        }
+
+        let id = next_id("");
+        let (progress_sender, progress) = watch::channel(IcebergProgress {
+            status: ExecutionStatus::Running,
+            target: iceberg.quantity,
+            filled: Decimal::ZERO,
+            active_order_id: None,
+            slices: 0,
+        });
+        let (cancel, cancel_receiver) = watch::channel(false);
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+
+        let updates = tracker.subscribe();
+        let mut runner = IcebergRunner {
+            id: id.clone(),
+            children: ChildOrders::new(sdk, tracker, &iceberg.account_id, iceberg.side),
+            iceberg,
+            lot,
+            progress: progress_sender,
+        };
+
+        runner.place_slice().await?;
+        runner.publish(ExecutionStatus::Running);
+        tokio::spawn(runner.run(updates, cancel_receiver, shutdown_receiver));
+
+        Ok(Self {
+            id,
+            progress,
+            cancel: Arc::new(cancel),
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        })
+    }
+
+    /// Возвращает идентификатор айсберг заявки, указываемый в комментарии видимых частей.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Возвращает текущий прогресс исполнения.
+    pub fn progress(&self) -> IcebergProgress {
+        self.progress.borrow().clone()
+    }
+
+    /// Подписывается на изменения прогресса исполнения.
+    pub fn subscribe(&self) -> watch::Receiver<IcebergProgress> {
+        self.progress.clone()
+    }
+
+    /// Останавливает айсберг заявку и отменяет видимую часть.
+    pub fn cancel(&self) {
+        let _ = self.cancel.send(true);
+    }
+
+    /// Ожидает завершения айсберг заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `IcebergProgress` - Итоговый прогресс исполнения.
+    pub async fn wait(&self) -> IcebergProgress {
+        wait_finished(&self.progress).await
+    }
+}
+
+/// Вычисляет видимое количество следующей части.
+///
+/// # Аргументы
+///
+/// * `remaining` - Неисполненный остаток в шт.
+/// * `display` - Заданное видимое количество в шт.
+/// * `lot` - Размер лота.
+/// * `variance` - Допустимое отклонение в долях.
+/// * `random` - Случайное число от -1 до 1.
+///
+/// # Возвращает
+///
+/// * `Decimal` - Видимое количество, кратное лоту и не превышающее остаток.
+pub fn display_size(
+    remaining: Decimal,
+    display: Decimal,
+    lot: Decimal,
+    variance: Option<Decimal>,
+    random: Decimal,
+) -> Decimal {
+    let factor =
+        Decimal::ONE + variance.unwrap_or_default() * random.clamp(-Decimal::ONE, Decimal::ONE);
+    let size = ((display * factor) / lot).round() * lot;
+
+    size.max(lot).min(remaining)
+}
+
+/// Состояние фоновой задачи айсберг заявки.
+struct IcebergRunner {
+    id: String,
+    iceberg: IcebergOrder,
+    lot: Decimal,
+    progress: watch::Sender<IcebergProgress>,
+    children: ChildOrders,
+}
+
+impl IcebergRunner {
+    async fn run(
+        mut self,
+        mut updates: broadcast::Receiver<OrderState>,
+        mut cancel: watch::Receiver<bool>,
+        mut shutdown: oneshot::Receiver<()>,
+    ) {
+        let status = loop {
+            let Some(order_id) = self.children.active().map(|active| active.order_id.clone())
+            else {
+                break ExecutionStatus::Completed;
+            };
+
+            let state = tokio::select! {
+                _ = &mut shutdown => {
+                    log::info!("Iceberg {} shutting down", self.id);
+                    break ExecutionStatus::Canceled;
+                }
+                _ = wait_canceled(&mut cancel) => break ExecutionStatus::Canceled,
+                update = updates.recv() => match update {
+                    Ok(state) if state.order_id == order_id => state,
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(_)) => match self.children.tracker().get(&order_id) {
+                        Some(state) => state,
+                        None => continue,
+                    },
+                    Err(broadcast::error::RecvError::Closed) => {
+                        break ExecutionStatus::Failed("order tracker stopped".to_string());
+                    }
+                },
+            };
+
+            match self.on_slice_update(&state).await {
+                Ok(None) => self.publish(ExecutionStatus::Running),
+                Ok(Some(status)) => break status,
+                Err(error) => {
+                    log::error!("Iceberg {} failed: {:?}", self.id, error);
+                    break ExecutionStatus::Failed(error.to_string());
+                }
+            }
+        };
+
+        if let Err(error) = self.children.cancel_active().await {
+            log::error!(
+                "Failed to cancel visible order of iceberg {}: {:?}",
+                self.id,
+                error
+            );
+        }
+
+        let status = if self.filled() >= self.iceberg.quantity {
+            ExecutionStatus::Completed
+        } else {
+            status
+        };
+
+        log::info!(
+            "Iceberg {} finished with {:?}, filled {} of {}",
+            self.id,
+            status,
+            self.filled(),
+            self.iceberg.quantity
+        );
+        self.publish(status);
+    }
+
+    /// Обрабатывает обновление видимой заявки.
+    ///
+    /// Возвращает итоговый статус, если айсберг заявка завершена.
+    async fn on_slice_update(
+        &mut self,
+        state: &OrderState,
+    ) -> Result<Option<ExecutionStatus>, FinamSdkError> {
+        self.children.on_update(state);
+
+        let status = state.status();
+        if !status.is_final() {
+            return Ok(None);
+        }
+
+        if self.filled() >= self.iceberg.quantity {
+            return Ok(Some(ExecutionStatus::Completed));
+        }
+
+        match status {
+            status if status.is_filled() => {
+                self.place_slice().await?;
+                Ok(None)
+            }
+            OrderStatus::Expired | OrderStatus::DoneForDay => Ok(Some(ExecutionStatus::Expired)),
+            OrderStatus::Canceled => Ok(Some(ExecutionStatus::Canceled)),
+            _ => Ok(Some(ExecutionStatus::Failed(format!(
+                "visible order {} finished with {}",
+                state.order_id,
+                status.as_str_name()
+            )))),
+        }
+    }
+
+    fn filled(&self) -> Decimal {
+        self.children.filled()
+    }
+
+    /// Выставляет следующую видимую часть на остаток.
+    async fn place_slice(&mut self) -> Result<(), FinamSdkError> {
+        let quantity = display_size(
+            self.iceberg.quantity - self.filled(),
+            self.iceberg.display_quantity,
+            self.lot,
+            self.iceberg.display_variance,
+            random_unit(),
+        );
+
+        self.children
+            .place(Order {
+                account_id: self.iceberg.account_id.clone(),
+                symbol: self.iceberg.symbol.clone(),
+                quantity: Some(from_decimal(quantity)),
+                side: self.iceberg.side as i32,
+                r#type: OrderType::Limit as i32,
+                time_in_force: self.iceberg.time_in_force as i32,
+                limit_price: Some(from_decimal(self.iceberg.limit_price)),
+                comment: format!("iceberg {} {}", self.id, self.children.len() + 1),
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    fn publish(&self, status: ExecutionStatus) {
+        self.children.publish(&self.progress, status);
+    }
+}
+
+fn validate(iceberg: &IcebergOrder, tracker: &OrderTracker) -> Result<(), FinamSdkError> {
+    if tracker.account_id() != iceberg.account_id {
+        return Err(FinamSdkError::InvalidArgument(
+            "order tracker belongs to another account".to_string(),
+        ));
+    }
+
+    if iceberg.side == Side::Unspecified
+        || iceberg.quantity <= Decimal::ZERO
+        || iceberg.limit_price <= Decimal::ZERO
+    {
+        return Err(FinamSdkError::InvalidArgument(
+            "iceberg requires side, positive quantity and limit price".to_string(),
+        ));
+    }
+
+    if iceberg.display_quantity <= Decimal::ZERO || iceberg.display_quantity > iceberg.quantity {
+        return Err(FinamSdkError::InvalidArgument(
+            "display quantity must be positive and not exceed the total quantity".to_string(),
+        ));
+    }
+
+    if iceberg
+        .display_variance
+        .is_some_and(|variance| variance < Decimal::ZERO || variance >= Decimal::ONE)
+    {
+        return Err(FinamSdkError::InvalidArgument(
+            "display variance must be in [0, 1)".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Возвращает случайное число от -1 до 1.
+fn random_unit() -> Decimal {
+    Decimal::new(fastrand::i64(-1_000..=1_000), 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_size_respects_lot_and_remaining() {
+        let lot = Decimal::from(10);
+
+        assert_eq!(
+            display_size(
+                Decimal::from(1_000),
+                Decimal::from(100),
+                lot,
+                None,
+                Decimal::ONE
+            ),
+            Decimal::from(100)
+        );
+        assert_eq!(
+            display_size(
+                Decimal::from(1_000),
+                Decimal::from(100),
+                lot,
+                Some(Decimal::new(2, 1)),
+                Decimal::ONE
+            ),
+            Decimal::from(120)
+        );
+        assert_eq!(
+            display_size(
+                Decimal::from(1_000),
+                Decimal::from(100),
+                lot,
+                Some(Decimal::new(2, 1)),
+                -Decimal::ONE
+            ),
+            Decimal::from(80)
+        );
+        assert_eq!(
+            display_size(
+                Decimal::from(30),
+                Decimal::from(100),
+                lot,
+                None,
+                Decimal::ZERO
+            ),
+            Decimal::from(30)
+        );
+    }
+
+    #[test]
+    fn test_random_display_stays_within_variance() {
+        let variance = Some(Decimal::new(5, 1));
+        let mut seen = std::collections::HashSet::new();
+
+        for _ in 0..100 {
+            let random = random_unit();
+            assert!(random >= -Decimal::ONE && random <= Decimal::ONE);
+            seen.insert(random);
+
+            let size = display_size(
+                Decimal::from(1_000),
+                Decimal::from(100),
+                Decimal::ONE,
+                variance,
+                random,
+            );
+            assert!(size >= Decimal::from(50) && size <= Decimal::from(150));
+        }
+
+        assert!(seen.len() > 1);
+    }
+}
@@ -0,0 +1,812 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use futures::StreamExt;
+use prost::Message;
+use prost_types::Timestamp;
+use tokio::{
+    fs::OpenOptions,
+    io::AsyncWriteExt,
+    sync::{mpsc, oneshot},
+    task::JoinSet,
+};
+
+use crate::{
+    FinamOrdersServiceClient, FinamSdkError, ShutdownGuard,
+    paper::PaperBroker,
+    proto::grpc::tradeapi::v1::{
+        AccountTrade,
+        orders::{CancelOrderRequest, Order, OrderState, SltpOrder},
+    },
+    trading::{OrderStream, TradeStream, api_order_stream, api_trade_stream},
+    util::RECONNECT_DELAY,
+};
+
+/// Запись журнала заявок и сделок.
+///
+/// Журнал хранится как последовательность записей protobuf, каждой из которых
+/// предшествует ее длина в формате varint.
+#[derive(Clone, PartialEq, Message)]
+pub struct JournalRecord {
+    /// Порядковый номер записи.
+    #[prost(uint64, tag = "1")]
+    pub sequence: u64,
+    /// Локальное время записи.
+    #[prost(message, optional, tag = "2")]
+    pub recorded_at: Option<Timestamp>,
+    /// Время ответа сервера на запрос в микросекундах. Заполняется для ответов и ошибок.
+    #[prost(uint64, optional, tag = "3")]
+    pub latency_micros: Option<u64>,
+    /// Идентификатор аккаунта.
+    #[prost(string, tag = "4")]
+    pub account_id: String,
+    /// Символ инструмента, если известен.
+    #[prost(string, tag = "5")]
+    pub symbol: String,
+    /// Содержимое записи.
+    #[prost(oneof = "journal_record::Entry", tags = "10, 11, 12, 13, 14, 15, 16")]
+    pub entry: Option<journal_record::Entry>,
+}
+
+/// Вложенные типы `JournalRecord`.
+pub mod journal_record {
+    use crate::proto::grpc::tradeapi::v1::{
+        AccountTrade,
+        orders::{CancelOrderRequest, Order, OrderState, SltpOrder},
+    };
+
+    /// Содержимое записи журнала.
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Entry {
+        /// Запрос выставления заявки.
+        #[prost(message, tag = "10")]
+        OrderRequest(Order),
+        /// Запрос выставления SL/TP заявки.
+        #[prost(message, tag = "11")]
+        SltpOrderRequest(SltpOrder),
+        /// Запрос отмены заявки.
+        #[prost(message, tag = "12")]
+        CancelRequest(CancelOrderRequest),
+        /// Ответ сервера на запрос выставления или отмены.
+        #[prost(message, tag = "13")]
+        OrderResponse(OrderState),
+        /// Состояние заявки из потока `subscribe_orders`.
+        #[prost(message, tag = "14")]
+        OrderUpdate(OrderState),
+        /// Сделка из потока `subscribe_trades`.
+        #[prost(message, tag = "15")]
+        Trade(AccountTrade),
+        /// Ошибка выполнения запроса.
+        #[prost(string, tag = "16")]
+        Error(String),
+    }
+}
+
+use journal_record::Entry;
+
+impl JournalRecord {
+    /// Возвращает локальное время записи.
+    pub fn recorded_at(&self) -> Option<SystemTime> {
+        self.recorded_at
+            .and_then(|timestamp| SystemTime::try_from(timestamp).ok())
+    }
+
+    /// Возвращает время ответа сервера.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency_micros.map(Duration::from_micros)
+    }
+}
+
+/// Команда фоновой задачи записи журнала.
+enum JournalCommand {
+    Record(Box<JournalRecord>),
+    Flush(oneshot::Sender<std::io::Result<()>>),
+}
+
+impl std::fmt::Debug for JournalCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JournalCommand").finish_non_exhaustive()
+    }
+}
+
+/// Журнал заявок и сделок только для добавления.
+///
+/// Записи передаются фоновой задаче, которая дописывает их в файл и вызывает
+/// `sync_data` после каждой пачки. При аварийном завершении может быть потеряна
+/// только последняя недописанная запись, которую `JournalReader` пропускает.
+#[derive(Clone, Debug)]
+pub struct Journal {
+    path: PathBuf,
+    sequence: Arc<AtomicU64>,
+    sender: mpsc::UnboundedSender<JournalCommand>,
+    seen: Arc<Mutex<JournalSeen>>,
+}
+
+/// Уже записанные обновления заявок и сделки.
+///
+/// Позволяет не дублировать события, повторно полученные после
+/// переподключения потоков или перезапуска процесса.
+#[derive(Debug, Default)]
+struct JournalSeen {
+    orders: HashMap<String, OrderState>,
+    trades: HashSet<String>,
+}
+
+impl JournalSeen {
+    fn from_records(records: &[JournalRecord]) -> Self {
+        let mut seen = Self::default();
+
+        for record in records {
+            match &record.entry {
+                Some(Entry::OrderUpdate(state)) => {
+                    seen.orders.insert(state.order_id.clone(), state.clone());
+                }
+                Some(Entry::Trade(trade)) => {
+                    seen.trades.insert(trade.trade_id.clone());
+                }
+                _ => {}
+            }
+        }
+
+        seen
+    }
+}
+
+impl Journal {
+    /// Открывает журнал для добавления записей, создавая файл при необходимости.
+    ///
+    /// Нумерация записей продолжается с последней записи существующего файла.
+    /// Недописанная последняя запись, оставшаяся после сбоя, удаляется, чтобы
+    /// новые записи можно было прочитать.
+    ///
+    /// # Аргументы
+    ///
+    /// * `path` - Путь к файлу журнала.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Журнал или ошибку открытия файла.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, FinamSdkError> {
+        let path = path.as_ref().to_path_buf();
+
+        let (last_sequence, seen) = if tokio::fs::try_exists(&path).await? {
+            let reader = JournalReader::open(&path).await?;
+
+            if reader.is_truncated() {
+                log::warn!(
+                    "Journal {} ends with an incomplete record, truncating it",
+                    path.display()
+                );
+
+                OpenOptions::new()
+                    .write(true)
+                    .open(&path)
+                    .await?
+                    .set_len(reader.complete_len())
+                    .await?;
+            }
+
+            (
+                reader.records().last().map_or(0, |record| record.sequence),
+                JournalSeen::from_records(reader.records()),
+            )
+        } else {
+            (0, JournalSeen::default())
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(write_records(file, receiver));
+
+        Ok(Self {
+            path,
+            sequence: Arc::new(AtomicU64::new(last_sequence)),
+            sender,
+            seen: Arc::new(Mutex::new(seen)),
+        })
+    }
+
+    /// Возвращает путь к файлу журнала.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Дожидается записи на диск всех ранее добавленных записей.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку записи.
+    pub async fn flush(&self) -> Result<(), FinamSdkError> {
+        let (sender, receiver) = oneshot::channel();
+
+        self.sender
+            .send(JournalCommand::Flush(sender))
+            .map_err(|_| journal_closed())?;
+
+        Ok(receiver.await.map_err(|_| journal_closed())??)
+    }
+
+    /// Добавляет запись в журнал.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `symbol` - Символ инструмента или пустая строка.
+    /// * `entry` - Содержимое записи.
+    /// * `latency` - Время ответа сервера, если запись является ответом.
+    pub fn record(&self, account_id: &str, symbol: &str, entry: Entry, latency: Option<Duration>) {
+        let record = JournalRecord {
+            sequence: self.sequence.fetch_add(1, Ordering::SeqCst) + 1,
+            recorded_at: Some(Timestamp::from(SystemTime::now())),
+            latency_micros: latency.map(|latency| latency.as_micros() as u64),
+            account_id: account_id.to_string(),
+            symbol: symbol.to_string(),
+            entry: Some(entry),
+        };
+
+        if self
+            .sender
+            .send(JournalCommand::Record(Box::new(record)))
+            .is_err()
+        {
+            log::error!("Journal {} is closed, record dropped", self.path.display());
+        }
+    }
+
+    pub(crate) fn record_order(&self, order: &Order) {
+        self.record(
+            &order.account_id,
+            &order.symbol,
+            Entry::OrderRequest(order.clone()),
+            None,
+        );
+    }
+
+    pub(crate) fn record_sltp_order(&self, order: &SltpOrder) {
+        self.record(
+            &order.account_id,
+            &order.symbol,
+            Entry::SltpOrderRequest(order.clone()),
+            None,
+        );
+    }
+
+    pub(crate) fn record_cancel(&self, request: &CancelOrderRequest) {
+        self.record(
+            &request.account_id,
+            "",
+            Entry::CancelRequest(request.clone()),
+            None,
+        );
+    }
+
+    /// Записывает ответ сервера или ошибку запроса.
+    pub(crate) fn record_response(
+        &self,
+        account_id: &str,
+        symbol: &str,
+        result: &Result<OrderState, FinamSdkError>,
+        latency: Duration,
+    ) {
+        let (symbol, entry) = match result {
+            Ok(state) => (
+                state.order.as_ref().map_or(symbol, |order| &order.symbol),
+                Entry::OrderResponse(state.clone()),
+            ),
+            Err(error) => (symbol, Entry::Error(error.to_string())),
+        };
+
+        self.record(account_id, symbol, entry, Some(latency));
+    }
+
+    /// Записывает состояние заявки, если оно отличается от последнего записанного.
+    pub(crate) fn record_order_update(&self, state: &OrderState) {
+        if self
+            .lock_seen()
+            .orders
+            .insert(state.order_id.clone(), state.clone())
+            .is_some_and(|previous| previous == *state)
+        {
+            return;
+        }
+
+        let (account_id, symbol) = state
+            .order
+            .as_ref()
+            .map_or(("", ""), |order| (&order.account_id, &order.symbol));
+
+        self.record(account_id, symbol, Entry::OrderUpdate(state.clone()), None);
+    }
+
+    /// Записывает сделку, если она еще не записана.
+    pub(crate) fn record_trade(&self, trade: &AccountTrade) {
+        if !self.lock_seen().trades.insert(trade.trade_id.clone()) {
+            return;
+        }
+
+        self.record(
+            &trade.account_id,
+            &trade.symbol,
+            Entry::Trade(trade.clone()),
+            None,
+        );
+    }
+
+    fn lock_seen(&self) -> MutexGuard<'_, JournalSeen> {
+        self.seen
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Источник событий заявок и сделок для журнала.
+#[derive(Clone)]
+pub(crate) enum JournalSource {
+    Api(FinamOrdersServiceClient),
+    Paper(PaperBroker),
+}
+
+impl JournalSource {
+    async fn orders(&self, account_id: &str) -> Result<OrderStream, FinamSdkError> {
+        match self {
+            Self::Api(orders) => api_order_stream(orders.clone(), account_id).await,
+            Self::Paper(paper) => Ok(paper.subscribe_orders(account_id)),
+        }
+    }
+
+    async fn trades(&self, account_id: &str) -> Result<TradeStream, FinamSdkError> {
+        match self {
+            Self::Api(orders) => api_trade_stream(orders.clone(), account_id).await,
+            Self::Paper(paper) => Ok(paper.subscribe_trades(account_id)),
+        }
+    }
+}
+
+/// Запускает запись в журнал событий заявок и сделок из собственных подписок SDK.
+///
+/// На каждый аккаунт открывается одна подписка на заявки и одна на сделки,
+/// поэтому события записываются независимо от числа подписчиков SDK.
+/// После ошибки потока подписка возобновляется через `RECONNECT_DELAY`.
+///
+/// # Аргументы
+///
+/// * `journal` - Журнал.
+/// * `source` - Источник событий.
+/// * `account_ids` - Идентификаторы аккаунтов.
+///
+/// # Возвращает
+///
+/// * `Arc<ShutdownGuard>` - Охранник, останавливающий запись при уничтожении.
+pub(crate) fn start_recorder(
+    journal: Journal,
+    source: JournalSource,
+    account_ids: Vec<String>,
+) -> Arc<ShutdownGuard> {
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+    let mut workers = JoinSet::new();
+
+    for account_id in account_ids {
+        workers.spawn(record_orders(
+            journal.clone(),
+            source.clone(),
+            account_id.clone(),
+        ));
+        workers.spawn(record_trades(journal.clone(), source.clone(), account_id));
+    }
+
+    tokio::spawn(async move {
+        let _ = shutdown_receiver.await;
+        workers.shutdown().await;
+        log::info!("Journal recorder shutting down");
+    });
+
+    Arc::new(ShutdownGuard {
+        sender: Some(shutdown_sender),
+    })
+}
+
+async fn record_orders(journal: Journal, source: JournalSource, account_id: String) {
+    loop {
+        match source.orders(&account_id).await {
+            Ok(mut stream) => {
+                while let Some(state) = stream.next().await {
+                    match state {
+                        Ok(state) => journal.record_order_update(&state),
+                        Err(error) => {
+                            log::warn!("Journal order stream for {account_id} failed: {error:?}");
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(error) => {
+                log::warn!("Failed to subscribe journal to orders of {account_id}: {error:?}");
+            }
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn record_trades(journal: Journal, source: JournalSource, account_id: String) {
+    loop {
+        match source.trades(&account_id).await {
+            Ok(mut stream) => {
+                while let Some(trade) = stream.next().await {
+                    match trade {
+                        Ok(trade) => journal.record_trade(&trade),
+                        Err(error) => {
+                            log::warn!("Journal trade stream for {account_id} failed: {error:?}");
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(error) => {
+                log::warn!("Failed to subscribe journal to trades of {account_id}: {error:?}");
+            }
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Дописывает записи в файл, синхронизируя его с диском после каждой пачки.
+async fn write_records(
+    mut file: tokio::fs::File,
+    mut receiver: mpsc::UnboundedReceiver<JournalCommand>,
+) {
+    let mut failed = false;
+
+    while let Some(command) = receiver.recv().await {
+        let mut buffer = Vec::new();
+        let mut waiters = Vec::new();
+        let mut next = Some(command);
+
+        while let Some(command) = next {
+            match command {
+                JournalCommand::Record(record) => record
+                    .encode_length_delimited(&mut buffer)
+                    .expect("vector has unlimited capacity"),
+                JournalCommand::Flush(waiter) => waiters.push(waiter),
+            }
+            next = receiver.try_recv().ok();
+        }
+
+        let result = async {
+            file.write_all(&buffer).await?;
+            file.sync_data().await
+        }
+        .await;
+
+        if let Err(error) = &result
+            && !failed
+        {
+            log::error!("Failed to write journal: {:?}", error);
+        }
+        failed = result.is_err();
+
+        for waiter in waiters {
+            let _ = waiter.send(match &result {
+                Ok(()) => Ok(()),
+                Err(error) => Err(std::io::Error::new(error.kind(), error.to_string())),
+            });
+        }
+    }
+}
+
+/// Условия выборки записей журнала.
+///
+/// Незаполненные поля не ограничивают выборку.
+#[derive(Clone, Debug, Default)]
+pub struct JournalQuery {
+    /// Идентификатор аккаунта.
+    pub account_id: Option<String>,
+    /// Символ инструмента.
+    pub symbol: Option<String>,
+    /// Начало периода включительно.
+    pub from: Option<SystemTime>,
+    /// Окончание периода не включительно.
+    pub to: Option<SystemTime>,
+}
+
+impl JournalQuery {
+    /// Проверяет, подходит ли запись под условия.
+    pub fn matches(&self, record: &JournalRecord) -> bool {
+        let recorded_at = record.recorded_at();
+
+        self.account_id
+            .as_ref()
+            .is_none_or(|account_id| *account_id == record.account_id)
+            && self
+                .symbol
+                .as_ref()
+                .is_none_or(|symbol| *symbol == record.symbol)
+            && self
+                .from
+                .is_none_or(|from| recorded_at.is_some_and(|at| at >= from))
+            && self
+                .to
+                .is_none_or(|to| recorded_at.is_some_and(|at| at < to))
+    }
+}
+
+/// Чтение журнала для воспроизведения и анализа.
+#[derive(Clone, Debug)]
+pub struct JournalReader {
+    records: Vec<JournalRecord>,
+    truncated: bool,
+    complete_len: u64,
+}
+
+impl JournalReader {
+    /// Читает журнал целиком.
+    ///
+    /// Недописанная последняя запись пропускается.
+    ///
+    /// # Аргументы
+    ///
+    /// * `path` - Путь к файлу журнала.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Прочитанный журнал или ошибку чтения файла.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, FinamSdkError> {
+        Ok(Self::from_bytes(&tokio::fs::read(path).await?))
+    }
+
+    /// Разбирает содержимое журнала.
+    ///
+    /// # Аргументы
+    ///
+    /// * `bytes` - Содержимое файла журнала.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Self` - Прочитанный журнал.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut records = Vec::new();
+        let mut rest = bytes;
+
+        while !rest.is_empty() {
+            let complete_len = (bytes.len() - rest.len()) as u64;
+
+            match JournalRecord::decode_length_delimited(&mut rest) {
+                Ok(record) => records.push(record),
+                Err(_) => {
+                    return Self {
+                        records,
+                        truncated: true,
+                        complete_len,
+                    };
+                }
+            }
+        }
+
+        Self {
+            records,
+            truncated: false,
+            complete_len: bytes.len() as u64,
+        }
+    }
+
+    /// Возвращает все записи в порядке добавления.
+    pub fn records(&self) -> &[JournalRecord] {
+        &self.records
+    }
+
+    /// Проверяет, закончился ли журнал недописанной записью.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Возвращает размер в байтах, занимаемый полностью записанными записями.
+    pub fn complete_len(&self) -> u64 {
+        self.complete_len
+    }
+
+    /// Возвращает записи, подходящие под условия, в порядке добавления.
+    ///
+    /// # Аргументы
+    ///
+    /// * `query` - Условия выборки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `impl Iterator<Item = &JournalRecord>` - Подходящие записи.
+    pub fn query<'a>(
+        &'a self,
+        query: &'a JournalQuery,
+    ) -> impl Iterator<Item = &'a JournalRecord> + 'a {
+        self.records
+            .iter()
+            .filter(move |record| query.matches(record))
+    }
+}
+
+fn journal_closed() -> FinamSdkError {
+    FinamSdkError::Io(std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "journal writer stopped",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(account_id: &str, symbol: &str) -> Order {
+        Order {
+            account_id: account_id.to_string(),
+            symbol: symbol.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_journal_roundtrip_and_query() {
+        let path = std::env::temp_dir().join(format!(
+            "finam-journal-{}-{:?}.bin",
+            std::process::id(),
+            SystemTime::now()
+        ));
+
+        let journal = Journal::open(&path).await.unwrap();
+        journal.record_order(&order("A1", "SBER@MISX"));
+        journal.record_response(
+            "A1",
+            "SBER@MISX",
+            &Err(FinamSdkError::TradingHalted),
+            Duration::from_millis(3),
+        );
+        journal.record_order(&order("A2", "GAZP@MISX"));
+        journal.flush().await.unwrap();
+        drop(journal);
+
+        let journal = Journal::open(&path).await.unwrap();
+        journal.record_order(&order("A1", "GAZP@MISX"));
+        journal.flush().await.unwrap();
+
+        let reader = JournalReader::open(&path).await.unwrap();
+        let _ = tokio::fs::remove_file(&path).await;
+
+        assert!(!reader.is_truncated());
+        assert_eq!(
+            reader
+                .records()
+                .iter()
+                .map(|record| record.sequence)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(
+            reader.records()[1].latency(),
+            Some(Duration::from_millis(3))
+        );
+
+        let query = JournalQuery {
+            account_id: Some("A1".to_string()),
+            symbol: Some("SBER@MISX".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(reader.query(&query).count(), 2);
+    }
+
+    #[test]
+    fn test_reader_skips_incomplete_tail() {
+        let mut bytes = Vec::new();
+        let record = JournalRecord {
+            sequence: 1,
+            entry: Some(Entry::Error("failed".to_string())),
+            ..Default::default()
+        };
+        record.encode_length_delimited(&mut bytes).unwrap();
+        record.encode_length_delimited(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() - 2);
+
+        let reader = JournalReader::from_bytes(&bytes);
+        assert_eq!(reader.records().len(), 1);
+        assert!(reader.is_truncated());
+        assert_eq!(
+            reader.complete_len(),
+            record.encode_length_delimited_to_vec().len() as u64
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_truncates_incomplete_tail_before_append() {
+        let path = std::env::temp_dir().join(format!(
+            "finam-journal-tail-{}-{:?}.bin",
+            std::process::id(),
+            SystemTime::now()
+        ));
+
+        let journal = Journal::open(&path).await.unwrap();
+        journal.record_order(&order("A1", "SBER@MISX"));
+        journal.flush().await.unwrap();
+        drop(journal);
+
+        let mut bytes = tokio::fs::read(&path).await.unwrap();
+        let mut partial = Vec::new();
+        JournalRecord {
+            sequence: 2,
+            entry: Some(Entry::Error("crashed".to_string())),
+            ..Default::default()
+        }
+        .encode_length_delimited(&mut partial)
+        .unwrap();
+        bytes.extend_from_slice(&partial[..partial.len() - 3]);
+        tokio::fs::write(&path, &bytes).await.unwrap();
+
+        let journal = Journal::open(&path).await.unwrap();
+        journal.record_order(&order("A1", "GAZP@MISX"));
+        journal.flush().await.unwrap();
+
+        let reader = JournalReader::open(&path).await.unwrap();
+        let _ = tokio::fs::remove_file(&path).await;
+
+        assert!(!reader.is_truncated());
+        assert_eq!(
+            reader
+                .records()
+                .iter()
+                .map(|record| record.symbol.as_str())
+                .collect::<Vec<_>>(),
+            vec!["SBER@MISX", "GAZP@MISX"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_events_are_recorded_once() {
+        let path = std::env::temp_dir().join(format!(
+            "finam-journal-dedupe-{}-{:?}.bin",
+            std::process::id(),
+            SystemTime::now()
+        ));
+
+        let state = OrderState {
+            order_id: "1".to_string(),
+            order: Some(order("A1", "SBER@MISX")),
+            ..Default::default()
+        };
+        let trade = AccountTrade {
+            trade_id: "T1".to_string(),
+            account_id: "A1".to_string(),
+            ..Default::default()
+        };
+
+        let journal = Journal::open(&path).await.unwrap();
+        journal.record_order_update(&state);
+        journal.record_order_update(&state);
+        journal.record_trade(&trade);
+        journal.record_trade(&trade);
+        journal.flush().await.unwrap();
+        drop(journal);
+
+        let journal = Journal::open(&path).await.unwrap();
+        journal.record_order_update(&state);
+        journal.record_trade(&trade);
+        journal.record_order_update(&OrderState {
+            status: 1,
+            ..state.clone()
+        });
+        journal.flush().await.unwrap();
+
+        let reader = JournalReader::open(&path).await.unwrap();
+        let _ = tokio::fs::remove_file(&path).await;
+
+        assert_eq!(reader.records().len(), 3);
+    }
+}
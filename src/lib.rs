@@ -1,4 +1,4 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, atomic::AtomicBool};
 
 use thiserror::Error;
 use tokio::sync::oneshot;
@@ -8,15 +8,52 @@ use tonic::{
     transport::{Channel, ClientTlsConfig},
 };
 
-use crate::proto::grpc::tradeapi::v1::{
-    accounts::accounts_service_client::AccountsServiceClient,
-    assets::assets_service_client::AssetsServiceClient,
-    auth::{AuthRequest, auth_service_client::AuthServiceClient},
-    marketdata::market_data_service_client::MarketDataServiceClient,
-    orders::orders_service_client::OrdersServiceClient,
+use crate::{
+    clock::ServerClock,
+    journal::{Journal, JournalSource},
+    paper::{PaperBroker, PaperTradingConfig},
+    proto::grpc::tradeapi::v1::{
+        accounts::accounts_service_client::AccountsServiceClient,
+        assets::assets_service_client::AssetsServiceClient,
+        auth::{
+            AuthRequest, TokenDetailsRequest, TokenDetailsResponse,
+            auth_service_client::AuthServiceClient,
+        },
+        marketdata::market_data_service_client::MarketDataServiceClient,
+        orders::{OrderState, OrderStatus, orders_service_client::OrdersServiceClient},
+    },
+    risk::{RiskEngine, RiskRule, RiskViolation},
 };
 
+pub mod calendar;
+pub mod catalog;
+mod child;
+pub mod clock;
+pub mod continuous;
+pub mod decimal;
+pub mod execution;
+pub mod history;
+pub mod iceberg;
+pub mod journal;
+pub mod margin;
+pub mod money;
+pub mod multileg;
+pub mod oco;
+pub mod paper;
+pub mod pnl;
+pub mod portfolio;
 pub mod proto;
+pub mod rate_limit;
+pub mod reconcile;
+pub mod risk;
+pub mod search;
+pub mod sltp;
+pub mod statement;
+pub mod symbol;
+pub mod tracker;
+pub mod trading;
+pub mod trailing;
+mod util;
 
 pub type FinamAccountsServiceClient =
     AccountsServiceClient<InterceptedService<Channel, FinamSdkInterceptor>>;
@@ -41,6 +78,21 @@ pub struct FinamSdk {
     auth: FinamAuthServiceClient,
     market_data: FinamMarketDataServiceClient,
     orders: FinamOrdersServiceClient,
+    /// Флаг аварийной остановки торговли. При установке SDK отклоняет новые заявки.
+    trading_halted: Arc<AtomicBool>,
+    /// Правила предторговых проверок заявок.
+    risk: RiskEngine,
+    /// Симулятор исполнения заявок в режиме бумажной торговли.
+    paper: Option<PaperBroker>,
+    /// Журнал заявок и сделок.
+    journal: Option<Journal>,
+    /// Удерживает фоновую запись событий заявок и сделок в журнал.
+    #[allow(dead_code)]
+    journal_recorder: Option<Arc<ShutdownGuard>>,
+    /// Интерцептор, хранящий текущий JWT токен.
+    interceptor: FinamSdkInterceptor,
+    /// Оценка времени сервера.
+    clock: ServerClock,
 }
 
 impl FinamSdk {
@@ -66,24 +118,41 @@ impl FinamSdk {
     /// }
     /// ```
     pub async fn new(secret: &str) -> Result<Self, FinamSdkError> {
-        let tls = ClientTlsConfig::new().with_native_roots();
-        let channel = Channel::from_static("https://api.finam.ru")
-            .tls_config(tls)?
-            .connect()
-            .await?;
-
-        let interceptor = FinamSdkInterceptor::new(secret, channel.clone()).await?;
+        Self::builder(secret).build().await
+    }
 
-        Ok(Self {
-            accounts: AccountsServiceClient::with_interceptor(channel.clone(), interceptor.clone()),
-            assets: AssetsServiceClient::with_interceptor(channel.clone(), interceptor.clone()),
-            auth: AuthServiceClient::with_interceptor(channel.clone(), interceptor.clone()),
-            market_data: MarketDataServiceClient::with_interceptor(
-                channel.clone(),
-                interceptor.clone(),
-            ),
-            orders: OrdersServiceClient::with_interceptor(channel.clone(), interceptor.clone()),
-        })
+    /// Создает построитель клиента SDK Финам с дополнительными настройками.
+    ///
+    /// # Аргументы
+    ///
+    /// * `secret` - Секретный ключ API для аутентификации в API Финам.
+    ///
+    /// # Возвращает
+    ///
+    /// * `FinamSdkBuilder` - Построитель клиента SDK.
+    ///
+    /// # Пример
+    ///
+    /// ```no_run
+    /// use finam::{FinamSdk, risk::MaxOpenOrders};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let sdk = FinamSdk::builder("your_secret_key")
+    ///         .risk_rule(MaxOpenOrders::new(10))
+    ///         .build()
+    ///         .await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn builder(secret: &str) -> FinamSdkBuilder {
+        FinamSdkBuilder {
+            secret: secret.to_string(),
+            risk: RiskEngine::default(),
+            paper: None,
+            journal: None,
+            clock_sync: None,
+        }
     }
 
     /// Возвращает клиент для работы со счетами.
@@ -145,6 +214,172 @@ impl FinamSdk {
     pub fn orders(&self) -> FinamOrdersServiceClient {
         self.orders.clone()
     }
+
+    /// Возвращает журнал заявок и сделок, если он включен.
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    /// Получает информацию о текущем JWT токене, включая доступные аккаунты.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<TokenDetailsResponse, FinamSdkError>` - Информацию о токене или ошибку.
+    pub async fn token_details(&self) -> Result<TokenDetailsResponse, FinamSdkError> {
+        let token = self.interceptor.get_jwt_token()?;
+
+        Ok(self
+            .auth()
+            .token_details(TokenDetailsRequest { token })
+            .await?
+            .into_inner())
+    }
+}
+
+/// Построитель клиента SDK Финам.
+///
+/// Позволяет настроить дополнительные слои обработки заявок перед подключением к API.
+#[derive(Debug)]
+pub struct FinamSdkBuilder {
+    secret: String,
+    risk: RiskEngine,
+    paper: Option<PaperTradingConfig>,
+    journal: Option<std::path::PathBuf>,
+    clock_sync: Option<std::time::Duration>,
+}
+
+impl FinamSdkBuilder {
+    /// Добавляет правило предторговой проверки заявок.
+    ///
+    /// Все заявки, выставляемые через `FinamSdk::place_order` и
+    /// `FinamSdk::place_sltp_order`, проходят проверку добавленными правилами.
+    ///
+    /// # Аргументы
+    ///
+    /// * `rule` - Правило риск-менеджмента.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Self` - Построитель с добавленным правилом.
+    pub fn risk_rule(mut self, rule: impl RiskRule + 'static) -> Self {
+        self.risk.add_rule(rule);
+        self
+    }
+
+    /// Включает режим бумажной торговли.
+    ///
+    /// Рыночные данные и справочники по-прежнему запрашиваются из API Финам, а
+    /// `place_order`, `cancel_order`, `get_order`, `get_orders`, `subscribe_orders`,
+    /// `subscribe_trades` и `get_account` обслуживаются симулятором, исполняющим
+    /// заявки по живым котировкам и сделкам.
+    ///
+    /// # Аргументы
+    ///
+    /// * `config` - Настройки бумажной торговли.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Self` - Построитель с включенной бумажной торговлей.
+    pub fn paper_trading(mut self, config: PaperTradingConfig) -> Self {
+        self.paper = Some(config);
+        self
+    }
+
+    /// Включает журнал заявок и сделок.
+    ///
+    /// В журнал записываются запросы выставления и отмены заявок, ответы сервера
+    /// с временем ответа, а также обновления заявок и сделки всех аккаунтов токена.
+    /// События получаются из собственных подписок SDK и записываются по одному разу
+    /// независимо от числа подписчиков `subscribe_orders` и `subscribe_trades`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `path` - Путь к файлу журнала.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Self` - Построитель с включенным журналом.
+    pub fn journal(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.journal = Some(path.into());
+        self
+    }
+
+    /// Включает фоновую синхронизацию с часами сервера.
+    ///
+    /// SDK периодически вызывает `clock` и оценивает смещение локальных часов.
+    /// Оценка используется при проверке срока действия заявок, в бумажной
+    /// торговле и в торговых календарях.
+    ///
+    /// # Аргументы
+    ///
+    /// * `interval` - Интервал между замерами.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Self` - Построитель с включенной синхронизацией часов.
+    pub fn clock_sync(mut self, interval: std::time::Duration) -> Self {
+        self.clock_sync = Some(interval);
+        self
+    }
+
+    /// Подключается к API Финам и создает клиент SDK.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<FinamSdk, FinamSdkError>` - Экземпляр SDK при успешном создании или ошибку.
+    pub async fn build(self) -> Result<FinamSdk, FinamSdkError> {
+        let tls = ClientTlsConfig::new().with_native_roots();
+        let channel = Channel::from_static("https://api.finam.ru")
+            .tls_config(tls)?
+            .connect()
+            .await?;
+
+        let interceptor = FinamSdkInterceptor::new(&self.secret, channel.clone()).await?;
+        let journal = match &self.journal {
+            Some(path) => Some(Journal::open(path).await?),
+            None => None,
+        };
+        let market_data =
+            MarketDataServiceClient::with_interceptor(channel.clone(), interceptor.clone());
+        let assets = AssetsServiceClient::with_interceptor(channel.clone(), interceptor.clone());
+        let clock = match self.clock_sync {
+            Some(interval) => ServerClock::start(assets.clone(), interval),
+            None => ServerClock::new(),
+        };
+
+        let paper = self.paper.map(|config| {
+            PaperBroker::start(market_data.clone(), assets.clone(), config, clock.clone())
+        });
+
+        let mut sdk = FinamSdk {
+            accounts: AccountsServiceClient::with_interceptor(channel.clone(), interceptor.clone()),
+            assets,
+            auth: AuthServiceClient::with_interceptor(channel.clone(), interceptor.clone()),
+            paper,
+            market_data,
+            orders: OrdersServiceClient::with_interceptor(channel.clone(), interceptor.clone()),
+            trading_halted: Arc::new(AtomicBool::new(false)),
+            risk: self.risk,
+            journal,
+            journal_recorder: None,
+            interceptor,
+            clock,
+        };
+
+        if let Some(journal) = sdk.journal.clone() {
+            let source = match &sdk.paper {
+                Some(paper) => JournalSource::Paper(paper.clone()),
+                None => JournalSource::Api(sdk.orders()),
+            };
+            sdk.journal_recorder = Some(journal::start_recorder(
+                journal,
+                source,
+                sdk.account_ids().await?,
+            ));
+        }
+
+        Ok(sdk)
+    }
 }
 
 /// Охранник для корректного завершения фонового потока обновления токена.
@@ -336,6 +571,60 @@ pub enum FinamSdkError {
     /// Ошибка при создании или обработке метаданных запроса.
     #[error(transparent)]
     InvalidMetadataValue(#[from] InvalidMetadataValue),
+
+    /// Десятичное значение в ответе API не удалось разобрать.
+    #[error("invalid decimal value: {0:?}")]
+    InvalidDecimal(String),
+
+    /// Некорректные параметры запроса.
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
+    /// Символ инструмента не соответствует форме `ticker@mic`.
+    #[error("invalid symbol: {0:?}")]
+    InvalidSymbol(String),
+
+    /// Операция не завершилась за отведенное время.
+    #[error("timeout: {0}")]
+    Timeout(String),
+
+    /// Заявка была исполнена до того, как ее удалось отменить.
+    #[error("order {0} was filled before it could be canceled")]
+    OrderFilled(String),
+
+    /// Заявка находится в конечном статусе и не может быть изменена.
+    #[error("order {order_id} is not active: {}", status.as_str_name())]
+    OrderNotActive {
+        order_id: String,
+        status: OrderStatus,
+    },
+
+    /// Торговля остановлена аварийным выключателем, новые заявки не принимаются.
+    #[error("trading is halted by the kill switch")]
+    TradingHalted,
+
+    /// Заявка отклонена предторговой проверкой риск-менеджмента и не была отправлена.
+    #[error("order rejected by risk check: {0}")]
+    RiskRejected(RiskViolation),
+
+    /// Исходная заявка отменена, но выставить новую не удалось.
+    #[error("order {} was canceled, but the replacement failed: {source}", canceled.order_id)]
+    ReplaceFailed {
+        canceled: Box<OrderState>,
+        source: Box<FinamSdkError>,
+    },
+
+    /// Операция над денежными суммами в разных валютах.
+    #[error("currency mismatch: expected {expected}, got {actual}")]
+    CurrencyMismatch { expected: String, actual: String },
+
+    /// Ошибка чтения или записи файла.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// Ошибка сериализации или разбора JSON.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
 }
 
 #[cfg(test)]
@@ -0,0 +1,656 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use tokio::sync::{broadcast, oneshot};
+
+use crate::{
+    FinamSdk, FinamSdkError, ShutdownGuard,
+    decimal::{Decimal, RoundingStrategy, money_amount, opt_decimal, round_to_step},
+    portfolio::{Portfolio, PortfolioTracker},
+    proto::grpc::tradeapi::v1::{
+        Side,
+        accounts::GetAccountResponse,
+        assets::{
+            GetAssetParamsRequest, GetAssetParamsResponse, GetAssetRequest, longable, shortable,
+        },
+        marketdata::QuoteRequest,
+    },
+};
+
+/// Емкость канала рассылки предупреждений о марже.
+const ALERTS_CAPACITY: usize = 256;
+
+/// Маржинальные показатели аккаунта.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarginMetrics {
+    /// Доступные средства плюс стоимость открытых позиций.
+    pub equity: Decimal,
+    /// Разобранный портфель аккаунта.
+    pub portfolio: Option<Portfolio>,
+    /// Требование к обеспечению: минимальная маржа для счетов Московской Биржи,
+    /// зарезервированные средства для срочного рынка.
+    pub requirement: Decimal,
+    /// Доля собственных средств, занятая обеспечением. `None`, если средства
+    /// не положительны при ненулевом требовании.
+    pub utilization: Option<Decimal>,
+    /// Доля собственных средств, занятая начальной маржой, для счетов Московской Биржи.
+    pub initial_utilization: Option<Decimal>,
+}
+
+impl MarginMetrics {
+    /// Рассчитывает маржинальные показатели по информации об аккаунте.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account` - Информация об аккаунте.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Показатели или ошибку разбора.
+    pub fn from_account(account: &GetAccountResponse) -> Result<Self, FinamSdkError> {
+        let equity = opt_decimal(account.equity.as_ref())?;
+        let portfolio = Portfolio::from_account(account)?;
+        let requirement = portfolio
+            .and_then(|portfolio| {
+                portfolio
+                    .maintenance_margin()
+                    .or(portfolio.money_reserved())
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            equity,
+            portfolio,
+            requirement,
+            utilization: ratio(requirement, equity),
+            initial_utilization: portfolio
+                .and_then(|portfolio| portfolio.initial_margin())
+                .and_then(|margin| ratio(margin, equity)),
+        })
+    }
+
+    /// Возвращает превышение собственных средств над требованием к обеспечению.
+    pub fn excess(&self) -> Decimal {
+        self.equity - self.requirement
+    }
+}
+
+/// Уровень маржинальной нагрузки.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MarginLevel {
+    /// Нагрузка ниже порога предупреждения.
+    #[default]
+    Normal,
+    /// Нагрузка достигла порога предупреждения.
+    Warning,
+    /// Нагрузка достигла критического порога.
+    Critical,
+}
+
+/// Пороги маржинальной нагрузки в долях от собственных средств.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarginThresholds {
+    /// Порог предупреждения.
+    pub warning: Decimal,
+    /// Критический порог.
+    pub critical: Decimal,
+}
+
+impl Default for MarginThresholds {
+    fn default() -> Self {
+        Self {
+            warning: Decimal::new(7, 1),
+            critical: Decimal::new(9, 1),
+        }
+    }
+}
+
+impl MarginThresholds {
+    /// Определяет уровень нагрузки по показателям аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `metrics` - Маржинальные показатели.
+    ///
+    /// # Возвращает
+    ///
+    /// * `MarginLevel` - Уровень нагрузки.
+    pub fn level(&self, metrics: &MarginMetrics) -> MarginLevel {
+        match metrics.utilization {
+            None => MarginLevel::Critical,
+            Some(utilization) if utilization >= self.critical => MarginLevel::Critical,
+            Some(utilization) if utilization >= self.warning => MarginLevel::Warning,
+            Some(_) => MarginLevel::Normal,
+        }
+    }
+}
+
+/// Предупреждение об изменении уровня маржинальной нагрузки.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarginAlert {
+    /// Идентификатор аккаунта.
+    pub account_id: String,
+    /// Новый уровень нагрузки.
+    pub level: MarginLevel,
+    /// Предыдущий уровень нагрузки.
+    pub previous: MarginLevel,
+    /// Показатели, на которых сменился уровень.
+    pub metrics: MarginMetrics,
+}
+
+/// Следит за маржинальной нагрузкой аккаунтов по снимкам [`PortfolioTracker`].
+///
+/// Рассылает [`MarginAlert`] при каждой смене уровня, в том числе при
+/// возврате к нормальному. Фоновая задача завершается при уничтожении
+/// последнего клона монитора или трекера портфеля.
+#[derive(Clone, Debug)]
+pub struct MarginMonitor {
+    state: Arc<MonitorState>,
+    /// Удерживает фоновую задачу. При уничтожении последней ссылки
+    /// на монитор отправляет сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Arc<ShutdownGuard>,
+}
+
+impl MarginMonitor {
+    /// Запускает наблюдение за аккаунтами трекера портфеля.
+    ///
+    /// Текущие снимки оцениваются сразу. Исходным уровнем аккаунта считается
+    /// `MarginLevel::Normal`, поэтому уже повышенная нагрузка также порождает
+    /// предупреждение. Оно записывается в лог и доступно через [`MarginMonitor::status`],
+    /// так как подписаться на рассылку до создания монитора нельзя.
+    ///
+    /// # Аргументы
+    ///
+    /// * `tracker` - Трекер портфеля.
+    /// * `thresholds` - Пороги нагрузки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Монитор или ошибку разбора текущих снимков.
+    pub fn start(
+        tracker: &PortfolioTracker,
+        thresholds: MarginThresholds,
+    ) -> Result<Self, FinamSdkError> {
+        let (alerts, _) = broadcast::channel(ALERTS_CAPACITY);
+        let (shutdown_sender, mut shutdown_receiver) = oneshot::channel();
+        let mut snapshots = tracker.subscribe_snapshots();
+
+        let monitor = Self {
+            state: Arc::new(MonitorState {
+                thresholds,
+                statuses: RwLock::new(HashMap::new()),
+                alerts,
+            }),
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        };
+
+        for account in tracker.snapshots() {
+            monitor.evaluate(&account)?;
+        }
+
+        let state = monitor.state.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_receiver => {
+                        log::info!("Margin monitor shutting down");
+                        return;
+                    }
+                    result = snapshots.recv() => match result {
+                        Ok(account) => {
+                            if let Err(error) = state.evaluate(&account) {
+                                log::error!(
+                                    "Failed to evaluate margin for {}: {:?}",
+                                    account.account_id,
+                                    error
+                                );
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => return,
+                    },
+                }
+            }
+        });
+
+        Ok(monitor)
+    }
+
+    /// Возвращает последний уровень нагрузки и показатели аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    pub fn status(&self, account_id: &str) -> Option<(MarginLevel, MarginMetrics)> {
+        self.state
+            .statuses
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+            .get(account_id)
+            .copied()
+    }
+
+    /// Подписывается на предупреждения о смене уровня нагрузки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `broadcast::Receiver<MarginAlert>` - Получатель предупреждений.
+    pub fn subscribe(&self) -> broadcast::Receiver<MarginAlert> {
+        self.state.alerts.subscribe()
+    }
+
+    /// Оценивает снимок аккаунта и рассылает предупреждение при смене уровня.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account` - Снимок аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Option<MarginAlert>, FinamSdkError>` - Разосланное предупреждение, если уровень сменился, или ошибку разбора.
+    pub fn evaluate(
+        &self,
+        account: &GetAccountResponse,
+    ) -> Result<Option<MarginAlert>, FinamSdkError> {
+        self.state.evaluate(account)
+    }
+}
+
+/// Общее состояние монитора, используемое фоновой задачей.
+#[derive(Debug)]
+struct MonitorState {
+    thresholds: MarginThresholds,
+    statuses: RwLock<HashMap<String, (MarginLevel, MarginMetrics)>>,
+    alerts: broadcast::Sender<MarginAlert>,
+}
+
+impl MonitorState {
+    fn evaluate(&self, account: &GetAccountResponse) -> Result<Option<MarginAlert>, FinamSdkError> {
+        let metrics = MarginMetrics::from_account(account)?;
+        let level = self.thresholds.level(&metrics);
+
+        let previous = self
+            .statuses
+            .write()
+            .unwrap_or_else(|error| error.into_inner())
+            .insert(account.account_id.clone(), (level, metrics))
+            .map_or(MarginLevel::Normal, |(level, _)| level);
+
+        // The first snapshot is compared with Normal so an existing risk is reported
+        if previous == level {
+            return Ok(None);
+        }
+
+        let alert = MarginAlert {
+            account_id: account.account_id.clone(),
+            level,
+            previous,
+            metrics,
+        };
+
+        if level > previous {
+            log::warn!(
+                "Margin level for {} raised to {:?}: {:?}",
+                alert.account_id,
+                level,
+                metrics.utilization
+            );
+        }
+
+        let _ = self.alerts.send(alert.clone());
+
+        Ok(Some(alert))
+    }
+}
+
+/// Оценка покупательной способности по инструменту.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuyingPower {
+    /// Сторона заявки.
+    pub side: Side,
+    /// Разрешены ли операции в эту сторону.
+    pub allowed: bool,
+    /// Свободные средства аккаунта.
+    pub available_funds: Decimal,
+    /// Требуемое обеспечение на одну штуку.
+    pub margin_per_unit: Decimal,
+    /// Максимальное количество в шт., кратное лоту.
+    pub max_quantity: Decimal,
+}
+
+/// Оценивает покупательную способность по торговым параметрам инструмента.
+///
+/// Требование на штуку берется из начальных требований для стороны, при их
+/// отсутствии рассчитывается по ставке риска в процентах от цены, а без ставки
+/// равно цене. Если свободные средства не указаны в портфеле аккаунта,
+/// учитываются только денежные средства в валюте инструмента.
+///
+/// # Аргументы
+///
+/// * `account` - Информация об аккаунте.
+/// * `params` - Торговые параметры инструмента для аккаунта.
+/// * `price` - Цена инструмента.
+/// * `lot_size` - Количество штук в лоте. Ноль отключает округление до лота.
+/// * `currency` - Код валюты инструмента ISO 4217.
+/// * `side` - Сторона заявки.
+///
+/// # Возвращает
+///
+/// * `Result<BuyingPower, FinamSdkError>` - Оценка или ошибку разбора.
+pub fn estimate_buying_power(
+    account: &GetAccountResponse,
+    params: &GetAssetParamsResponse,
+    price: Decimal,
+    lot_size: Decimal,
+    currency: &str,
+    side: Side,
+) -> Result<BuyingPower, FinamSdkError> {
+    let available_funds = match Portfolio::from_account(account)?.and_then(|p| p.available_cash()) {
+        Some(cash) => cash,
+        None => account
+            .cash
+            .iter()
+            .filter(|money| money.currency_code == currency)
+            .map(money_amount)
+            .sum(),
+    };
+
+    let (allowed, initial_margin, risk_rate) = match side {
+        Side::Buy => (
+            params
+                .longable
+                .is_some_and(|longable| longable.value() == longable::Status::Available),
+            params.long_initial_margin.as_ref(),
+            params.long_risk_rate.as_ref(),
+        ),
+        Side::Sell => (
+            params.shortable.is_some_and(|shortable| {
+                matches!(
+                    shortable.value(),
+                    shortable::Status::Available | shortable::Status::Htb
+                )
+            }),
+            params.short_initial_margin.as_ref(),
+            params.short_risk_rate.as_ref(),
+        ),
+        Side::Unspecified => {
+            return Err(FinamSdkError::InvalidArgument(
+                "side must be specified".to_string(),
+            ));
+        }
+    };
+
+    let risk_rate = opt_decimal(risk_rate)?;
+    let margin_per_unit = match initial_margin.map(money_amount) {
+        Some(margin) if margin > Decimal::ZERO => margin,
+        _ if risk_rate > Decimal::ZERO => price * risk_rate / Decimal::ONE_HUNDRED,
+        _ => price,
+    };
+
+    let max_quantity =
+        if !allowed || margin_per_unit <= Decimal::ZERO || available_funds <= Decimal::ZERO {
+            Decimal::ZERO
+        } else {
+            round_to_step(
+                available_funds / margin_per_unit,
+                lot_size,
+                RoundingStrategy::ToZero,
+            )
+            .trunc()
+        };
+
+    Ok(BuyingPower {
+        side,
+        allowed,
+        available_funds,
+        margin_per_unit,
+        max_quantity,
+    })
+}
+
+impl FinamSdk {
+    /// Оценивает покупательную способность аккаунта по инструменту.
+    ///
+    /// Цена берется по аску для покупки и по биду для продажи, а при их
+    /// отсутствии по последней сделке.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `symbol` - Символ инструмента.
+    /// * `side` - Сторона заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<BuyingPower, FinamSdkError>` - Оценка или ошибку.
+    pub async fn buying_power(
+        &self,
+        account_id: &str,
+        symbol: impl AsRef<str>,
+        side: Side,
+    ) -> Result<BuyingPower, FinamSdkError> {
+        let symbol = symbol.as_ref();
+        let account = self.get_account(account_id).await?;
+        let params = self
+            .assets()
+            .get_asset_params(GetAssetParamsRequest {
+                symbol: symbol.to_string(),
+                account_id: account_id.to_string(),
+            })
+            .await?
+            .into_inner();
+        let asset = self
+            .assets()
+            .get_asset(GetAssetRequest {
+                symbol: symbol.to_string(),
+                account_id: account_id.to_string(),
+            })
+            .await?
+            .into_inner();
+        let quote = self
+            .market_data()
+            .last_quote(QuoteRequest {
+                symbol: symbol.to_string(),
+            })
+            .await?
+            .into_inner()
+            .quote
+            .unwrap_or_default();
+
+        let price = match side {
+            Side::Sell => opt_decimal(quote.bid.as_ref())?,
+            _ => opt_decimal(quote.ask.as_ref())?,
+        };
+        let price = if price.is_zero() {
+            opt_decimal(quote.last.as_ref())?
+        } else {
+            price
+        };
+
+        // Without a quote currency, fall back to the currency of the margin requirement
+        let margin = match side {
+            Side::Sell => params.short_initial_margin.as_ref(),
+            _ => params.long_initial_margin.as_ref(),
+        };
+        let currency = match (asset.quote_currency.as_str(), margin) {
+            ("", Some(margin)) => margin.currency_code.as_str(),
+            (currency, _) => currency,
+        };
+
+        estimate_buying_power(
+            &account,
+            &params,
+            price,
+            opt_decimal(asset.lot_size.as_ref())?,
+            currency,
+            side,
+        )
+    }
+}
+
+fn ratio(value: Decimal, equity: Decimal) -> Option<Decimal> {
+    if value.is_zero() {
+        Some(Decimal::ZERO)
+    } else if equity > Decimal::ZERO {
+        Some(value / equity)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        decimal::from_decimal,
+        proto::{
+            google::r#type::Money,
+            grpc::tradeapi::v1::{
+                accounts::{Mc, get_account_response},
+                assets::{Longable, Shortable},
+            },
+        },
+    };
+
+    fn account(equity: i64, available_cash: i64, maintenance_margin: i64) -> GetAccountResponse {
+        GetAccountResponse {
+            account_id: "A1".to_string(),
+            equity: Some(from_decimal(Decimal::from(equity))),
+            portfolio: Some(get_account_response::Portfolio::PortfolioMc(Mc {
+                available_cash: Some(from_decimal(Decimal::from(available_cash))),
+                initial_margin: Some(from_decimal(Decimal::from(maintenance_margin * 2))),
+                maintenance_margin: Some(from_decimal(Decimal::from(maintenance_margin))),
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn monitor() -> MonitorState {
+        let (alerts, _) = broadcast::channel(16);
+
+        MonitorState {
+            thresholds: MarginThresholds::default(),
+            statuses: RwLock::new(HashMap::new()),
+            alerts,
+        }
+    }
+
+    #[test]
+    fn test_levels_change_on_thresholds() {
+        let monitor = monitor();
+
+        assert_eq!(monitor.evaluate(&account(1000, 500, 500)).unwrap(), None);
+
+        let mut critical = account(0, 0, 760);
+        critical.account_id = "A2".to_string();
+        let alert = monitor.evaluate(&critical).unwrap().unwrap();
+        assert_eq!(alert.level, MarginLevel::Critical);
+        assert_eq!(alert.previous, MarginLevel::Normal);
+
+        let alert = monitor.evaluate(&account(1000, 100, 750)).unwrap().unwrap();
+        assert_eq!(alert.level, MarginLevel::Warning);
+        assert_eq!(alert.metrics.utilization, Some(Decimal::new(75, 2)));
+        assert_eq!(alert.metrics.excess(), Decimal::from(250));
+
+        assert_eq!(monitor.evaluate(&account(1000, 100, 760)).unwrap(), None);
+
+        let alert = monitor.evaluate(&account(0, 0, 760)).unwrap().unwrap();
+        assert_eq!(alert.level, MarginLevel::Critical);
+        assert_eq!(alert.previous, MarginLevel::Warning);
+
+        let alert = monitor.evaluate(&account(1000, 900, 0)).unwrap().unwrap();
+        assert_eq!(alert.level, MarginLevel::Normal);
+    }
+
+    #[test]
+    fn test_buying_power_estimate() {
+        let params = GetAssetParamsResponse {
+            longable: Some(Longable {
+                value: longable::Status::Available as i32,
+                halted_days: 0,
+            }),
+            shortable: Some(Shortable {
+                value: shortable::Status::NotAvailable as i32,
+                halted_days: 0,
+            }),
+            long_risk_rate: Some(from_decimal(Decimal::from(25))),
+            ..Default::default()
+        };
+        let account = account(100_000, 10_000, 0);
+
+        let long = estimate_buying_power(
+            &account,
+            &params,
+            Decimal::from(300),
+            Decimal::from(10),
+            "RUB",
+            Side::Buy,
+        )
+        .unwrap();
+        assert_eq!(long.margin_per_unit, Decimal::from(75));
+        assert_eq!(long.max_quantity, Decimal::from(130));
+
+        let short = estimate_buying_power(
+            &account,
+            &params,
+            Decimal::from(300),
+            Decimal::from(10),
+            "RUB",
+            Side::Sell,
+        )
+        .unwrap();
+        assert!(!short.allowed);
+        assert_eq!(short.max_quantity, Decimal::ZERO);
+
+        let params = GetAssetParamsResponse {
+            long_initial_margin: Some(Money {
+                currency_code: "RUB".to_string(),
+                units: 3000,
+                nanos: 500_000_000,
+            }),
+            ..params
+        };
+        let futures = estimate_buying_power(
+            &account,
+            &params,
+            Decimal::from(90_000),
+            Decimal::ONE,
+            "RUB",
+            Side::Buy,
+        )
+        .unwrap();
+        assert_eq!(futures.margin_per_unit, Decimal::new(30005, 1));
+        assert_eq!(futures.max_quantity, Decimal::from(3));
+
+        let cash_only = GetAccountResponse {
+            cash: vec![
+                Money {
+                    currency_code: "RUB".to_string(),
+                    units: 9_000,
+                    nanos: 0,
+                },
+                Money {
+                    currency_code: "USD".to_string(),
+                    units: 1_000,
+                    nanos: 0,
+                },
+            ],
+            ..Default::default()
+        };
+        let by_cash = estimate_buying_power(
+            &cash_only,
+            &params,
+            Decimal::from(90_000),
+            Decimal::ONE,
+            "RUB",
+            Side::Buy,
+        )
+        .unwrap();
+        assert_eq!(by_cash.available_funds, Decimal::from(9_000));
+    }
+}
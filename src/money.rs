@@ -0,0 +1,273 @@
+use std::fmt;
+
+use crate::{
+    FinamSdkError,
+    decimal::{Decimal, RoundingStrategy, from_decimal, money_amount},
+    proto::google::r#type::{Decimal as ProtoDecimal, Money},
+};
+
+/// Количество нано-единиц в одной единице валюты.
+const NANOS_PER_UNIT: i128 = 1_000_000_000;
+
+/// Пересчет денежных сумм между валютами.
+///
+/// Реализуется источниками курсов, например [`crate::portfolio::FxRates`],
+/// курсы которого загружаются по котировкам вроде `USD000UTSTOM@MISX`.
+pub trait CurrencyConverter {
+    /// Возвращает курс: количество единиц валюты `to` за единицу валюты `from`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `from` - Код исходной валюты ISO 4217.
+    /// * `to` - Код целевой валюты ISO 4217.
+    fn exchange_rate(&self, from: &str, to: &str) -> Option<Decimal>;
+
+    /// Пересчитывает сумму в другую валюту.
+    ///
+    /// # Аргументы
+    ///
+    /// * `money` - Исходная сумма.
+    /// * `to` - Код целевой валюты ISO 4217.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Money, FinamSdkError>` - Сумму в целевой валюте или ошибку, если курс неизвестен.
+    fn convert(&self, money: &Money, to: &str) -> Result<Money, FinamSdkError> {
+        if money.currency_code == to {
+            return Ok(money.normalized());
+        }
+
+        let rate = self
+            .exchange_rate(&money.currency_code, to)
+            .ok_or_else(|| {
+                FinamSdkError::InvalidArgument(format!(
+                    "no FX rate for {} to {to}",
+                    money.currency_code
+                ))
+            })?;
+
+        Money::from_decimal(money.amount() * rate, to)
+    }
+}
+
+impl Money {
+    /// Создает денежную сумму из числа.
+    ///
+    /// Сумма округляется до нано-единиц.
+    ///
+    /// # Аргументы
+    ///
+    /// * `amount` - Сумма.
+    /// * `currency_code` - Код валюты ISO 4217.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Сумму или ошибку, если целая часть не помещается в `i64`.
+    pub fn from_decimal(amount: Decimal, currency_code: &str) -> Result<Self, FinamSdkError> {
+        let amount = amount.round_dp_with_strategy(9, RoundingStrategy::MidpointNearestEven);
+        let units = i64::try_from(amount.trunc()).map_err(|_| {
+            FinamSdkError::InvalidArgument(format!("money amount {amount} is out of range"))
+        })?;
+        let nanos =
+            i32::try_from((amount.fract() * Decimal::from(1_000_000_000)).trunc()).unwrap_or(0);
+
+        Ok(Self {
+            currency_code: currency_code.to_string(),
+            units,
+            nanos,
+        })
+    }
+
+    /// Возвращает сумму без учета валюты: `units + nanos / 10^9`.
+    pub fn amount(&self) -> Decimal {
+        money_amount(self)
+    }
+
+    /// Проверяет, равна ли сумма нулю.
+    pub fn is_zero(&self) -> bool {
+        self.units == 0 && self.nanos == 0
+    }
+
+    /// Приводит сумму к каноническому виду `google.type.Money`.
+    ///
+    /// После нормализации `nanos` по модулю меньше 10^9, а `units` и `nanos`
+    /// не имеют разных знаков. Значения, не помещающиеся в `i64`, насыщаются.
+    pub fn normalized(&self) -> Self {
+        let total = i128::from(self.units) * NANOS_PER_UNIT + i128::from(self.nanos);
+        let units = total / NANOS_PER_UNIT;
+
+        Self {
+            currency_code: self.currency_code.clone(),
+            units: units.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64,
+            nanos: (total % NANOS_PER_UNIT) as i32,
+        }
+    }
+
+    /// Складывает суммы в одной валюте.
+    ///
+    /// # Аргументы
+    ///
+    /// * `other` - Вторая сумма.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Сумму или ошибку при разных валютах либо переполнении.
+    pub fn checked_add(&self, other: &Self) -> Result<Self, FinamSdkError> {
+        self.combine(other, 1)
+    }
+
+    /// Вычитает сумму в той же валюте.
+    ///
+    /// # Аргументы
+    ///
+    /// * `other` - Вычитаемая сумма.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Разность или ошибку при разных валютах либо переполнении.
+    pub fn checked_sub(&self, other: &Self) -> Result<Self, FinamSdkError> {
+        self.combine(other, -1)
+    }
+
+    /// Возвращает сумму с противоположным знаком.
+    pub fn negated(&self) -> Self {
+        Self {
+            currency_code: self.currency_code.clone(),
+            units: self.units.saturating_neg(),
+            nanos: -self.nanos,
+        }
+        .normalized()
+    }
+
+    /// Возвращает количество знаков после запятой валюты по ISO 4217.
+    ///
+    /// Для неизвестных валют возвращает 2.
+    pub fn minor_units(&self) -> u32 {
+        match self.currency_code.as_str() {
+            "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF"
+            | "UGX" | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+            "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+            "CLF" | "UYW" => 4,
+            _ => 2,
+        }
+    }
+
+    fn combine(&self, other: &Self, sign: i128) -> Result<Self, FinamSdkError> {
+        if self.currency_code != other.currency_code {
+            return Err(FinamSdkError::CurrencyMismatch {
+                expected: self.currency_code.clone(),
+                actual: other.currency_code.clone(),
+            });
+        }
+
+        let total = i128::from(self.units) * NANOS_PER_UNIT
+            + i128::from(self.nanos)
+            + sign * (i128::from(other.units) * NANOS_PER_UNIT + i128::from(other.nanos));
+        let units = i64::try_from(total / NANOS_PER_UNIT).map_err(|_| {
+            FinamSdkError::InvalidArgument(format!(
+                "money amount overflow in {}",
+                self.currency_code
+            ))
+        })?;
+
+        Ok(Self {
+            currency_code: self.currency_code.clone(),
+            units,
+            nanos: (total % NANOS_PER_UNIT) as i32,
+        })
+    }
+}
+
+/// Выводит сумму с количеством знаков валюты по ISO 4217, например `-1.75 USD`.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minor_units = self.minor_units();
+        let amount = self
+            .amount()
+            .round_dp_with_strategy(minor_units, RoundingStrategy::MidpointAwayFromZero);
+
+        write!(
+            f,
+            "{:.*} {}",
+            minor_units as usize, amount, self.currency_code
+        )
+    }
+}
+
+impl From<&Money> for Decimal {
+    fn from(value: &Money) -> Self {
+        value.amount()
+    }
+}
+
+impl From<&Money> for ProtoDecimal {
+    fn from(value: &Money) -> Self {
+        from_decimal(value.amount())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(currency_code: &str, units: i64, nanos: i32) -> Money {
+        Money {
+            currency_code: currency_code.to_string(),
+            units,
+            nanos,
+        }
+    }
+
+    #[test]
+    fn test_money_arithmetic_and_normalization() {
+        let price = Money::from_decimal(Decimal::new(-175, 2), "USD").unwrap();
+        assert_eq!(price, money("USD", -1, -750_000_000));
+        assert_eq!(price.to_string(), "-1.75 USD");
+
+        assert_eq!(
+            money("RUB", 1, -250_000_000).normalized(),
+            money("RUB", 0, 750_000_000)
+        );
+        assert_eq!(
+            money("RUB", 2, 1_500_000_000).normalized(),
+            money("RUB", 3, 500_000_000)
+        );
+
+        let sum = money("USD", 1, 500_000_000).checked_add(&price).unwrap();
+        assert_eq!(sum, money("USD", 0, -250_000_000));
+        assert_eq!(
+            money("USD", 1, 0).checked_sub(&price).unwrap(),
+            money("USD", 2, 750_000_000)
+        );
+        assert!(matches!(
+            price.checked_add(&money("RUB", 1, 0)),
+            Err(FinamSdkError::CurrencyMismatch { .. })
+        ));
+        assert!(
+            money("USD", i64::MAX, 0)
+                .checked_add(&money("USD", 1, 0))
+                .is_err()
+        );
+
+        assert_eq!(money("JPY", 1500, 600_000_000).to_string(), "1501 JPY");
+        assert_eq!(money("KWD", 2, 5_000_000).to_string(), "2.005 KWD");
+        assert_eq!(Decimal::from(&sum), Decimal::new(-25, 2));
+    }
+
+    #[test]
+    fn test_currency_converter_default_convert() {
+        struct Fixed;
+
+        impl CurrencyConverter for Fixed {
+            fn exchange_rate(&self, from: &str, to: &str) -> Option<Decimal> {
+                (from == "USD" && to == "RUB").then(|| Decimal::new(905, 1))
+            }
+        }
+
+        assert_eq!(
+            Fixed.convert(&money("USD", 2, 0), "RUB").unwrap(),
+            money("RUB", 181, 0)
+        );
+        assert!(Fixed.convert(&money("EUR", 2, 0), "RUB").is_err());
+    }
+}
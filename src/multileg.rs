@@ -0,0 +1,803 @@
+use std::collections::HashMap;
+
+use tonic::Code;
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    decimal::{Decimal, from_decimal, opt_decimal},
+    proto::{
+        google::r#type::Date,
+        grpc::tradeapi::v1::{
+            Side,
+            assets::{GetAssetRequest, OptionsChainResponse, option},
+            marketdata::{Quote, QuoteRequest, quote},
+            orders::{Leg, Order, OrderType, TimeInForce},
+        },
+    },
+};
+
+/// Тип опциона.
+pub type OptionType = option::Type;
+
+/// Построитель мульти лег заявки.
+///
+/// Количество в ноге задает соотношение: сколько единиц инструмента приходится
+/// на одну комбинацию. Количество заявки задает число комбинаций. Стороны ног
+/// описывают покупку комбинации, продажа комбинации задается через [`MultiLegBuilder::side`].
+///
+/// # Пример
+///
+/// ```no_run
+/// use finam::{
+///     FinamSdk,
+///     decimal::Decimal,
+///     multileg::{MultiLegBuilder, OptionType},
+///     proto::grpc::tradeapi::v1::assets::OptionsChainRequest,
+/// };
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let sdk = FinamSdk::new("your_secret_key").await?;
+///     let chain = sdk
+///         .assets()
+///         .options_chain(OptionsChainRequest {
+///             underlying_symbol: "SBER@MISX".to_string(),
+///             ..Default::default()
+///         })
+///         .await?
+///         .into_inner();
+///
+///     let combo = MultiLegBuilder::vertical(
+///         "account_id",
+///         &chain,
+///         None,
+///         OptionType::Call,
+///         Decimal::from(300),
+///         Decimal::from(320),
+///     )?
+///     .quantity(Decimal::from(5));
+///     let quote = combo.quote(&sdk).await?;
+///     let order = combo.limit_price(quote.ask).build()?;
+///     sdk.place_order(order).await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultiLegBuilder {
+    account_id: String,
+    legs: Vec<Leg>,
+    side: Side,
+    quantity: Decimal,
+    limit_price: Option<Decimal>,
+    time_in_force: TimeInForce,
+    client_order_id: String,
+    comment: String,
+}
+
+impl MultiLegBuilder {
+    /// Создает построитель без ног.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    pub fn new(account_id: &str) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            legs: Vec::new(),
+            side: Side::Buy,
+            quantity: Decimal::ONE,
+            limit_price: None,
+            time_in_force: TimeInForce::Day,
+            client_order_id: String::new(),
+            comment: String::new(),
+        }
+    }
+
+    /// Календарный спред: покупка дальнего контракта и продажа ближнего.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `near` - Символ ближнего контракта.
+    /// * `far` - Символ дальнего контракта.
+    pub fn calendar_spread(account_id: &str, near: &str, far: &str) -> Self {
+        Self::new(account_id)
+            .leg(far, Side::Buy, Decimal::ONE)
+            .leg(near, Side::Sell, Decimal::ONE)
+    }
+
+    /// Спред фьючерс против базового актива: покупка базового актива и продажа фьючерса.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `future` - Символ фьючерса.
+    /// * `spot` - Символ базового актива.
+    /// * `spot_ratio` - Количество базового актива на один контракт фьючерса.
+    pub fn futures_vs_spot(
+        account_id: &str,
+        future: &str,
+        spot: &str,
+        spot_ratio: Decimal,
+    ) -> Self {
+        Self::new(account_id)
+            .leg(spot, Side::Buy, spot_ratio)
+            .leg(future, Side::Sell, Decimal::ONE)
+    }
+
+    /// Вертикальный дебетовый спред.
+    ///
+    /// Для коллов покупается нижний страйк и продается верхний,
+    /// для путов покупается верхний страйк и продается нижний.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `chain` - Опционная доска.
+    /// * `expiration` - Дата окончания экспирации опционов. Если не задана,
+    ///   доска должна содержать опционы одной даты экспирации.
+    /// * `option_type` - Тип опционов.
+    /// * `lower_strike` - Нижний страйк.
+    /// * `upper_strike` - Верхний страйк.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Построитель или ошибку `InvalidArgument`.
+    pub fn vertical(
+        account_id: &str,
+        chain: &OptionsChainResponse,
+        expiration: Option<Date>,
+        option_type: OptionType,
+        lower_strike: Decimal,
+        upper_strike: Decimal,
+    ) -> Result<Self, FinamSdkError> {
+        if lower_strike >= upper_strike {
+            return Err(FinamSdkError::InvalidArgument(
+                "lower strike must be below upper strike".to_string(),
+            ));
+        }
+
+        let lower = chain.option_symbol(option_type, lower_strike, expiration)?;
+        let upper = chain.option_symbol(option_type, upper_strike, expiration)?;
+        let (long, short) = match option_type {
+            OptionType::Put => (upper, lower),
+            _ => (lower, upper),
+        };
+
+        Ok(Self::new(account_id)
+            .leg(long, Side::Buy, Decimal::ONE)
+            .leg(short, Side::Sell, Decimal::ONE))
+    }
+
+    /// Стрэддл: покупка колла и пута с одним страйком.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `chain` - Опционная доска.
+    /// * `expiration` - Дата окончания экспирации опционов. Если не задана,
+    ///   доска должна содержать опционы одной даты экспирации.
+    /// * `strike` - Страйк.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Построитель или ошибку `InvalidArgument`.
+    pub fn straddle(
+        account_id: &str,
+        chain: &OptionsChainResponse,
+        expiration: Option<Date>,
+        strike: Decimal,
+    ) -> Result<Self, FinamSdkError> {
+        Self::strangle(account_id, chain, expiration, strike, strike)
+    }
+
+    /// Стрэнгл: покупка пута с нижним страйком и колла с верхним.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `chain` - Опционная доска.
+    /// * `expiration` - Дата окончания экспирации опционов. Если не задана,
+    ///   доска должна содержать опционы одной даты экспирации.
+    /// * `put_strike` - Страйк пута.
+    /// * `call_strike` - Страйк колла.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Построитель или ошибку `InvalidArgument`.
+    pub fn strangle(
+        account_id: &str,
+        chain: &OptionsChainResponse,
+        expiration: Option<Date>,
+        put_strike: Decimal,
+        call_strike: Decimal,
+    ) -> Result<Self, FinamSdkError> {
+        if put_strike > call_strike {
+            return Err(FinamSdkError::InvalidArgument(
+                "put strike must not be above call strike".to_string(),
+            ));
+        }
+
+        let call = chain.option_symbol(OptionType::Call, call_strike, expiration)?;
+        let put = chain.option_symbol(OptionType::Put, put_strike, expiration)?;
+
+        Ok(Self::new(account_id)
+            .leg(call, Side::Buy, Decimal::ONE)
+            .leg(put, Side::Buy, Decimal::ONE))
+    }
+
+    /// Бабочка: покупка крайних страйков и продажа двух опционов среднего.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `chain` - Опционная доска.
+    /// * `expiration` - Дата окончания экспирации опционов. Если не задана,
+    ///   доска должна содержать опционы одной даты экспирации.
+    /// * `option_type` - Тип опционов.
+    /// * `lower_strike` - Нижний страйк.
+    /// * `middle_strike` - Средний страйк.
+    /// * `upper_strike` - Верхний страйк.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Построитель или ошибку `InvalidArgument`.
+    pub fn butterfly(
+        account_id: &str,
+        chain: &OptionsChainResponse,
+        expiration: Option<Date>,
+        option_type: OptionType,
+        lower_strike: Decimal,
+        middle_strike: Decimal,
+        upper_strike: Decimal,
+    ) -> Result<Self, FinamSdkError> {
+        if lower_strike >= middle_strike || middle_strike >= upper_strike {
+            return Err(FinamSdkError::InvalidArgument(
+                "butterfly strikes must be strictly increasing".to_string(),
+            ));
+        }
+
+        Ok(Self::new(account_id)
+            .leg(
+                chain.option_symbol(option_type, lower_strike, expiration)?,
+                Side::Buy,
+                Decimal::ONE,
+            )
+            .leg(
+                chain.option_symbol(option_type, middle_strike, expiration)?,
+                Side::Sell,
+                Decimal::TWO,
+            )
+            .leg(
+                chain.option_symbol(option_type, upper_strike, expiration)?,
+                Side::Buy,
+                Decimal::ONE,
+            ))
+    }
+
+    /// Добавляет ногу.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    /// * `side` - Сторона ноги при покупке комбинации.
+    /// * `ratio` - Количество инструмента на одну комбинацию.
+    pub fn leg(mut self, symbol: &str, side: Side, ratio: Decimal) -> Self {
+        self.legs.push(Leg {
+            symbol: symbol.to_string(),
+            quantity: Some(from_decimal(ratio)),
+            side: side as i32,
+        });
+        self
+    }
+
+    /// Задает сторону комбинации. `Side::Sell` исполняет ноги в обратную сторону.
+    pub fn side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Задает количество комбинаций.
+    pub fn quantity(mut self, quantity: Decimal) -> Self {
+        self.quantity = quantity;
+        self
+    }
+
+    /// Задает лимитную цену одной комбинации. Без цены заявка выставляется по рынку.
+    pub fn limit_price(mut self, price: Decimal) -> Self {
+        self.limit_price = Some(price);
+        self
+    }
+
+    /// Задает срок действия заявки.
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    /// Задает клиентский идентификатор заявки.
+    pub fn client_order_id(mut self, client_order_id: &str) -> Self {
+        self.client_order_id = client_order_id.to_string();
+        self
+    }
+
+    /// Задает метку заявки.
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = comment.to_string();
+        self
+    }
+
+    /// Возвращает ноги комбинации.
+    pub fn legs(&self) -> &[Leg] {
+        &self.legs
+    }
+
+    /// Проверяет, что все символы ног существуют.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку `InvalidArgument` для неизвестного символа.
+    pub async fn check_symbols(&self, sdk: &FinamSdk) -> Result<(), FinamSdkError> {
+        for leg in &self.legs {
+            let result = sdk
+                .assets()
+                .get_asset(GetAssetRequest {
+                    symbol: leg.symbol.clone(),
+                    account_id: self.account_id.clone(),
+                })
+                .await;
+
+            match result {
+                Ok(_) => {}
+                Err(status) if status.code() == Code::NotFound => {
+                    return Err(FinamSdkError::InvalidArgument(format!(
+                        "unknown leg symbol {}",
+                        leg.symbol
+                    )));
+                }
+                Err(status) => return Err(status.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Проверяет символы ног и рассчитывает котировку комбинации по текущим котировкам ног.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<ComboQuote, FinamSdkError>` - Котировка комбинации или ошибку.
+    pub async fn quote(&self, sdk: &FinamSdk) -> Result<ComboQuote, FinamSdkError> {
+        self.check_symbols(sdk).await?;
+
+        let mut quotes = HashMap::new();
+        for leg in &self.legs {
+            if quotes.contains_key(&leg.symbol) {
+                continue;
+            }
+
+            let quote = sdk
+                .market_data()
+                .last_quote(QuoteRequest {
+                    symbol: leg.symbol.clone(),
+                })
+                .await?
+                .into_inner()
+                .quote
+                .unwrap_or_default();
+            quotes.insert(leg.symbol.clone(), quote);
+        }
+
+        ComboQuote::from_legs(&self.legs, &quotes)
+    }
+
+    /// Проверяет согласованность полей и формирует мульти лег заявку.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Order, FinamSdkError>` - Заявка или ошибку `InvalidArgument`.
+    pub fn build(self) -> Result<Order, FinamSdkError> {
+        let invalid = |message: &str| Err(FinamSdkError::InvalidArgument(message.to_string()));
+
+        if self.legs.len() < 2 {
+            return invalid("multi-leg order requires at least two legs");
+        }
+
+        if self.side == Side::Unspecified {
+            return invalid("side must be specified");
+        }
+
+        if self.quantity <= Decimal::ZERO {
+            return invalid("quantity must be positive");
+        }
+
+        for leg in &self.legs {
+            if leg.symbol.is_empty() || leg.side() == Side::Unspecified {
+                return invalid("each leg requires a symbol and a side");
+            }
+
+            if opt_decimal(leg.quantity.as_ref())? <= Decimal::ZERO {
+                return invalid("leg ratios must be positive");
+            }
+        }
+
+        Ok(Order {
+            account_id: self.account_id,
+            quantity: Some(from_decimal(self.quantity)),
+            side: self.side as i32,
+            r#type: OrderType::MultiLeg as i32,
+            time_in_force: self.time_in_force as i32,
+            limit_price: self.limit_price.map(from_decimal),
+            legs: self.legs,
+            client_order_id: self.client_order_id,
+            comment: self.comment,
+            ..Default::default()
+        })
+    }
+}
+
+/// Греки опциона или комбинации.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Greeks {
+    /// Delta.
+    pub delta: Decimal,
+    /// Gamma.
+    pub gamma: Decimal,
+    /// Theta.
+    pub theta: Decimal,
+    /// Vega.
+    pub vega: Decimal,
+    /// Rho.
+    pub rho: Decimal,
+}
+
+impl Greeks {
+    /// Читает греки из информации об опционе в котировке.
+    ///
+    /// # Аргументы
+    ///
+    /// * `option` - Информация об опционе.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Греки или ошибку разбора.
+    pub fn from_option(option: &quote::Option) -> Result<Self, FinamSdkError> {
+        Ok(Self {
+            delta: opt_decimal(option.delta.as_ref())?,
+            gamma: opt_decimal(option.gamma.as_ref())?,
+            theta: opt_decimal(option.theta.as_ref())?,
+            vega: opt_decimal(option.vega.as_ref())?,
+            rho: opt_decimal(option.rho.as_ref())?,
+        })
+    }
+
+    fn add_scaled(&mut self, other: &Self, factor: Decimal) {
+        self.delta += other.delta * factor;
+        self.gamma += other.gamma * factor;
+        self.theta += other.theta * factor;
+        self.vega += other.vega * factor;
+        self.rho += other.rho * factor;
+    }
+}
+
+/// Котировка одной комбинации, купленной по сторонам ног.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComboQuote {
+    /// Цена продажи комбинации: покупки по бидам, продажи по аскам.
+    pub bid: Decimal,
+    /// Цена покупки комбинации: покупки по аскам, продажи по бидам.
+    pub ask: Decimal,
+    /// Средняя цена комбинации.
+    pub mid: Decimal,
+    /// Суммарные греки комбинации. Неопционные ноги учитываются с delta 1.
+    pub greeks: Greeks,
+}
+
+impl ComboQuote {
+    /// Рассчитывает котировку комбинации по котировкам ног.
+    ///
+    /// # Аргументы
+    ///
+    /// * `legs` - Ноги комбинации.
+    /// * `quotes` - Котировки по символам ног.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Котировка или ошибку `InvalidArgument`, если у ноги нет бида или аска.
+    pub fn from_legs(legs: &[Leg], quotes: &HashMap<String, Quote>) -> Result<Self, FinamSdkError> {
+        let mut bid = Decimal::ZERO;
+        let mut ask = Decimal::ZERO;
+        let mut greeks = Greeks::default();
+
+        for leg in legs {
+            let quote = quotes.get(&leg.symbol).ok_or_else(|| {
+                FinamSdkError::InvalidArgument(format!("no quote for leg {}", leg.symbol))
+            })?;
+            let leg_bid = opt_decimal(quote.bid.as_ref())?;
+            let leg_ask = opt_decimal(quote.ask.as_ref())?;
+
+            if leg_bid.is_zero() || leg_ask.is_zero() {
+                return Err(FinamSdkError::InvalidArgument(format!(
+                    "no bid or ask for leg {}",
+                    leg.symbol
+                )));
+            }
+
+            let ratio = opt_decimal(leg.quantity.as_ref())?;
+            let sells = leg.side() == Side::Sell;
+
+            if sells {
+                bid -= leg_ask * ratio;
+                ask -= leg_bid * ratio;
+            } else {
+                bid += leg_bid * ratio;
+                ask += leg_ask * ratio;
+            }
+
+            let leg_greeks = match &quote.additions {
+                Some(quote::Additions::Option(option)) => Greeks::from_option(option)?,
+                None => Greeks {
+                    delta: Decimal::ONE,
+                    ..Default::default()
+                },
+            };
+            greeks.add_scaled(&leg_greeks, if sells { -ratio } else { ratio });
+        }
+
+        Ok(Self {
+            bid,
+            ask,
+            mid: (bid + ask) / Decimal::TWO,
+            greeks,
+        })
+    }
+}
+
+impl OptionsChainResponse {
+    /// Возвращает даты окончания экспирации опционов доски по возрастанию.
+    pub fn expirations(&self) -> Vec<Date> {
+        let mut dates: Vec<_> = self
+            .options
+            .iter()
+            .filter_map(|option| option.expiration_last_day)
+            .collect();
+        dates.sort_by_key(|date| (date.year, date.month, date.day));
+        dates.dedup();
+        dates
+    }
+
+    /// Находит опцион заданного типа, страйка и даты экспирации.
+    ///
+    /// # Аргументы
+    ///
+    /// * `option_type` - Тип опциона.
+    /// * `strike` - Страйк.
+    /// * `expiration` - Дата окончания экспирации. `None` не ограничивает дату.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Option<&assets::Option>` - Опцион или `None`.
+    pub fn find_option(
+        &self,
+        option_type: OptionType,
+        strike: Decimal,
+        expiration: Option<Date>,
+    ) -> Option<&crate::proto::grpc::tradeapi::v1::assets::Option> {
+        self.options.iter().find(|option| {
+            option.r#type() == option_type
+                && opt_decimal(option.strike.as_ref()).is_ok_and(|value| value == strike)
+                && expiration.is_none_or(|date| option.expiration_last_day == Some(date))
+        })
+    }
+
+    /// Находит символ опциона для ноги структуры.
+    ///
+    /// Без даты экспирации доска должна содержать одну дату, чтобы ноги
+    /// не оказались из разных серий.
+    fn option_symbol(
+        &self,
+        option_type: OptionType,
+        strike: Decimal,
+        expiration: Option<Date>,
+    ) -> Result<&str, FinamSdkError> {
+        if expiration.is_none() && self.expirations().len() > 1 {
+            return Err(FinamSdkError::InvalidArgument(format!(
+                "options chain for {} has several expirations, specify one",
+                self.symbol
+            )));
+        }
+
+        self.find_option(option_type, strike, expiration)
+            .map(|option| option.symbol.as_str())
+            .ok_or_else(|| {
+                FinamSdkError::InvalidArgument(format!(
+                    "no {} option with strike {} for {}",
+                    option_type.as_str_name(),
+                    strike,
+                    self.symbol
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::grpc::tradeapi::v1::assets;
+
+    fn chain() -> OptionsChainResponse {
+        let option = |symbol: &str, option_type: OptionType, strike: i64| assets::Option {
+            symbol: symbol.to_string(),
+            r#type: option_type as i32,
+            strike: Some(from_decimal(Decimal::from(strike))),
+            ..Default::default()
+        };
+
+        OptionsChainResponse {
+            symbol: "SBER@MISX".to_string(),
+            options: vec![
+                option("C300", OptionType::Call, 300),
+                option("C310", OptionType::Call, 310),
+                option("C320", OptionType::Call, 320),
+                option("P300", OptionType::Put, 300),
+                option("P310", OptionType::Put, 310),
+            ],
+        }
+    }
+
+    fn quote(bid: i64, ask: i64, delta: Option<&str>) -> Quote {
+        Quote {
+            bid: Some(from_decimal(Decimal::from(bid))),
+            ask: Some(from_decimal(Decimal::from(ask))),
+            additions: delta.map(|delta| {
+                quote::Additions::Option(quote::Option {
+                    delta: Some(from_decimal(delta.parse().unwrap())),
+                    ..Default::default()
+                })
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn legs(builder: &MultiLegBuilder) -> Vec<(&str, Side, Decimal)> {
+        builder
+            .legs()
+            .iter()
+            .map(|leg| {
+                (
+                    leg.symbol.as_str(),
+                    leg.side(),
+                    opt_decimal(leg.quantity.as_ref()).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_option_structures() {
+        let chain = chain();
+
+        let put_vertical = MultiLegBuilder::vertical(
+            "acc",
+            &chain,
+            None,
+            OptionType::Put,
+            Decimal::from(300),
+            Decimal::from(310),
+        )
+        .unwrap();
+        assert_eq!(
+            legs(&put_vertical),
+            vec![
+                ("P310", Side::Buy, Decimal::ONE),
+                ("P300", Side::Sell, Decimal::ONE)
+            ]
+        );
+
+        let straddle = MultiLegBuilder::straddle("acc", &chain, None, Decimal::from(310)).unwrap();
+        assert_eq!(
+            legs(&straddle),
+            vec![
+                ("C310", Side::Buy, Decimal::ONE),
+                ("P310", Side::Buy, Decimal::ONE)
+            ]
+        );
+
+        let butterfly = MultiLegBuilder::butterfly(
+            "acc",
+            &chain,
+            None,
+            OptionType::Call,
+            Decimal::from(300),
+            Decimal::from(310),
+            Decimal::from(320),
+        )
+        .unwrap();
+        assert_eq!(butterfly.legs()[1].symbol, "C310");
+        assert_eq!(
+            opt_decimal(butterfly.legs()[1].quantity.as_ref()).unwrap(),
+            Decimal::TWO
+        );
+
+        assert!(MultiLegBuilder::straddle("acc", &chain, None, Decimal::from(320)).is_err());
+
+        let near = Date {
+            year: 2025,
+            month: 6,
+            day: 19,
+        };
+        let far = Date { month: 9, ..near };
+        let mut series = chain.clone();
+        for option in &mut series.options {
+            option.expiration_last_day = Some(near);
+        }
+        series.options.push(assets::Option {
+            symbol: "C310-far".to_string(),
+            expiration_last_day: Some(far),
+            ..chain.options[1].clone()
+        });
+        assert_eq!(series.expirations(), vec![near, far]);
+        assert!(MultiLegBuilder::straddle("acc", &series, None, Decimal::from(310)).is_err());
+        assert_eq!(
+            series
+                .find_option(OptionType::Call, Decimal::from(310), Some(far))
+                .map(|option| option.symbol.as_str()),
+            Some("C310-far")
+        );
+        let straddle =
+            MultiLegBuilder::straddle("acc", &series, Some(near), Decimal::from(310)).unwrap();
+        assert_eq!(straddle.legs()[0].symbol, "C310");
+
+        let order = butterfly.side(Side::Sell).build().unwrap();
+        assert_eq!(order.r#type(), OrderType::MultiLeg);
+        assert_eq!(order.side(), Side::Sell);
+        assert_eq!(order.legs.len(), 3);
+        assert!(
+            MultiLegBuilder::new("acc")
+                .leg("A", Side::Buy, Decimal::ONE)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_combo_quote() {
+        let builder = MultiLegBuilder::vertical(
+            "acc",
+            &chain(),
+            None,
+            OptionType::Call,
+            Decimal::from(300),
+            Decimal::from(320),
+        )
+        .unwrap();
+        let quotes = HashMap::from([
+            ("C300".to_string(), quote(10, 12, Some("0.6"))),
+            ("C320".to_string(), quote(3, 4, Some("0.25"))),
+        ]);
+
+        let combo = ComboQuote::from_legs(builder.legs(), &quotes).unwrap();
+
+        assert_eq!(combo.bid, Decimal::from(6));
+        assert_eq!(combo.ask, Decimal::from(9));
+        assert_eq!(combo.mid, "7.5".parse().unwrap());
+        assert_eq!(combo.greeks.delta, "0.35".parse().unwrap());
+
+        let spot = MultiLegBuilder::futures_vs_spot("acc", "SiZ5", "USD", Decimal::from(1000));
+        let quotes = HashMap::from([
+            ("USD".to_string(), quote(90, 91, None)),
+            ("SiZ5".to_string(), quote(92000, 92010, None)),
+        ]);
+        let combo = ComboQuote::from_legs(spot.legs(), &quotes).unwrap();
+        assert_eq!(combo.ask, Decimal::from(91000 - 92000));
+        assert_eq!(combo.greeks.delta, Decimal::from(999));
+    }
+}
@@ -0,0 +1,1054 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::{Mutex, broadcast, oneshot};
+
+use crate::{
+    FinamSdk, FinamSdkError, ShutdownGuard,
+    decimal::{Decimal, from_decimal, opt_decimal},
+    proto::grpc::tradeapi::v1::{
+        Side,
+        orders::{Order, OrderState, OrderStatus, OrderType, StopCondition, ValidBefore},
+    },
+    tracker::OrderTracker,
+    trading::ReplaceOrderResult,
+    util::next_id,
+};
+
+/// Префикс метки заявок, входящих в группы OCO и bracket.
+const TAG_PREFIX: &str = "oco";
+
+/// Разделитель комментария пользователя и метки группы.
+const TAG_SEPARATOR: &str = " #";
+
+/// Роль заявки в группе.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LegRole {
+    /// Равноправная заявка группы OCO.
+    Sibling,
+    /// Входящая заявка bracket.
+    Entry,
+    /// Защитная стоп заявка bracket.
+    StopLoss,
+    /// Заявка фиксации прибыли bracket.
+    TakeProfit,
+}
+
+impl LegRole {
+    fn as_tag(&self) -> &'static str {
+        match self {
+            Self::Sibling => "leg",
+            Self::Entry => "entry",
+            Self::StopLoss => "sl",
+            Self::TakeProfit => "tp",
+        }
+    }
+
+    fn from_tag(value: &str) -> Option<Self> {
+        match value {
+            "leg" => Some(Self::Sibling),
+            "entry" => Some(Self::Entry),
+            "sl" => Some(Self::StopLoss),
+            "tp" => Some(Self::TakeProfit),
+            _ => None,
+        }
+    }
+}
+
+/// Цены защитных заявок bracket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BracketPrices {
+    /// Цена срабатывания стоп заявки.
+    pub stop_price: Decimal,
+    /// Цена заявки фиксации прибыли.
+    pub take_profit_price: Decimal,
+}
+
+/// Заявка в составе группы.
+///
+/// При изменении объема заявка заменяется новой, поэтому идентификатор
+/// может меняться, а исполненное ранее количество накапливается.
+#[derive(Clone, Debug)]
+pub struct OcoLeg {
+    /// Роль заявки в группе.
+    pub role: LegRole,
+    /// Идентификатор текущей заявки.
+    pub order_id: String,
+    /// Параметры текущей заявки.
+    pub order: Order,
+    /// Последний известный статус текущей заявки.
+    pub status: OrderStatus,
+    /// Количество, исполненное по заявкам этой роли, замененным ранее.
+    pub filled_before: Decimal,
+    /// Количество, исполненное по текущей заявке.
+    pub executed: Decimal,
+}
+
+impl OcoLeg {
+    /// Возвращает общее исполненное количество по роли.
+    pub fn filled(&self) -> Decimal {
+        self.filled_before + self.executed
+    }
+
+    /// Проверяет, активна ли текущая заявка.
+    pub fn is_active(&self) -> bool {
+        self.status.is_active()
+    }
+}
+
+/// Группа связанных заявок OCO или bracket.
+#[derive(Clone, Debug)]
+pub struct OcoGroup {
+    /// Идентификатор группы.
+    pub id: String,
+    /// Идентификатор аккаунта.
+    pub account_id: String,
+    /// Общий объем группы OCO. Для bracket не используется.
+    pub quantity: Decimal,
+    /// Цены защитных заявок, если группа является bracket.
+    pub bracket: Option<BracketPrices>,
+    /// Заявки группы.
+    pub legs: Vec<OcoLeg>,
+}
+
+impl OcoGroup {
+    /// Возвращает объем, который должны покрывать защитные заявки.
+    ///
+    /// Для OCO это общий объем группы, для bracket - исполненная часть входящей заявки.
+    fn base_quantity(&self) -> Decimal {
+        match self.bracket {
+            Some(_) => self
+                .legs
+                .iter()
+                .filter(|leg| leg.role == LegRole::Entry)
+                .map(OcoLeg::filled)
+                .sum(),
+            None => self.quantity,
+        }
+    }
+
+    /// Возвращает еще не закрытый защитными заявками объем.
+    fn remaining(&self) -> Decimal {
+        let filled: Decimal = self
+            .legs
+            .iter()
+            .filter(|leg| leg.role != LegRole::Entry)
+            .map(OcoLeg::filled)
+            .sum();
+
+        self.base_quantity() - filled
+    }
+
+    /// Проверяет, есть ли у группы активные заявки.
+    fn is_active(&self) -> bool {
+        self.legs.iter().any(OcoLeg::is_active)
+    }
+
+    fn leg_mut(&mut self, role: LegRole) -> Option<&mut OcoLeg> {
+        self.legs.iter_mut().find(|leg| leg.role == role)
+    }
+}
+
+/// Клиентский движок заявок OCO (one-cancels-other) и bracket.
+///
+/// Отслеживает заявки групп через [`OrderTracker`]. При частичном исполнении
+/// одной заявки группы остальные уменьшаются на исполненный объем, при полном
+/// исполнении - отменяются. Bracket выставляет входящую заявку и после ее
+/// исполнения выставляет стоп заявку и заявку фиксации прибыли как группу OCO.
+///
+/// Принадлежность к группе записывается в поле `comment` заявок, поэтому после
+/// перезапуска процесса состояние восстанавливается методом [`OcoEngine::recover`].
+#[derive(Clone, Debug)]
+pub struct OcoEngine {
+    inner: Arc<OcoInner>,
+    /// Удерживает фоновую задачу обработки обновлений. При уничтожении последней
+    /// ссылки на движок отправляет сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Arc<ShutdownGuard>,
+}
+
+#[derive(Debug)]
+struct OcoInner {
+    sdk: FinamSdk,
+    tracker: OrderTracker,
+    groups: Mutex<HashMap<String, OcoGroup>>,
+    /// Упорядочивает сверки групп, выполняемые без блокировки `groups`.
+    reconciling: Mutex<()>,
+}
+
+impl OcoEngine {
+    /// Запускает движок поверх трекера заявок аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    /// * `tracker` - Трекер заявок аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Self` - Запущенный движок.
+    pub fn start(sdk: FinamSdk, tracker: OrderTracker) -> Self {
+        let inner = Arc::new(OcoInner {
+            sdk,
+            tracker,
+            groups: Mutex::new(HashMap::new()),
+            reconciling: Mutex::new(()),
+        });
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+
+        tokio::spawn(inner.clone().run(shutdown_receiver));
+
+        Self {
+            inner,
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        }
+    }
+
+    /// Выставляет группу заявок OCO.
+    ///
+    /// Все заявки группы должны иметь одинаковый объем. К полю `comment` заявок
+    /// добавляется метка группы, исходный комментарий сохраняется и может быть
+    /// получен через [`strip_tag`]. Если какую-либо заявку выставить не удалось,
+    /// уже выставленные заявки отменяются.
+    ///
+    /// # Аргументы
+    ///
+    /// * `orders` - Заявки группы, не менее двух.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OcoGroup, FinamSdkError>` - Выставленная группа или ошибку.
+    pub async fn place_oco(&self, orders: Vec<Order>) -> Result<OcoGroup, FinamSdkError> {
+        if orders.len() < 2 {
+            return Err(FinamSdkError::InvalidArgument(
+                "OCO group requires at least two orders".to_string(),
+            ));
+        }
+
+        let quantity = opt_decimal(orders[0].quantity.as_ref())?;
+
+        for order in &orders {
+            if opt_decimal(order.quantity.as_ref())? != quantity {
+                return Err(FinamSdkError::InvalidArgument(
+                    "OCO orders must have equal quantities".to_string(),
+                ));
+            }
+        }
+
+        let id = next_id("");
+        let mut group = OcoGroup {
+            id: id.clone(),
+            account_id: self.inner.tracker.account_id().to_string(),
+            quantity,
+            bracket: None,
+            legs: Vec::new(),
+        };
+
+        for mut order in orders {
+            order.account_id = group.account_id.clone();
+            order.comment = tag(&order.comment, &id, LegRole::Sibling, None);
+
+            match self.inner.place(order.clone()).await {
+                Ok(state) => group.legs.push(leg(LegRole::Sibling, order, &state)?),
+                Err(error) => {
+                    for placed in &group.legs {
+                        if let Err(cancel_error) = self
+                            .inner
+                            .sdk
+                            .cancel_order(&group.account_id, &placed.order_id)
+                            .await
+                        {
+                            log::error!(
+                                "Failed to roll back OCO order {}: {:?}",
+                                placed.order_id,
+                                cancel_error
+                            );
+                        }
+                    }
+                    return Err(error);
+                }
+            }
+        }
+
+        Ok(self.inner.register(group).await)
+    }
+
+    /// Выставляет bracket: входящую заявку, после исполнения которой
+    /// выставляются стоп заявка и заявка фиксации прибыли.
+    ///
+    /// При частичном исполнении входящей заявки защитные заявки выставляются
+    /// на исполненный объем и увеличиваются по мере дальнейшего исполнения.
+    ///
+    /// # Аргументы
+    ///
+    /// * `entry` - Входящая заявка.
+    /// * `prices` - Цены защитных заявок.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OcoGroup, FinamSdkError>` - Выставленная группа или ошибку.
+    pub async fn place_bracket(
+        &self,
+        mut entry: Order,
+        prices: BracketPrices,
+    ) -> Result<OcoGroup, FinamSdkError> {
+        let id = next_id("");
+
+        entry.account_id = self.inner.tracker.account_id().to_string();
+        entry.comment = tag(&entry.comment, &id, LegRole::Entry, Some(&prices));
+
+        let state = self.inner.place(entry.clone()).await?;
+        let group = OcoGroup {
+            id,
+            account_id: entry.account_id.clone(),
+            quantity: opt_decimal(entry.quantity.as_ref())?,
+            bracket: Some(prices),
+            legs: vec![leg(LegRole::Entry, entry, &state)?],
+        };
+
+        Ok(self.inner.register(group).await)
+    }
+
+    /// Отменяет все активные заявки группы.
+    ///
+    /// # Аргументы
+    ///
+    /// * `group_id` - Идентификатор группы.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или первую ошибку отмены.
+    pub async fn cancel_group(&self, group_id: &str) -> Result<(), FinamSdkError> {
+        let _reconciling = self.inner.reconciling.lock().await;
+        let Some(group) = self.inner.groups.lock().await.remove(group_id) else {
+            return Ok(());
+        };
+
+        let mut result = Ok(());
+
+        for leg in group.legs.iter().filter(|leg| leg.is_active()) {
+            if let Err(error) = self
+                .inner
+                .sdk
+                .cancel_order(&group.account_id, &leg.order_id)
+                .await
+            {
+                log::error!("Failed to cancel OCO order {}: {:?}", leg.order_id, error);
+                if result.is_ok() {
+                    result = Err(error);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Возвращает активные группы.
+    pub async fn groups(&self) -> Vec<OcoGroup> {
+        self.inner.groups.lock().await.values().cloned().collect()
+    }
+
+    /// Восстанавливает группы по меткам заявок после перезапуска процесса.
+    ///
+    /// Загружает заявки через `get_orders`, собирает группы по меткам в поле
+    /// `comment` и досылает недостающие действия: выставляет защитные заявки
+    /// по исполненным входящим и отменяет лишние заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<usize, FinamSdkError>` - Количество восстановленных групп или ошибку.
+    pub async fn recover(&self) -> Result<usize, FinamSdkError> {
+        self.inner.tracker.reload(&self.inner.sdk).await?;
+
+        let mut recovered: HashMap<String, OcoGroup> = HashMap::new();
+        let mut states = self.inner.tracker.orders();
+        states.sort_by_key(|state| state.transact_at.map(|time| (time.seconds, time.nanos)));
+
+        for state in states {
+            let Some(order) = state.order.clone() else {
+                continue;
+            };
+            let Some((id, role, prices)) = parse_tag(&order.comment) else {
+                continue;
+            };
+
+            let group = recovered.entry(id.clone()).or_insert_with(|| OcoGroup {
+                id,
+                account_id: order.account_id.clone(),
+                quantity: Decimal::ZERO,
+                bracket: None,
+                legs: Vec::new(),
+            });
+
+            if prices.is_some() {
+                group.bracket = prices;
+            }
+
+            let restored = leg(role, order, &state)?;
+
+            match group.leg_mut(role) {
+                Some(existing) if existing.is_active() && !restored.is_active() => {
+                    existing.filled_before += restored.executed;
+                }
+                Some(existing) => {
+                    let filled_before = existing.filled();
+                    *existing = OcoLeg {
+                        filled_before,
+                        ..restored
+                    };
+                }
+                None => group.legs.push(restored),
+            }
+        }
+
+        let mut ids = Vec::with_capacity(recovered.len());
+
+        for (id, mut group) in recovered {
+            if group.bracket.is_none() {
+                group.quantity = group
+                    .legs
+                    .iter()
+                    .map(|leg| {
+                        leg.filled_before
+                            + opt_decimal(leg.order.quantity.as_ref()).unwrap_or_default()
+                    })
+                    .max()
+                    .unwrap_or_default();
+            }
+
+            self.inner.groups.lock().await.insert(id.clone(), group);
+            ids.push(id);
+        }
+
+        let mut count = 0;
+        for id in ids {
+            self.inner.reconcile(&id).await;
+
+            if self.inner.groups.lock().await.contains_key(&id) {
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+impl OcoInner {
+    /// Обрабатывает обновления заявок до получения сигнала завершения.
+    async fn run(self: Arc<Self>, mut shutdown_receiver: oneshot::Receiver<()>) {
+        let mut updates = self.tracker.subscribe();
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_receiver => {
+                    log::info!("OCO engine shutting down");
+                    return;
+                }
+                update = updates.recv() => match update {
+                    Ok(state) => self.handle(state).await,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("OCO engine skipped {} order updates, resyncing", skipped);
+                        self.resync().await;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+            }
+        }
+    }
+
+    /// Выставляет заявку и сохраняет ее состояние в трекере.
+    async fn place(&self, order: Order) -> Result<OrderState, FinamSdkError> {
+        let state = self.sdk.place_order(order).await?;
+        self.tracker.record(state.clone());
+        Ok(state)
+    }
+
+    /// Регистрирует группу и применяет обновления, пришедшие до регистрации.
+    async fn register(&self, mut group: OcoGroup) -> OcoGroup {
+        let id = group.id.clone();
+
+        self.refresh(&mut group);
+        self.groups.lock().await.insert(id.clone(), group.clone());
+        self.reconcile(&id).await;
+
+        self.groups.lock().await.get(&id).cloned().unwrap_or(group)
+    }
+
+    /// Применяет обновление заявки к группе, в которую она входит.
+    async fn handle(&self, state: OrderState) {
+        let id = {
+            let mut groups = self.groups.lock().await;
+
+            let Some(group) = groups
+                .values_mut()
+                .find(|group| group.legs.iter().any(|leg| leg.order_id == state.order_id))
+            else {
+                return;
+            };
+
+            if !update_leg(group, &state) {
+                return;
+            }
+
+            group.id.clone()
+        };
+
+        self.reconcile(&id).await;
+    }
+
+    /// Повторно применяет последние известные состояния заявок всех групп.
+    async fn resync(&self) {
+        let ids: Vec<_> = {
+            let mut groups = self.groups.lock().await;
+
+            for group in groups.values_mut() {
+                self.refresh(group);
+            }
+
+            groups.keys().cloned().collect()
+        };
+
+        for id in ids {
+            self.reconcile(&id).await;
+        }
+    }
+
+    /// Обновляет заявки группы по последним состояниям из трекера.
+    fn refresh(&self, group: &mut OcoGroup) {
+        let states: Vec<_> = group
+            .legs
+            .iter()
+            .filter_map(|leg| self.tracker.get(&leg.order_id))
+            .collect();
+
+        for state in states {
+            update_leg(group, &state);
+        }
+    }
+
+    /// Приводит заявки группы в соответствие с исполненными объемами
+    /// и снимает завершенную группу.
+    ///
+    /// Действия вычисляются под блокировкой групп, а запросы к API выполняются
+    /// после ее освобождения. Сверки выполняются по одной, чтобы не выставить
+    /// одну и ту же защитную заявку дважды.
+    async fn reconcile(&self, id: &str) {
+        let _reconciling = self.reconciling.lock().await;
+
+        let Some((account_id, actions)) = self
+            .groups
+            .lock()
+            .await
+            .get(id)
+            .map(|group| (group.account_id.clone(), plan(group)))
+        else {
+            return;
+        };
+
+        let mut outcomes = Vec::with_capacity(actions.len());
+        for action in actions {
+            if let Some(outcome) = self.execute(&account_id, id, action).await {
+                outcomes.push(outcome);
+            }
+        }
+
+        let mut groups = self.groups.lock().await;
+        let Some(group) = groups.get_mut(id) else {
+            return;
+        };
+
+        for outcome in outcomes {
+            apply_outcome(group, outcome);
+        }
+
+        if !group.is_active() {
+            log::info!("OCO group {} completed", id);
+            groups.remove(id);
+        }
+    }
+
+    /// Выполняет действие над заявкой группы и сохраняет новые состояния в трекере.
+    async fn execute(
+        &self,
+        account_id: &str,
+        group_id: &str,
+        action: LegAction,
+    ) -> Option<LegOutcome> {
+        match action {
+            LegAction::Arm { role, order } => match self.place(order.clone()).await {
+                Ok(state) => match leg(role, order, &state) {
+                    Ok(leg) => Some(LegOutcome::Armed(Box::new(leg))),
+                    Err(error) => {
+                        log::error!("Invalid bracket order state: {:?}", error);
+                        None
+                    }
+                },
+                Err(error) => {
+                    log::error!(
+                        "Failed to arm {:?} for bracket {}: {:?}",
+                        role,
+                        group_id,
+                        error
+                    );
+                    None
+                }
+            },
+            LegAction::Cancel { order_id } => {
+                match self.sdk.cancel_order(account_id, &order_id).await {
+                    Ok(state) => {
+                        let status = state.status();
+                        self.tracker.record(state);
+                        Some(LegOutcome::Canceled { order_id, status })
+                    }
+                    Err(error) => {
+                        log::error!("Failed to cancel OCO order {}: {:?}", order_id, error);
+                        None
+                    }
+                }
+            }
+            LegAction::Resize { order_id, quantity } => match self
+                .sdk
+                .replace_order(account_id, &order_id, None, quantity)
+                .await
+            {
+                Ok(result) => {
+                    self.tracker.record(result.canceled.clone());
+                    if let Some(placed) = &result.placed {
+                        self.tracker.record(placed.clone());
+                    }
+                    Some(LegOutcome::Resized {
+                        order_id,
+                        quantity,
+                        result: Box::new(result),
+                    })
+                }
+                Err(error) => {
+                    log::error!("Failed to resize OCO order {}: {:?}", order_id, error);
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// Действие над заявкой группы, вычисленное при сверке.
+#[derive(Clone, Debug)]
+enum LegAction {
+    /// Выставить защитную заявку bracket.
+    Arm { role: LegRole, order: Order },
+    /// Отменить заявку.
+    Cancel { order_id: String },
+    /// Изменить объем заявки.
+    Resize { order_id: String, quantity: Decimal },
+}
+
+/// Результат успешно выполненного действия.
+#[derive(Debug)]
+enum LegOutcome {
+    Armed(Box<OcoLeg>),
+    Canceled {
+        order_id: String,
+        status: OrderStatus,
+    },
+    Resized {
+        order_id: String,
+        quantity: Decimal,
+        result: Box<ReplaceOrderResult>,
+    },
+}
+
+/// Вычисляет действия, приводящие заявки группы в соответствие с исполненными объемами.
+///
+/// Защитные заявки bracket выставляются на еще не закрытый объем, лишние заявки
+/// отменяются, остальные уменьшаются или увеличиваются до оставшегося объема.
+fn plan(group: &OcoGroup) -> Vec<LegAction> {
+    let mut actions = Vec::new();
+    let remaining = group.remaining();
+
+    if let Some(prices) = group.bracket
+        && remaining > Decimal::ZERO
+        && let Some(entry) = group.legs.iter().find(|leg| leg.role == LegRole::Entry)
+    {
+        for role in [LegRole::StopLoss, LegRole::TakeProfit] {
+            if group.legs.iter().all(|leg| leg.role != role) {
+                actions.push(LegAction::Arm {
+                    role,
+                    order: protective_order(&group.id, &entry.order, role, &prices, remaining),
+                });
+            }
+        }
+    }
+
+    let protective_filled = group
+        .legs
+        .iter()
+        .any(|leg| leg.role != LegRole::Entry && leg.filled() > Decimal::ZERO);
+
+    for leg in group.legs.iter().filter(|leg| leg.is_active()) {
+        let order_id = leg.order_id.clone();
+
+        if leg.role == LegRole::Entry {
+            if remaining <= Decimal::ZERO && protective_filled {
+                actions.push(LegAction::Cancel { order_id });
+            }
+            continue;
+        }
+
+        let quantity = opt_decimal(leg.order.quantity.as_ref()).unwrap_or_default();
+        let target = remaining + leg.executed;
+
+        if target == quantity {
+            continue;
+        }
+
+        if remaining <= Decimal::ZERO {
+            actions.push(LegAction::Cancel { order_id });
+        } else {
+            actions.push(LegAction::Resize {
+                order_id,
+                quantity: target,
+            });
+        }
+    }
+
+    actions
+}
+
+/// Применяет результат действия к группе.
+fn apply_outcome(group: &mut OcoGroup, outcome: LegOutcome) {
+    match outcome {
+        LegOutcome::Armed(leg) => {
+            if group.legs.iter().all(|existing| existing.role != leg.role) {
+                group.legs.push(*leg);
+            }
+        }
+        LegOutcome::Canceled { order_id, status } => {
+            if let Some(leg) = group.legs.iter_mut().find(|leg| leg.order_id == order_id) {
+                leg.status = status;
+            }
+        }
+        LegOutcome::Resized {
+            order_id,
+            quantity,
+            result,
+        } => {
+            let Some(leg) = group.legs.iter_mut().find(|leg| leg.order_id == order_id) else {
+                return;
+            };
+
+            leg.filled_before += result.executed_quantity;
+            leg.executed = Decimal::ZERO;
+            leg.order.quantity = Some(from_decimal(quantity - result.executed_quantity));
+
+            match result.placed {
+                Some(placed) => {
+                    leg.order_id = placed.order_id.clone();
+                    leg.status = placed.status();
+                }
+                None => leg.status = result.canceled.status(),
+            }
+        }
+    }
+}
+
+/// Обновляет заявку группы по ее состоянию.
+///
+/// # Возвращает
+///
+/// * `bool` - `true`, если изменились статус или исполненный объем.
+fn update_leg(group: &mut OcoGroup, state: &OrderState) -> bool {
+    let Some(leg) = group
+        .legs
+        .iter_mut()
+        .find(|leg| leg.order_id == state.order_id)
+    else {
+        return false;
+    };
+
+    let executed = opt_decimal(state.executed_quantity.as_ref()).unwrap_or(leg.executed);
+    let status = state.status();
+    let changed = executed != leg.executed || status != leg.status;
+
+    leg.executed = executed;
+    leg.status = status;
+
+    changed
+}
+
+fn leg(role: LegRole, order: Order, state: &OrderState) -> Result<OcoLeg, FinamSdkError> {
+    Ok(OcoLeg {
+        role,
+        order_id: state.order_id.clone(),
+        order,
+        status: state.status(),
+        filled_before: Decimal::ZERO,
+        executed: opt_decimal(state.executed_quantity.as_ref())?,
+    })
+}
+
+/// Формирует защитную заявку bracket противоположной стороны.
+fn protective_order(
+    group_id: &str,
+    entry: &Order,
+    role: LegRole,
+    prices: &BracketPrices,
+    quantity: Decimal,
+) -> Order {
+    let long = entry.side() == Side::Buy;
+    let side = if long { Side::Sell } else { Side::Buy };
+
+    let mut order = Order {
+        account_id: entry.account_id.clone(),
+        symbol: entry.symbol.clone(),
+        quantity: Some(from_decimal(quantity)),
+        side: side as i32,
+        time_in_force: entry.time_in_force,
+        comment: tag(strip_tag(&entry.comment), group_id, role, None),
+        ..Default::default()
+    };
+
+    match role {
+        LegRole::StopLoss => {
+            order.r#type = OrderType::Stop as i32;
+            order.stop_price = Some(from_decimal(prices.stop_price));
+            order.stop_condition = if long {
+                StopCondition::LastDown
+            } else {
+                StopCondition::LastUp
+            } as i32;
+            order.valid_before = ValidBefore::GoodTillCancel as i32;
+        }
+        _ => {
+            order.r#type = OrderType::Limit as i32;
+            order.limit_price = Some(from_decimal(prices.take_profit_price));
+        }
+    }
+
+    order
+}
+
+/// Возвращает комментарий заявки без метки группы OCO или bracket.
+///
+/// # Аргументы
+///
+/// * `comment` - Поле `comment` заявки или сделки.
+///
+/// # Возвращает
+///
+/// * `&str` - Комментарий пользователя. Для заявок вне групп совпадает с `comment`.
+pub fn strip_tag(comment: &str) -> &str {
+    split_tag(comment).0
+}
+
+/// Добавляет к комментарию пользователя метку заявки вида
+/// `oco:<группа>:<роль>[:<стоп>:<тейк>]`, отделенную ` #`.
+fn tag(comment: &str, group_id: &str, role: LegRole, prices: Option<&BracketPrices>) -> String {
+    let tag = match prices {
+        Some(prices) => format!(
+            "{TAG_PREFIX}:{group_id}:{}:{}:{}",
+            role.as_tag(),
+            prices.stop_price,
+            prices.take_profit_price
+        ),
+        None => format!("{TAG_PREFIX}:{group_id}:{}", role.as_tag()),
+    };
+
+    if comment.is_empty() {
+        tag
+    } else {
+        format!("{comment}{TAG_SEPARATOR}{tag}")
+    }
+}
+
+/// Разобранная метка: идентификатор группы, роль и цены bracket.
+type ParsedTag = (String, LegRole, Option<BracketPrices>);
+
+/// Разделяет комментарий на комментарий пользователя и метку группы.
+fn split_tag(comment: &str) -> (&str, Option<ParsedTag>) {
+    if let Some(parsed) = parse_tag_body(comment) {
+        return ("", Some(parsed));
+    }
+
+    match comment.rsplit_once(TAG_SEPARATOR) {
+        Some((user, tag)) => match parse_tag_body(tag) {
+            Some(parsed) => (user, Some(parsed)),
+            None => (comment, None),
+        },
+        None => (comment, None),
+    }
+}
+
+fn parse_tag(comment: &str) -> Option<ParsedTag> {
+    split_tag(comment).1
+}
+
+fn parse_tag_body(tag: &str) -> Option<ParsedTag> {
+    let mut parts = tag.split(':');
+
+    if parts.next()? != TAG_PREFIX {
+        return None;
+    }
+
+    let id = parts.next()?.to_string();
+    let role = LegRole::from_tag(parts.next()?)?;
+    let prices = match (parts.next(), parts.next()) {
+        (Some(stop), Some(take)) => Some(BracketPrices {
+            stop_price: stop.parse().ok()?,
+            take_profit_price: take.parse().ok()?,
+        }),
+        (None, None) => None,
+        _ => return None,
+    };
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some((id, role, prices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_leg(role: LegRole, quantity: i64, executed: i64, status: OrderStatus) -> OcoLeg {
+        OcoLeg {
+            role,
+            order_id: format!("{role:?}"),
+            order: Order {
+                quantity: Some(from_decimal(Decimal::from(quantity))),
+                ..Default::default()
+            },
+            status,
+            filled_before: Decimal::ZERO,
+            executed: Decimal::from(executed),
+        }
+    }
+
+    #[test]
+    fn test_tag_roundtrip() {
+        let prices = BracketPrices {
+            stop_price: Decimal::new(2955, 1),
+            take_profit_price: Decimal::from(320),
+        };
+
+        assert_eq!(
+            parse_tag(&tag("", "abc", LegRole::Entry, Some(&prices))),
+            Some(("abc".to_string(), LegRole::Entry, Some(prices)))
+        );
+
+        let comment = tag("mean-revert #2", "abc", LegRole::StopLoss, None);
+        assert_eq!(comment, "mean-revert #2 #oco:abc:sl");
+        assert_eq!(
+            parse_tag(&comment),
+            Some(("abc".to_string(), LegRole::StopLoss, None))
+        );
+        assert_eq!(strip_tag(&comment), "mean-revert #2");
+
+        assert_eq!(parse_tag("manual order"), None);
+        assert_eq!(strip_tag("manual #order"), "manual #order");
+    }
+
+    #[test]
+    fn test_remaining_quantity() {
+        let oco = OcoGroup {
+            id: "1".to_string(),
+            account_id: "A1".to_string(),
+            quantity: Decimal::from(10),
+            bracket: None,
+            legs: vec![
+                test_leg(LegRole::Sibling, 10, 4, OrderStatus::PartiallyFilled),
+                test_leg(LegRole::Sibling, 10, 0, OrderStatus::New),
+            ],
+        };
+        assert_eq!(oco.remaining(), Decimal::from(6));
+
+        let bracket = OcoGroup {
+            bracket: Some(BracketPrices {
+                stop_price: Decimal::from(90),
+                take_profit_price: Decimal::from(110),
+            }),
+            legs: vec![
+                test_leg(LegRole::Entry, 10, 7, OrderStatus::PartiallyFilled),
+                test_leg(LegRole::StopLoss, 7, 0, OrderStatus::New),
+                test_leg(LegRole::TakeProfit, 7, 2, OrderStatus::PartiallyFilled),
+            ],
+            ..oco
+        };
+        assert_eq!(bracket.base_quantity(), Decimal::from(7));
+        assert_eq!(bracket.remaining(), Decimal::from(5));
+    }
+
+    #[test]
+    fn test_plan_actions() {
+        let oco = OcoGroup {
+            id: "1".to_string(),
+            account_id: "A1".to_string(),
+            quantity: Decimal::from(10),
+            bracket: None,
+            legs: vec![
+                test_leg(LegRole::Sibling, 10, 4, OrderStatus::PartiallyFilled),
+                test_leg(LegRole::Sibling, 10, 0, OrderStatus::New),
+            ],
+        };
+        let actions = plan(&oco);
+        assert_eq!(actions.len(), 1);
+        assert!(
+            matches!(&actions[0], LegAction::Resize { quantity, .. } if *quantity == Decimal::from(6))
+        );
+
+        let bracket = OcoGroup {
+            bracket: Some(BracketPrices {
+                stop_price: Decimal::from(90),
+                take_profit_price: Decimal::from(110),
+            }),
+            legs: vec![test_leg(
+                LegRole::Entry,
+                10,
+                7,
+                OrderStatus::PartiallyFilled,
+            )],
+            ..oco
+        };
+        let armed: Vec<_> = plan(&bracket)
+            .into_iter()
+            .filter_map(|action| match action {
+                LegAction::Arm { role, order } => {
+                    Some((role, opt_decimal(order.quantity.as_ref()).unwrap()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            armed,
+            vec![
+                (LegRole::StopLoss, Decimal::from(7)),
+                (LegRole::TakeProfit, Decimal::from(7))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_protective_order_for_long_entry() {
+        let entry = Order {
+            account_id: "A1".to_string(),
+            symbol: "SBER@MISX".to_string(),
+            side: Side::Buy as i32,
+            ..Default::default()
+        };
+        let prices = BracketPrices {
+            stop_price: Decimal::from(90),
+            take_profit_price: Decimal::from(110),
+        };
+
+        let stop = protective_order("g", &entry, LegRole::StopLoss, &prices, Decimal::from(3));
+        assert_eq!(stop.side(), Side::Sell);
+        assert_eq!(stop.r#type(), OrderType::Stop);
+        assert_eq!(stop.stop_condition(), StopCondition::LastDown);
+
+        let take = protective_order("g", &entry, LegRole::TakeProfit, &prices, Decimal::from(3));
+        assert_eq!(take.r#type(), OrderType::Limit);
+        assert_eq!(take.limit_price, Some(from_decimal(Decimal::from(110))));
+    }
+}
@@ -0,0 +1,1199 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{StreamExt, stream::BoxStream};
+use prost_types::Timestamp;
+use tokio::{
+    sync::{broadcast, oneshot},
+    task::JoinHandle,
+};
+
+use crate::{
+    FinamAssetsServiceClient, FinamMarketDataServiceClient, FinamSdkError, ShutdownGuard,
+    calendar::TradingCalendar,
+    clock::ServerClock,
+    decimal::{Decimal, from_decimal, opt_decimal},
+    proto::{
+        google::r#type::{Interval, Money},
+        grpc::tradeapi::v1::{
+            AccountTrade, Side,
+            accounts::{GetAccountResponse, Position},
+            assets::ScheduleRequest,
+            marketdata::{QuoteRequest, SubscribeLatestTradesRequest, SubscribeQuoteRequest},
+            orders::{Order, OrderState, OrderStatus, OrderType, StopCondition, TimeInForce},
+        },
+    },
+    trading::{OrderStream, TradeStream},
+    util::{EVENTS_CAPACITY, RECONNECT_DELAY, SECONDS_PER_DAY},
+};
+
+/// Интервал проверки истечения срока действия заявок.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Смещение московского времени от UTC.
+///
+/// Торговый день Московской биржи заканчивается не позже полуночи по Москве,
+/// а московское время не переходит на летнее. Полночь используется как граница
+/// дня, внутри которой ищется окончание последней торговой сессии по расписанию,
+/// и как срок действия дневных заявок, если расписание недоступно.
+const MOSCOW_OFFSET: Duration = Duration::from_secs(3 * 60 * 60);
+
+/// Настройки режима бумажной торговли.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaperTradingConfig {
+    /// Начальный остаток денежных средств каждого аккаунта.
+    pub initial_cash: Decimal,
+    /// Валюта денежных средств.
+    pub currency: String,
+}
+
+impl PaperTradingConfig {
+    /// Создает настройки с начальным остатком в рублях.
+    ///
+    /// # Аргументы
+    ///
+    /// * `initial_cash` - Начальный остаток денежных средств.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Self` - Настройки бумажной торговли.
+    pub fn new(initial_cash: Decimal) -> Self {
+        Self {
+            initial_cash,
+            currency: "RUB".to_string(),
+        }
+    }
+
+    /// Задает валюту денежных средств.
+    ///
+    /// # Аргументы
+    ///
+    /// * `currency` - Трехбуквенный код валюты ISO 4217.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Self` - Настройки с указанной валютой.
+    pub fn with_currency(mut self, currency: &str) -> Self {
+        self.currency = currency.to_string();
+        self
+    }
+}
+
+/// Рыночные цены инструмента, по которым исполняются заявки.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarketSnapshot {
+    /// Лучшая цена покупки.
+    pub bid: Option<Decimal>,
+    /// Лучшая цена продажи.
+    pub ask: Option<Decimal>,
+    /// Цена последней сделки.
+    pub last: Option<Decimal>,
+}
+
+/// Позиция симулированного аккаунта.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PaperPosition {
+    /// Количество в шт. со знаком: положительное для длинной позиции.
+    pub quantity: Decimal,
+    /// Средняя цена открытия.
+    pub average_price: Decimal,
+}
+
+/// Денежные средства и позиции симулированного аккаунта.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PaperLedger {
+    /// Остаток денежных средств.
+    pub cash: Decimal,
+    /// Позиции по символам.
+    pub positions: HashMap<String, PaperPosition>,
+}
+
+impl PaperLedger {
+    /// Учитывает сделку в денежных средствах и позиции.
+    fn apply_fill(&mut self, symbol: &str, side: Side, quantity: Decimal, price: Decimal) {
+        let delta = if side == Side::Sell {
+            -quantity
+        } else {
+            quantity
+        };
+        self.cash -= delta * price;
+
+        let position = self.positions.entry(symbol.to_string()).or_default();
+        let updated = position.quantity + delta;
+
+        position.average_price = if position.quantity.is_zero()
+            || position.quantity.is_sign_positive() != updated.is_sign_positive()
+        {
+            // Opened or flipped: the remainder was opened at the fill price
+            price
+        } else if delta.is_sign_positive() == position.quantity.is_sign_positive() {
+            (position.average_price * position.quantity + price * delta) / updated
+        } else {
+            position.average_price
+        };
+        position.quantity = updated;
+
+        if position.quantity.is_zero() {
+            self.positions.remove(symbol);
+        }
+    }
+}
+
+/// Событие симулятора, рассылаемое подписчикам.
+#[derive(Clone, Debug)]
+enum PaperEvent {
+    Order(Box<OrderState>),
+    Trade(AccountTrade),
+}
+
+/// Заявка в симуляторе.
+#[derive(Clone, Debug)]
+struct PaperOrder {
+    state: OrderState,
+    /// Время истечения срока действия заявки `TimeInForce::Day`.
+    expires_at: Option<SystemTime>,
+    /// Сработало ли условие стоп заявки.
+    triggered: bool,
+}
+
+/// Состояние симулятора: заявки, аккаунты и последние рыночные цены.
+#[derive(Debug, Default)]
+struct PaperState {
+    initial_cash: Decimal,
+    ledgers: HashMap<String, PaperLedger>,
+    orders: HashMap<String, PaperOrder>,
+    markets: HashMap<String, MarketSnapshot>,
+    trades: Vec<AccountTrade>,
+    sequence: u64,
+}
+
+impl PaperState {
+    fn new(initial_cash: Decimal) -> Self {
+        Self {
+            initial_cash,
+            ..Default::default()
+        }
+    }
+
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.sequence += 1;
+        format!("{prefix}{}", self.sequence)
+    }
+
+    fn ledger(&mut self, account_id: &str) -> &mut PaperLedger {
+        let initial_cash = self.initial_cash;
+        self.ledgers
+            .entry(account_id.to_string())
+            .or_insert_with(|| PaperLedger {
+                cash: initial_cash,
+                ..Default::default()
+            })
+    }
+
+    /// Принимает заявку и пытается исполнить ее по текущим ценам.
+    ///
+    /// Дневная заявка истекает в `day_end`.
+    fn submit(
+        &mut self,
+        order: Order,
+        now: SystemTime,
+        day_end: SystemTime,
+    ) -> Result<Vec<PaperEvent>, FinamSdkError> {
+        let quantity = validate_order(&order)?;
+        let expires_at = match order.time_in_force() {
+            TimeInForce::Day | TimeInForce::Unspecified => Some(day_end),
+            _ => None,
+        };
+
+        let order_id = self.next_id("paper-");
+        let state = OrderState {
+            order_id: order_id.clone(),
+            exec_id: order_id.clone(),
+            status: OrderStatus::New as i32,
+            order: Some(order),
+            transact_at: Some(Timestamp::from(now)),
+            accept_at: Some(Timestamp::from(now)),
+            initial_quantity: Some(from_decimal(quantity)),
+            executed_quantity: Some(from_decimal(Decimal::ZERO)),
+            remaining_quantity: Some(from_decimal(quantity)),
+            ..Default::default()
+        };
+
+        self.orders.insert(
+            order_id.clone(),
+            PaperOrder {
+                state: state.clone(),
+                expires_at,
+                triggered: false,
+            },
+        );
+
+        let mut events = vec![PaperEvent::Order(Box::new(state))];
+        let filled = self.try_fill(&order_id, None, now, &mut events);
+
+        if !filled {
+            let order = self.orders[&order_id]
+                .state
+                .order
+                .as_ref()
+                .expect("paper orders keep parameters");
+
+            let market = order.r#type() == OrderType::Market;
+            let immediate = matches!(order.time_in_force(), TimeInForce::Ioc | TimeInForce::Fok);
+
+            if market {
+                // There is no price to fill a market order against
+                self.finish(&order_id, OrderStatus::Rejected, now, &mut events);
+            } else if immediate {
+                self.finish(&order_id, OrderStatus::Canceled, now, &mut events);
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Обновляет рыночные цены инструмента и исполняет подходящие заявки.
+    fn on_market(
+        &mut self,
+        symbol: &str,
+        update: MarketSnapshot,
+        print: Option<Decimal>,
+        now: SystemTime,
+    ) -> Vec<PaperEvent> {
+        let market = self.markets.entry(symbol.to_string()).or_default();
+        market.bid = update.bid.or(market.bid);
+        market.ask = update.ask.or(market.ask);
+        market.last = print.or(update.last).or(market.last);
+
+        let mut ids: Vec<_> = self
+            .orders
+            .iter()
+            .filter(|(_, paper)| {
+                paper.state.status().is_active()
+                    && paper
+                        .state
+                        .order
+                        .as_ref()
+                        .is_some_and(|order| order.symbol == symbol)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+
+        let mut events = Vec::new();
+        for id in ids {
+            self.try_fill(&id, print, now, &mut events);
+        }
+
+        events
+    }
+
+    /// Отменяет активную заявку.
+    fn cancel(
+        &mut self,
+        account_id: &str,
+        order_id: &str,
+        now: SystemTime,
+    ) -> Result<Vec<PaperEvent>, FinamSdkError> {
+        let paper = self
+            .orders
+            .get(order_id)
+            .filter(|paper| {
+                paper
+                    .state
+                    .order
+                    .as_ref()
+                    .is_some_and(|order| order.account_id == account_id)
+            })
+            .ok_or_else(|| not_found(order_id))?;
+
+        if !paper.state.status().is_active() {
+            return Err(FinamSdkError::Status(tonic::Status::failed_precondition(
+                format!("order {order_id} is not active"),
+            )));
+        }
+
+        let mut events = Vec::new();
+        self.finish(order_id, OrderStatus::Canceled, now, &mut events);
+        Ok(events)
+    }
+
+    /// Завершает заявки, срок действия которых истек.
+    fn expire(&mut self, now: SystemTime) -> Vec<PaperEvent> {
+        let expired: Vec<_> = self
+            .orders
+            .iter()
+            .filter(|(_, paper)| {
+                paper.state.status().is_active()
+                    && paper.expires_at.is_some_and(|expires_at| expires_at <= now)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut events = Vec::new();
+        for id in expired {
+            self.finish(&id, OrderStatus::Expired, now, &mut events);
+        }
+
+        events
+    }
+
+    /// Исполняет заявку целиком, если позволяют текущие цены.
+    ///
+    /// Глубина стакана не моделируется, поэтому частичного исполнения нет.
+    fn try_fill(
+        &mut self,
+        order_id: &str,
+        print: Option<Decimal>,
+        now: SystemTime,
+        events: &mut Vec<PaperEvent>,
+    ) -> bool {
+        let market = {
+            let Some(paper) = self.orders.get(order_id) else {
+                return false;
+            };
+            let Some(order) = paper.state.order.as_ref() else {
+                return false;
+            };
+            self.markets.get(&order.symbol).copied().unwrap_or_default()
+        };
+
+        let paper = self.orders.get_mut(order_id).expect("order exists");
+        let order = paper
+            .state
+            .order
+            .clone()
+            .expect("paper orders keep parameters");
+
+        if !paper.triggered {
+            paper.triggered = stop_triggered(&order, &market, print);
+            if !paper.triggered {
+                return false;
+            }
+        }
+
+        let Some(price) = execution_price(&order, &market, print) else {
+            return false;
+        };
+        let quantity = opt_decimal(order.quantity.as_ref()).unwrap_or_default();
+
+        let trade_id = self.next_id("paper-trade-");
+        self.ledger(&order.account_id)
+            .apply_fill(&order.symbol, order.side(), quantity, price);
+
+        let trade = AccountTrade {
+            trade_id,
+            symbol: order.symbol.clone(),
+            price: Some(from_decimal(price)),
+            size: Some(from_decimal(quantity)),
+            side: order.side,
+            timestamp: Some(Timestamp::from(now)),
+            order_id: order_id.to_string(),
+            account_id: order.account_id.clone(),
+            comment: order.comment.clone(),
+        };
+        self.trades.push(trade.clone());
+        events.push(PaperEvent::Trade(trade));
+
+        let paper = self.orders.get_mut(order_id).expect("order exists");
+        paper.state.executed_quantity = Some(from_decimal(quantity));
+        paper.state.remaining_quantity = Some(from_decimal(Decimal::ZERO));
+        paper.state.status = OrderStatus::Filled as i32;
+        events.push(PaperEvent::Order(Box::new(paper.state.clone())));
+
+        true
+    }
+
+    fn finish(
+        &mut self,
+        order_id: &str,
+        status: OrderStatus,
+        now: SystemTime,
+        events: &mut Vec<PaperEvent>,
+    ) {
+        if let Some(paper) = self.orders.get_mut(order_id) {
+            paper.state.status = status as i32;
+            paper.state.withdraw_at = Some(Timestamp::from(now));
+            events.push(PaperEvent::Order(Box::new(paper.state.clone())));
+        }
+    }
+
+    fn account(
+        &mut self,
+        account_id: &str,
+        currency: &str,
+    ) -> Result<GetAccountResponse, FinamSdkError> {
+        let markets = self.markets.clone();
+        let ledger = self.ledger(account_id).clone();
+
+        let mut equity = ledger.cash;
+        let mut unrealized_profit = Decimal::ZERO;
+        let mut symbols: Vec<_> = ledger.positions.keys().collect();
+        symbols.sort();
+
+        let positions = symbols
+            .into_iter()
+            .map(|symbol| {
+                let position = ledger.positions[symbol];
+                let current_price = markets
+                    .get(symbol)
+                    .and_then(|market| market.last)
+                    .unwrap_or(position.average_price);
+                let pnl = (current_price - position.average_price) * position.quantity;
+
+                equity += current_price * position.quantity;
+                unrealized_profit += pnl;
+
+                Position {
+                    symbol: symbol.clone(),
+                    quantity: Some(from_decimal(position.quantity)),
+                    average_price: Some(from_decimal(position.average_price)),
+                    current_price: Some(from_decimal(current_price)),
+                    unrealized_pnl: Some(from_decimal(pnl)),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Ok(GetAccountResponse {
+            account_id: account_id.to_string(),
+            r#type: "PAPER".to_string(),
+            status: "ACCOUNT_ACTIVE".to_string(),
+            equity: Some(from_decimal(equity)),
+            unrealized_profit: Some(from_decimal(unrealized_profit)),
+            positions,
+            cash: vec![Money::from_decimal(ledger.cash, currency)?],
+            ..Default::default()
+        })
+    }
+}
+
+/// Симулятор исполнения заявок по живым рыночным данным.
+///
+/// Обслуживает торговые методы `FinamSdk` в режиме бумажной торговли. Заявки
+/// исполняются по лучшим ценам из потока котировок и по ценам сделок ленты,
+/// денежные средства и позиции учитываются в памяти процесса.
+#[derive(Clone, Debug)]
+pub(crate) struct PaperBroker {
+    inner: Arc<PaperInner>,
+    /// Удерживает фоновые задачи симулятора. При уничтожении последней ссылки
+    /// отправляет сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Arc<ShutdownGuard>,
+}
+
+#[derive(Debug)]
+struct PaperInner {
+    market_data: FinamMarketDataServiceClient,
+    assets: FinamAssetsServiceClient,
+    currency: String,
+    state: Mutex<PaperState>,
+    orders: broadcast::Sender<OrderState>,
+    trades: broadcast::Sender<AccountTrade>,
+    feeds: Mutex<HashMap<String, JoinHandle<()>>>,
+    /// Торговые календари инструментов для срока действия дневных заявок.
+    calendars: Mutex<HashMap<String, TradingCalendar>>,
+    /// Часы сервера для времени сделок и истечения заявок.
+    clock: ServerClock,
+}
+
+impl PaperBroker {
+    /// Запускает симулятор.
+    ///
+    /// # Аргументы
+    ///
+    /// * `market_data` - Клиент рыночных данных для получения цен.
+    /// * `assets` - Клиент инструментов для получения расписания торгов.
+    /// * `config` - Настройки бумажной торговли.
+    /// * `clock` - Часы сервера.
+    pub(crate) fn start(
+        market_data: FinamMarketDataServiceClient,
+        assets: FinamAssetsServiceClient,
+        config: PaperTradingConfig,
+        clock: ServerClock,
+    ) -> Self {
+        let (orders, _) = broadcast::channel(EVENTS_CAPACITY);
+        let (trades, _) = broadcast::channel(EVENTS_CAPACITY);
+        let inner = Arc::new(PaperInner {
+            market_data,
+            assets,
+            currency: config.currency,
+            state: Mutex::new(PaperState::new(config.initial_cash)),
+            orders,
+            trades,
+            feeds: Mutex::new(HashMap::new()),
+            calendars: Mutex::new(HashMap::new()),
+            clock,
+        });
+
+        let (shutdown_sender, mut shutdown_receiver) = oneshot::channel();
+        let expiry = inner.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(EXPIRY_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_receiver => {
+                        log::info!("Paper broker shutting down");
+                        for (_, feed) in expiry.lock_feeds().drain() {
+                            feed.abort();
+                        }
+                        return;
+                    }
+                    _ = interval.tick() => {
+                        let events = expiry.lock_state().expire(expiry.clock.now());
+                        expiry.publish(events);
+                    }
+                }
+            }
+        });
+
+        Self {
+            inner,
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        }
+    }
+
+    pub(crate) async fn place_order(&self, order: Order) -> Result<OrderState, FinamSdkError> {
+        let symbol = order.symbol.clone();
+
+        if !self.inner.lock_state().markets.contains_key(&symbol) {
+            let snapshot = self.inner.load_snapshot(&symbol).await?;
+            self.inner
+                .lock_state()
+                .on_market(&symbol, snapshot, None, self.inner.clock.now());
+        }
+
+        let now = self.inner.clock.now();
+        let day_end = self.inner.day_end(&symbol, now).await;
+        let events = self.inner.lock_state().submit(order, now, day_end)?;
+        let state = events
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                PaperEvent::Order(state) => Some(state.as_ref().clone()),
+                PaperEvent::Trade(_) => None,
+            })
+            .expect("submit reports the order state");
+
+        self.inner.publish(events);
+
+        if state.status().is_active() {
+            self.inner.clone().ensure_feed(&symbol);
+        }
+
+        Ok(state)
+    }
+
+    pub(crate) fn cancel_order(
+        &self,
+        account_id: &str,
+        order_id: &str,
+    ) -> Result<OrderState, FinamSdkError> {
+        let events =
+            self.inner
+                .lock_state()
+                .cancel(account_id, order_id, self.inner.clock.now())?;
+        self.inner.publish(events);
+
+        self.get_order(account_id, order_id)
+    }
+
+    pub(crate) fn get_order(
+        &self,
+        account_id: &str,
+        order_id: &str,
+    ) -> Result<OrderState, FinamSdkError> {
+        self.inner
+            .lock_state()
+            .orders
+            .get(order_id)
+            .map(|paper| paper.state.clone())
+            .filter(|state| {
+                state
+                    .order
+                    .as_ref()
+                    .is_some_and(|order| order.account_id == account_id)
+            })
+            .ok_or_else(|| not_found(order_id))
+    }
+
+    pub(crate) fn get_orders(&self, account_id: &str) -> Vec<OrderState> {
+        let mut orders: Vec<_> = self
+            .inner
+            .lock_state()
+            .orders
+            .values()
+            .map(|paper| paper.state.clone())
+            .filter(|state| {
+                state
+                    .order
+                    .as_ref()
+                    .is_some_and(|order| order.account_id == account_id)
+            })
+            .collect();
+        orders.sort_by_key(|state| (state.order_id.len(), state.order_id.clone()));
+        orders
+    }
+
+    /// Возвращает сделки аккаунта за период в порядке исполнения.
+    pub(crate) fn trades(&self, account_id: &str, interval: &Interval) -> Vec<AccountTrade> {
+        let key = |timestamp: Option<&Timestamp>| timestamp.map(|time| (time.seconds, time.nanos));
+        let start = key(interval.start_time.as_ref());
+        let end = key(interval.end_time.as_ref());
+
+        self.inner
+            .lock_state()
+            .trades
+            .iter()
+            .filter(|trade| trade.account_id == account_id)
+            .filter(|trade| {
+                let time = key(trade.timestamp.as_ref());
+                start.is_none_or(|start| time >= Some(start))
+                    && end.is_none_or(|end| time < Some(end))
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn subscribe_orders(&self, account_id: &str) -> OrderStream {
+        let account_id = account_id.to_string();
+        broadcast_stream(self.inner.orders.subscribe(), move |state: &OrderState| {
+            state
+                .order
+                .as_ref()
+                .is_some_and(|order| order.account_id == account_id)
+        })
+    }
+
+    pub(crate) fn subscribe_trades(&self, account_id: &str) -> TradeStream {
+        let account_id = account_id.to_string();
+        broadcast_stream(
+            self.inner.trades.subscribe(),
+            move |trade: &AccountTrade| trade.account_id == account_id,
+        )
+    }
+
+    pub(crate) fn get_account(
+        &self,
+        account_id: &str,
+    ) -> Result<GetAccountResponse, FinamSdkError> {
+        self.inner
+            .lock_state()
+            .account(account_id, &self.inner.currency)
+    }
+}
+
+impl PaperInner {
+    fn lock_state(&self) -> MutexGuard<'_, PaperState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn lock_calendars(&self) -> MutexGuard<'_, HashMap<String, TradingCalendar>> {
+        self.calendars
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn lock_feeds(&self) -> MutexGuard<'_, HashMap<String, JoinHandle<()>>> {
+        self.feeds.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn publish(&self, events: Vec<PaperEvent>) {
+        for event in events {
+            match event {
+                PaperEvent::Order(state) => {
+                    let _ = self.orders.send(*state);
+                }
+                PaperEvent::Trade(trade) => {
+                    let _ = self.trades.send(trade);
+                }
+            }
+        }
+    }
+
+    /// Возвращает срок действия дневной заявки по расписанию инструмента.
+    ///
+    /// Если расписание не загрузилось, срок ограничивается полуночью по Москве.
+    async fn day_end(&self, symbol: &str, now: SystemTime) -> SystemTime {
+        let cached = self
+            .lock_calendars()
+            .get(symbol)
+            .filter(|calendar| calendar.covered_until().is_some_and(|end| now < end))
+            .cloned();
+
+        let calendar = match cached {
+            Some(calendar) => calendar,
+            None => match self
+                .assets
+                .clone()
+                .schedule(ScheduleRequest {
+                    symbol: symbol.to_string(),
+                })
+                .await
+            {
+                Ok(schedule) => {
+                    let calendar = TradingCalendar::from_schedule(&schedule.into_inner());
+                    self.lock_calendars()
+                        .insert(symbol.to_string(), calendar.clone());
+                    calendar
+                }
+                Err(status) => {
+                    log::warn!("Failed to load schedule of {}: {:?}", symbol, status);
+                    return end_of_day(now);
+                }
+            },
+        };
+
+        trading_day_end(&calendar, now)
+    }
+
+    async fn load_snapshot(&self, symbol: &str) -> Result<MarketSnapshot, FinamSdkError> {
+        let quote = self
+            .market_data
+            .clone()
+            .last_quote(QuoteRequest {
+                symbol: symbol.to_string(),
+            })
+            .await?
+            .into_inner()
+            .quote
+            .unwrap_or_default();
+
+        snapshot(&quote.bid, &quote.ask, &quote.last)
+    }
+
+    /// Запускает подписку на рыночные данные инструмента, если она еще не запущена.
+    fn ensure_feed(self: Arc<Self>, symbol: &str) {
+        let mut feeds = self.lock_feeds();
+
+        if feeds.get(symbol).is_some_and(|feed| !feed.is_finished()) {
+            return;
+        }
+
+        let feed = tokio::spawn(self.clone().run_feed(symbol.to_string()));
+        feeds.insert(symbol.to_string(), feed);
+    }
+
+    async fn run_feed(self: Arc<Self>, symbol: String) {
+        loop {
+            if !self.has_active_orders(&symbol) {
+                return;
+            }
+
+            if let Err(error) = self.stream_market(&symbol).await {
+                log::error!(
+                    "Paper market data for {} failed. Reconnecting in 5 seconds... {:?}",
+                    symbol,
+                    error
+                );
+            }
+
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    fn has_active_orders(&self, symbol: &str) -> bool {
+        self.lock_state().orders.values().any(|paper| {
+            paper.state.status().is_active()
+                && paper
+                    .state
+                    .order
+                    .as_ref()
+                    .is_some_and(|order| order.symbol == symbol)
+        })
+    }
+
+    async fn stream_market(&self, symbol: &str) -> Result<(), FinamSdkError> {
+        let mut market_data = self.market_data.clone();
+        let mut quotes = market_data
+            .subscribe_quote(SubscribeQuoteRequest {
+                symbols: vec![symbol.to_string()],
+            })
+            .await?
+            .into_inner();
+        let mut trades = market_data
+            .subscribe_latest_trades(SubscribeLatestTradesRequest {
+                symbol: symbol.to_string(),
+            })
+            .await?
+            .into_inner();
+
+        loop {
+            let events = tokio::select! {
+                response = quotes.next() => {
+                    let Some(response) = response else { return Ok(()) };
+                    let mut events = Vec::new();
+
+                    for quote in response?.quote {
+                        let update = snapshot(&quote.bid, &quote.ask, &quote.last)?;
+                        events.extend(self.lock_state().on_market(symbol, update, None, self.clock.now()));
+                    }
+
+                    events
+                }
+                response = trades.next() => {
+                    let Some(response) = response else { return Ok(()) };
+                    let mut events = Vec::new();
+
+                    for trade in response?.trades {
+                        let price = opt_decimal(trade.price.as_ref())?;
+                        if price > Decimal::ZERO {
+                            events.extend(self.lock_state().on_market(
+                                symbol,
+                                MarketSnapshot::default(),
+                                Some(price),
+                                self.clock.now(),
+                            ));
+                        }
+                    }
+
+                    events
+                }
+            };
+
+            self.publish(events);
+
+            if !self.has_active_orders(symbol) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Проверяет параметры заявки и возвращает ее количество.
+fn validate_order(order: &Order) -> Result<Decimal, FinamSdkError> {
+    let quantity = opt_decimal(order.quantity.as_ref())?;
+
+    if quantity <= Decimal::ZERO || order.side() == Side::Unspecified {
+        return Err(FinamSdkError::InvalidArgument(
+            "order requires side and positive quantity".to_string(),
+        ));
+    }
+
+    let needs_limit = matches!(order.r#type(), OrderType::Limit | OrderType::StopLimit);
+    let needs_stop = matches!(order.r#type(), OrderType::Stop | OrderType::StopLimit);
+
+    if !matches!(
+        order.r#type(),
+        OrderType::Market | OrderType::Limit | OrderType::Stop | OrderType::StopLimit
+    ) {
+        return Err(FinamSdkError::InvalidArgument(format!(
+            "order type {} is not supported in paper trading",
+            order.r#type().as_str_name()
+        )));
+    }
+
+    if (needs_limit && order.limit_price.is_none()) || (needs_stop && order.stop_price.is_none()) {
+        return Err(FinamSdkError::InvalidArgument(format!(
+            "order type {} requires its prices",
+            order.r#type().as_str_name()
+        )));
+    }
+
+    if !matches!(
+        order.time_in_force(),
+        TimeInForce::Unspecified
+            | TimeInForce::Day
+            | TimeInForce::GoodTillCancel
+            | TimeInForce::Ioc
+            | TimeInForce::Fok
+    ) {
+        return Err(FinamSdkError::InvalidArgument(format!(
+            "time in force {} is not supported in paper trading",
+            order.time_in_force().as_str_name()
+        )));
+    }
+
+    Ok(quantity)
+}
+
+/// Проверяет условие срабатывания стоп заявки. Для остальных типов всегда `true`.
+fn stop_triggered(order: &Order, market: &MarketSnapshot, print: Option<Decimal>) -> bool {
+    if !matches!(order.r#type(), OrderType::Stop | OrderType::StopLimit) {
+        return true;
+    }
+
+    let (Ok(stop), Some(last)) = (
+        opt_decimal(order.stop_price.as_ref()),
+        print.or(market.last),
+    ) else {
+        return false;
+    };
+
+    let condition = match order.stop_condition() {
+        StopCondition::Unspecified if order.side() == Side::Buy => StopCondition::LastUp,
+        StopCondition::Unspecified => StopCondition::LastDown,
+        condition => condition,
+    };
+
+    match condition {
+        StopCondition::LastUp => last >= stop,
+        _ => last <= stop,
+    }
+}
+
+/// Определяет цену исполнения заявки или `None`, если заявка не может быть исполнена.
+///
+/// Рыночные заявки исполняются по лучшей встречной цене. Лимитные заявки исполняются
+/// по встречной цене, если она не хуже лимитной, или по лимитной цене, если через нее
+/// прошла сделка в ленте.
+fn execution_price(
+    order: &Order,
+    market: &MarketSnapshot,
+    print: Option<Decimal>,
+) -> Option<Decimal> {
+    let buys = order.side() == Side::Buy;
+    let opposite = if buys { market.ask } else { market.bid };
+
+    match order.r#type() {
+        OrderType::Market | OrderType::Stop => opposite.or(market.last),
+        _ => {
+            let limit = opt_decimal(order.limit_price.as_ref()).ok()?;
+            let crosses = |price: Decimal| if buys { price <= limit } else { price >= limit };
+
+            match (opposite, print) {
+                (Some(price), _) if crosses(price) => Some(price),
+                (_, Some(price)) if crosses(price) => Some(limit),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn snapshot(
+    bid: &Option<crate::proto::google::r#type::Decimal>,
+    ask: &Option<crate::proto::google::r#type::Decimal>,
+    last: &Option<crate::proto::google::r#type::Decimal>,
+) -> Result<MarketSnapshot, FinamSdkError> {
+    let positive = |value: Decimal| (value > Decimal::ZERO).then_some(value);
+
+    Ok(MarketSnapshot {
+        bid: positive(opt_decimal(bid.as_ref())?),
+        ask: positive(opt_decimal(ask.as_ref())?),
+        last: positive(opt_decimal(last.as_ref())?),
+    })
+}
+
+/// Возвращает окончание последней торговой сессии текущего дня по расписанию.
+///
+/// Если до полуночи по Москве торговых сессий больше нет, возвращает полночь.
+fn trading_day_end(calendar: &TradingCalendar, now: SystemTime) -> SystemTime {
+    let midnight = end_of_day(now);
+
+    calendar
+        .sessions()
+        .iter()
+        .filter(|session| session.kind.is_open() && now < session.end && session.end <= midnight)
+        .map(|session| session.end)
+        .max()
+        .unwrap_or(midnight)
+}
+
+/// Возвращает ближайшую полночь по московскому времени.
+fn end_of_day(now: SystemTime) -> SystemTime {
+    let moscow = now.duration_since(UNIX_EPOCH).unwrap_or_default() + MOSCOW_OFFSET;
+    let next_day = (moscow.as_secs() / SECONDS_PER_DAY + 1) * SECONDS_PER_DAY;
+
+    UNIX_EPOCH + Duration::from_secs(next_day) - MOSCOW_OFFSET
+}
+
+fn not_found(order_id: &str) -> FinamSdkError {
+    FinamSdkError::Status(tonic::Status::not_found(format!(
+        "order {order_id} not found"
+    )))
+}
+
+/// Превращает канал рассылки в поток с фильтрацией сообщений.
+fn broadcast_stream<T, F>(
+    receiver: broadcast::Receiver<T>,
+    filter: F,
+) -> BoxStream<'static, Result<T, FinamSdkError>>
+where
+    T: Clone + Send + 'static,
+    F: Fn(&T) -> bool + Send + 'static,
+{
+    futures::stream::unfold((receiver, filter), |(mut receiver, filter)| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(item) if filter(&item) => return Some((Ok(item), (receiver, filter))),
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("Paper trading subscriber lagged by {} messages", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(side: Side, kind: OrderType, quantity: i64, time_in_force: TimeInForce) -> Order {
+        Order {
+            account_id: "A1".to_string(),
+            symbol: "SBER@MISX".to_string(),
+            quantity: Some(from_decimal(Decimal::from(quantity))),
+            side: side as i32,
+            r#type: kind as i32,
+            time_in_force: time_in_force as i32,
+            ..Default::default()
+        }
+    }
+
+    fn market(bid: i64, ask: i64) -> MarketSnapshot {
+        MarketSnapshot {
+            bid: Some(Decimal::from(bid)),
+            ask: Some(Decimal::from(ask)),
+            last: Some(Decimal::from(bid)),
+        }
+    }
+
+    fn last_status(events: &[PaperEvent]) -> OrderStatus {
+        events
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                PaperEvent::Order(state) => Some(state.status()),
+                PaperEvent::Trade(_) => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_market_order_fills_at_opposite_price_and_updates_ledger() {
+        let now = SystemTime::now();
+        let mut state = PaperState::new(Decimal::from(10_000));
+        state.on_market("SBER@MISX", market(99, 101), None, now);
+
+        let events = state
+            .submit(
+                order(Side::Buy, OrderType::Market, 10, TimeInForce::Day),
+                now,
+                end_of_day(now),
+            )
+            .unwrap();
+        assert_eq!(last_status(&events), OrderStatus::Filled);
+        assert_eq!(state.trades.len(), 1);
+        assert_eq!(state.trades[0].account_id, "A1");
+
+        let ledger = state.ledger("A1").clone();
+        assert_eq!(ledger.cash, Decimal::from(8_990));
+        assert_eq!(
+            ledger.positions["SBER@MISX"].average_price,
+            Decimal::from(101)
+        );
+
+        let account = state.account("A1", "RUB").unwrap();
+        assert_eq!(account.equity, Some(from_decimal(Decimal::from(9_980))));
+
+        let mut rich = PaperState::new(Decimal::MAX);
+        assert!(rich.account("A1", "RUB").is_err());
+    }
+
+    #[test]
+    fn test_limit_order_rests_until_crossed_and_ioc_cancels() {
+        let now = SystemTime::now();
+        let mut state = PaperState::new(Decimal::from(10_000));
+        state.on_market("SBER@MISX", market(99, 101), None, now);
+
+        let mut limit = order(Side::Buy, OrderType::Limit, 1, TimeInForce::GoodTillCancel);
+        limit.limit_price = Some(from_decimal(Decimal::from(100)));
+
+        let events = state.submit(limit.clone(), now, end_of_day(now)).unwrap();
+        assert_eq!(last_status(&events), OrderStatus::New);
+
+        let events = state.on_market(
+            "SBER@MISX",
+            MarketSnapshot::default(),
+            Some(Decimal::from(100)),
+            now,
+        );
+        assert_eq!(last_status(&events), OrderStatus::Filled);
+
+        limit.time_in_force = TimeInForce::Ioc as i32;
+        let events = state.submit(limit, now, end_of_day(now)).unwrap();
+        assert_eq!(last_status(&events), OrderStatus::Canceled);
+    }
+
+    #[test]
+    fn test_stop_order_triggers_and_day_order_expires() {
+        let now = SystemTime::now();
+        let mut state = PaperState::new(Decimal::from(10_000));
+        state.on_market("SBER@MISX", market(99, 101), None, now);
+
+        let mut stop = order(Side::Sell, OrderType::Stop, 1, TimeInForce::Day);
+        stop.stop_price = Some(from_decimal(Decimal::from(95)));
+        state.submit(stop, now, end_of_day(now)).unwrap();
+
+        let events = state.on_market("SBER@MISX", market(94, 95), Some(Decimal::from(94)), now);
+        assert_eq!(last_status(&events), OrderStatus::Filled);
+        assert_eq!(
+            state.ledger("A1").positions["SBER@MISX"].quantity,
+            Decimal::from(-1)
+        );
+
+        let mut limit = order(Side::Buy, OrderType::Limit, 1, TimeInForce::Day);
+        limit.limit_price = Some(from_decimal(Decimal::from(50)));
+        state.submit(limit, now, end_of_day(now)).unwrap();
+
+        let events = state.expire(end_of_day(now));
+        assert_eq!(last_status(&events), OrderStatus::Expired);
+    }
+
+    #[test]
+    fn test_day_orders_expire_at_the_last_session_of_the_day() {
+        use crate::proto::grpc::tradeapi::v1::assets::{ScheduleResponse, schedule_response};
+
+        const DAY: u64 = SECONDS_PER_DAY;
+        let at = |seconds: u64| UNIX_EPOCH + Duration::from_secs(seconds);
+        let session = |kind: &str, start: u64, end: u64| schedule_response::Sessions {
+            r#type: kind.to_string(),
+            interval: Some(Interval {
+                start_time: Some(Timestamp::from(at(start))),
+                end_time: Some(Timestamp::from(at(end))),
+            }),
+        };
+        let calendar = TradingCalendar::from_schedule(&ScheduleResponse {
+            symbol: "SBER@MISX".to_string(),
+            sessions: vec![
+                session("CORE_TRADING", DAY + 25_200, DAY + 57_600),
+                session("CLEARING", DAY + 57_600, DAY + 58_500),
+                session("EVENING_TRADING", DAY + 58_500, DAY + 74_400),
+                session("CORE_TRADING", 2 * DAY + 25_200, 2 * DAY + 57_600),
+            ],
+        });
+
+        let now = at(DAY + 30_000);
+        assert_eq!(end_of_day(now), at(2 * DAY - 3 * 60 * 60));
+        assert_eq!(trading_day_end(&calendar, now), at(DAY + 74_400));
+        assert_eq!(
+            trading_day_end(&calendar, at(DAY + 75_000)),
+            at(2 * DAY - 3 * 60 * 60)
+        );
+    }
+
+    #[test]
+    fn test_ledger_average_price_on_increase_reduce_and_flip() {
+        let mut ledger = PaperLedger::default();
+
+        ledger.apply_fill("S", Side::Buy, Decimal::from(10), Decimal::from(100));
+        ledger.apply_fill("S", Side::Buy, Decimal::from(10), Decimal::from(110));
+        assert_eq!(ledger.positions["S"].average_price, Decimal::from(105));
+
+        ledger.apply_fill("S", Side::Sell, Decimal::from(5), Decimal::from(120));
+        assert_eq!(ledger.positions["S"].average_price, Decimal::from(105));
+
+        ledger.apply_fill("S", Side::Sell, Decimal::from(20), Decimal::from(90));
+        assert_eq!(ledger.positions["S"].quantity, Decimal::from(-5));
+        assert_eq!(ledger.positions["S"].average_price, Decimal::from(90));
+    }
+}
@@ -0,0 +1,810 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use futures::{StreamExt, TryStreamExt};
+use prost_types::Timestamp;
+use tokio::sync::{broadcast, oneshot};
+
+use crate::{
+    FinamSdk, FinamSdkError, ShutdownGuard,
+    decimal::{Decimal, money_amount, opt_decimal},
+    oco::strip_tag,
+    proto::{
+        google::r#type::{Date, Interval},
+        grpc::tradeapi::v1::{
+            AccountTrade, Side,
+            accounts::{Transaction, transaction::TransactionCategory},
+        },
+    },
+    trading::TradeStream,
+    util::{EVENTS_CAPACITY, RECONNECT_DELAY, SECONDS_PER_DAY, date_from_days},
+};
+
+/// Метод списания себестоимости при закрытии позиции.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CostBasisMethod {
+    /// Первыми закрываются самые ранние открытые лоты.
+    #[default]
+    Fifo,
+    /// Первыми закрываются самые поздние открытые лоты.
+    Lifo,
+    /// Позиция учитывается по средней цене открытия.
+    AverageCost,
+}
+
+/// Сводка PnL за период, по инструменту или стратегии.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PnlSummary {
+    /// Реализованный PnL без учета комиссий.
+    pub realized: Decimal,
+    /// Уплаченные комиссии.
+    pub commission: Decimal,
+    /// Количество учтенных сделок.
+    pub trades: u32,
+    /// Оборот по сделкам.
+    pub turnover: Decimal,
+}
+
+impl PnlSummary {
+    /// Возвращает реализованный PnL за вычетом комиссий.
+    pub fn net(&self) -> Decimal {
+        self.realized - self.commission
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.realized += other.realized;
+        self.commission += other.commission;
+        self.trades += other.trades;
+        self.turnover += other.turnover;
+    }
+}
+
+/// Открытая позиция по данным движка PnL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpenPosition {
+    /// Количество в шт. со знаком: отрицательное для коротких позиций.
+    pub quantity: Decimal,
+    /// Средняя цена открытых лотов.
+    pub average_price: Decimal,
+}
+
+/// Открытый лот позиции.
+#[derive(Clone, Copy, Debug)]
+struct Lot {
+    quantity: Decimal,
+    price: Decimal,
+}
+
+/// Открытые лоты и последняя стратегия по инструменту.
+#[derive(Clone, Debug, Default)]
+struct SymbolBook {
+    lots: VecDeque<Lot>,
+    strategy: String,
+}
+
+/// Движок расчета реализованного и нереализованного PnL по сделкам аккаунта.
+///
+/// Строит позиции из истории `trades` и потока `subscribe_trades`, не полагаясь
+/// на `average_price` сервера, который не заполняется для FORTS. Комиссии
+/// учитываются по транзакциям категории [`TransactionCategory::Commission`].
+/// Стратегией считается метка `comment` сделки без метки группы OCO
+/// (см. [`strip_tag`]). Комиссия относится к стратегии
+/// последней сделки по инструменту, поэтому транзакции следует применять
+/// после сделок, к которым они относятся. Повторно переданные сделки и
+/// транзакции с известным идентификатором пропускаются.
+///
+/// Цены фьючерсов указаны в пунктах, для расчета PnL в деньгах задайте
+/// стоимость пункта через [`PnlEngine::with_point_value`].
+#[derive(Clone, Debug, Default)]
+pub struct PnlEngine {
+    method: CostBasisMethod,
+    utc_offset: i64,
+    point_values: HashMap<String, Decimal>,
+    books: HashMap<String, SymbolBook>,
+    trade_ids: HashSet<String>,
+    transaction_ids: HashSet<String>,
+    last_trade_at: Option<Timestamp>,
+    days: HashMap<Date, PnlSummary>,
+    symbols: HashMap<String, PnlSummary>,
+    strategies: HashMap<String, PnlSummary>,
+}
+
+impl PnlEngine {
+    /// Создает пустой движок.
+    ///
+    /// # Аргументы
+    ///
+    /// * `method` - Метод списания себестоимости.
+    pub fn new(method: CostBasisMethod) -> Self {
+        Self {
+            method,
+            ..Default::default()
+        }
+    }
+
+    /// Задает смещение от UTC, по которому сделки группируются по дням.
+    ///
+    /// # Аргументы
+    ///
+    /// * `offset` - Смещение в секундах, например `3 * 3600` для московского времени.
+    pub fn with_utc_offset(mut self, offset: i64) -> Self {
+        self.utc_offset = offset;
+        self
+    }
+
+    /// Задает стоимость пункта цены инструмента. По умолчанию равна единице.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    /// * `value` - Стоимость изменения цены на единицу для одной штуки.
+    pub fn with_point_value(mut self, symbol: &str, value: Decimal) -> Self {
+        self.point_values.insert(symbol.to_string(), value);
+        self
+    }
+
+    /// Возвращает метод списания себестоимости.
+    pub fn method(&self) -> CostBasisMethod {
+        self.method
+    }
+
+    /// Учитывает сделку аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `trade` - Сделка из истории или потока сделок.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Option<Decimal>, FinamSdkError>` - Реализованный сделкой PnL, `None` если сделка уже учтена, или ошибку разбора.
+    pub fn apply_trade(&mut self, trade: &AccountTrade) -> Result<Option<Decimal>, FinamSdkError> {
+        if !trade.trade_id.is_empty() && !self.trade_ids.insert(trade.trade_id.clone()) {
+            return Ok(None);
+        }
+
+        let size = opt_decimal(trade.size.as_ref())?;
+        let price = opt_decimal(trade.price.as_ref())?;
+        let point_value = self.point_value(&trade.symbol);
+        let mut remaining = if trade.side() == Side::Sell {
+            -size
+        } else {
+            size
+        };
+
+        let book = self.books.entry(trade.symbol.clone()).or_default();
+        let mut realized = Decimal::ZERO;
+
+        while !remaining.is_zero() {
+            let lot = match self.method {
+                CostBasisMethod::Lifo => book.lots.back_mut(),
+                CostBasisMethod::Fifo | CostBasisMethod::AverageCost => book.lots.front_mut(),
+            };
+            let Some(lot) =
+                lot.filter(|lot| lot.quantity.is_sign_negative() != remaining.is_sign_negative())
+            else {
+                break;
+            };
+
+            let closed = remaining.abs().min(lot.quantity.abs());
+            let direction = if lot.quantity.is_sign_negative() {
+                Decimal::NEGATIVE_ONE
+            } else {
+                Decimal::ONE
+            };
+
+            realized += (price - lot.price) * closed * direction * point_value;
+            lot.quantity -= closed * direction;
+            remaining += closed * direction;
+
+            if lot.quantity.is_zero() {
+                match self.method {
+                    CostBasisMethod::Lifo => book.lots.pop_back(),
+                    CostBasisMethod::Fifo | CostBasisMethod::AverageCost => book.lots.pop_front(),
+                };
+            }
+        }
+
+        if !remaining.is_zero() {
+            match (self.method, book.lots.front_mut()) {
+                (CostBasisMethod::AverageCost, Some(lot)) => {
+                    let quantity = lot.quantity + remaining;
+                    lot.price = (lot.quantity * lot.price + remaining * price) / quantity;
+                    lot.quantity = quantity;
+                }
+                _ => book.lots.push_back(Lot {
+                    quantity: remaining,
+                    price,
+                }),
+            }
+        }
+
+        let strategy = strip_tag(&trade.comment);
+        book.strategy = strategy.to_string();
+
+        let summary = PnlSummary {
+            realized,
+            trades: 1,
+            turnover: size * price * point_value,
+            ..Default::default()
+        };
+        self.record(
+            day_of(trade.timestamp.as_ref(), self.utc_offset),
+            &trade.symbol,
+            strategy,
+            &summary,
+        );
+
+        if sort_key(trade.timestamp.as_ref()) > sort_key(self.last_trade_at.as_ref()) {
+            self.last_trade_at = trade.timestamp;
+        }
+
+        Ok(Some(realized))
+    }
+
+    /// Учитывает транзакцию аккаунта. Учитываются только комиссии.
+    ///
+    /// # Аргументы
+    ///
+    /// * `transaction` - Транзакция из истории `transactions`.
+    ///
+    /// # Возвращает
+    ///
+    /// * `bool` - `true`, если транзакция учтена как комиссия.
+    pub fn apply_transaction(&mut self, transaction: &Transaction) -> bool {
+        if transaction.resolved_category() != TransactionCategory::Commission
+            || (!transaction.id.is_empty() && !self.transaction_ids.insert(transaction.id.clone()))
+        {
+            return false;
+        }
+
+        // Commissions are debited, so the change is negative
+        let commission = -transaction
+            .change
+            .as_ref()
+            .map_or(Decimal::ZERO, money_amount);
+        let strategy = self
+            .books
+            .get(&transaction.symbol)
+            .map(|book| book.strategy.clone())
+            .unwrap_or_default();
+
+        self.record(
+            day_of(transaction.timestamp.as_ref(), self.utc_offset),
+            &transaction.symbol,
+            &strategy,
+            &PnlSummary {
+                commission,
+                ..Default::default()
+            },
+        );
+
+        true
+    }
+
+    /// Возвращает открытую позицию по инструменту.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    pub fn position(&self, symbol: &str) -> Option<OpenPosition> {
+        let lots = &self.books.get(symbol)?.lots;
+        let quantity: Decimal = lots.iter().map(|lot| lot.quantity).sum();
+
+        if quantity.is_zero() {
+            return None;
+        }
+
+        let cost: Decimal = lots.iter().map(|lot| lot.quantity * lot.price).sum();
+
+        Some(OpenPosition {
+            quantity,
+            average_price: cost / quantity,
+        })
+    }
+
+    /// Возвращает открытые позиции по всем инструментам.
+    pub fn positions(&self) -> HashMap<String, OpenPosition> {
+        self.books
+            .keys()
+            .filter_map(|symbol| Some((symbol.clone(), self.position(symbol)?)))
+            .collect()
+    }
+
+    /// Рассчитывает нереализованный PnL позиции по текущей цене.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    /// * `price` - Текущая цена.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Decimal` - Нереализованный PnL, ноль если позиции нет.
+    pub fn unrealized(&self, symbol: &str, price: Decimal) -> Decimal {
+        let point_value = self.point_value(symbol);
+
+        self.books.get(symbol).map_or(Decimal::ZERO, |book| {
+            book.lots
+                .iter()
+                .map(|lot| (price - lot.price) * lot.quantity * point_value)
+                .sum()
+        })
+    }
+
+    /// Рассчитывает суммарный нереализованный PnL по текущим ценам.
+    ///
+    /// Позиции по инструментам без цены пропускаются.
+    ///
+    /// # Аргументы
+    ///
+    /// * `prices` - Текущие цены по символам.
+    pub fn unrealized_total(&self, prices: &HashMap<String, Decimal>) -> Decimal {
+        prices
+            .iter()
+            .map(|(symbol, price)| self.unrealized(symbol, *price))
+            .sum()
+    }
+
+    /// Возвращает сводку по всем сделкам и комиссиям.
+    pub fn total(&self) -> PnlSummary {
+        let mut total = PnlSummary::default();
+        for summary in self.days.values() {
+            total.add(summary);
+        }
+        total
+    }
+
+    /// Возвращает сводки по дням в хронологическом порядке.
+    pub fn by_day(&self) -> Vec<(Date, PnlSummary)> {
+        let mut days: Vec<_> = self
+            .days
+            .iter()
+            .map(|(day, summary)| (*day, *summary))
+            .collect();
+        days.sort_by_key(|(day, _)| (day.year, day.month, day.day));
+        days
+    }
+
+    /// Возвращает сводки по инструментам.
+    pub fn by_symbol(&self) -> &HashMap<String, PnlSummary> {
+        &self.symbols
+    }
+
+    /// Возвращает сводки по стратегиям, заданным меткой `comment` сделок.
+    pub fn by_strategy(&self) -> &HashMap<String, PnlSummary> {
+        &self.strategies
+    }
+
+    /// Возвращает время последней учтенной сделки.
+    pub fn last_trade_at(&self) -> Option<Timestamp> {
+        self.last_trade_at
+    }
+
+    fn point_value(&self, symbol: &str) -> Decimal {
+        self.point_values
+            .get(symbol)
+            .copied()
+            .unwrap_or(Decimal::ONE)
+    }
+
+    fn record(&mut self, day: Date, symbol: &str, strategy: &str, summary: &PnlSummary) {
+        self.days.entry(day).or_default().add(summary);
+        if !symbol.is_empty() {
+            self.symbols
+                .entry(symbol.to_string())
+                .or_default()
+                .add(summary);
+        }
+        self.strategies
+            .entry(strategy.to_string())
+            .or_default()
+            .add(summary);
+    }
+}
+
+/// Сделка, учтенная трекером PnL.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PnlEvent {
+    /// Сделка аккаунта.
+    pub trade: AccountTrade,
+    /// Реализованный сделкой PnL.
+    pub realized: Decimal,
+}
+
+/// Ведет [`PnlEngine`] по потоку сделок `subscribe_trades`.
+///
+/// После подписки и каждого переподключения догружает через `trades` сделки,
+/// совершенные после последней учтенной. Комиссии в потоке не передаются,
+/// их следует применять через [`PnlTracker::update`]. Фоновая задача
+/// завершается при уничтожении последнего клона трекера.
+#[derive(Clone, Debug)]
+pub struct PnlTracker {
+    state: Arc<TrackerState>,
+    /// Удерживает фоновую задачу подписки. При уничтожении последней ссылки
+    /// на трекер отправляет сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Arc<ShutdownGuard>,
+}
+
+impl PnlTracker {
+    /// Запускает учет сделок аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `engine` - Движок, например заполненный через [`FinamSdk::load_pnl_history`].
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Трекер или ошибку подписки.
+    pub async fn start(
+        sdk: FinamSdk,
+        account_id: &str,
+        engine: PnlEngine,
+    ) -> Result<Self, FinamSdkError> {
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        let (shutdown_sender, mut shutdown_receiver) = oneshot::channel();
+
+        let state = Arc::new(TrackerState {
+            account_id: account_id.to_string(),
+            engine: RwLock::new(engine),
+            events,
+        });
+
+        let stream = sdk.subscribe_trades(account_id).await?;
+        state.catch_up(&sdk).await?;
+
+        let runner = state.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = &mut shutdown_receiver => log::info!("PnL tracker shutting down"),
+                _ = runner.run(sdk, stream) => {}
+            }
+        });
+
+        Ok(Self {
+            state,
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        })
+    }
+
+    /// Возвращает копию текущего состояния движка.
+    pub fn engine(&self) -> PnlEngine {
+        self.state.read().clone()
+    }
+
+    /// Изменяет состояние движка, например для применения транзакций.
+    ///
+    /// # Аргументы
+    ///
+    /// * `f` - Функция, получающая изменяемую ссылку на движок.
+    pub fn update<R>(&self, f: impl FnOnce(&mut PnlEngine) -> R) -> R {
+        f(&mut self
+            .state
+            .engine
+            .write()
+            .unwrap_or_else(|error| error.into_inner()))
+    }
+
+    /// Подписывается на учтенные сделки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `broadcast::Receiver<PnlEvent>` - Получатель событий.
+    pub fn subscribe(&self) -> broadcast::Receiver<PnlEvent> {
+        self.state.events.subscribe()
+    }
+}
+
+/// Общее состояние трекера, используемое фоновой задачей.
+#[derive(Debug)]
+struct TrackerState {
+    account_id: String,
+    engine: RwLock<PnlEngine>,
+    events: broadcast::Sender<PnlEvent>,
+}
+
+impl TrackerState {
+    /// Обрабатывает поток сделок, переподключаясь после обрывов.
+    async fn run(&self, sdk: FinamSdk, mut stream: TradeStream) {
+        loop {
+            while let Some(trade) = stream.next().await {
+                let result = trade.and_then(|trade| self.apply(trade));
+                if let Err(error) = result {
+                    log::error!(
+                        "Trade stream for {} failed. Reconnecting in 5 seconds... {:?}",
+                        self.account_id,
+                        error
+                    );
+                    break;
+                }
+            }
+
+            tokio::time::sleep(RECONNECT_DELAY).await;
+
+            match sdk.subscribe_trades(&self.account_id).await {
+                Ok(resubscribed) => stream = resubscribed,
+                Err(error) => {
+                    log::error!(
+                        "Failed to resubscribe to trades for {}: {:?}",
+                        self.account_id,
+                        error
+                    );
+                    continue;
+                }
+            }
+
+            if let Err(error) = self.catch_up(&sdk).await {
+                log::error!(
+                    "Failed to load missed trades for {}: {:?}",
+                    self.account_id,
+                    error
+                );
+            }
+        }
+    }
+
+    /// Догружает сделки, совершенные после последней учтенной.
+    async fn catch_up(&self, sdk: &FinamSdk) -> Result<(), FinamSdkError> {
+        let Some(since) = self.read().last_trade_at() else {
+            return Ok(());
+        };
+
+        let mut trades = sdk.trades_history(
+            &self.account_id,
+            Interval {
+                start_time: Some(since),
+                end_time: Some(Timestamp::from(sdk.server_now())),
+            },
+        )?;
+
+        while let Some(trade) = trades.next().await {
+            self.apply(trade?)?;
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, trade: AccountTrade) -> Result<(), FinamSdkError> {
+        let realized = self
+            .engine
+            .write()
+            .unwrap_or_else(|error| error.into_inner())
+            .apply_trade(&trade)?;
+
+        if let Some(realized) = realized {
+            let _ = self.events.send(PnlEvent { trade, realized });
+        }
+
+        Ok(())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, PnlEngine> {
+        self.engine
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl FinamSdk {
+    /// Загружает в движок PnL сделки и комиссии аккаунта за период.
+    ///
+    /// Сделки и транзакции загружаются постранично через
+    /// [`FinamSdk::trades_history`] и [`FinamSdk::transactions_history`]
+    /// и применяются в хронологическом порядке.
+    ///
+    /// # Аргументы
+    ///
+    /// * `engine` - Движок PnL.
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `interval` - Период истории с заданными началом и окончанием.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку загрузки данных.
+    pub async fn load_pnl_history(
+        &self,
+        engine: &mut PnlEngine,
+        account_id: &str,
+        interval: Interval,
+    ) -> Result<(), FinamSdkError> {
+        let trades: Vec<_> = self
+            .trades_history(account_id, interval)?
+            .try_collect()
+            .await?;
+        let transactions: Vec<_> = self
+            .transactions_history(account_id, interval)?
+            .try_collect()
+            .await?;
+
+        apply_history(engine, &trades, &transactions)
+    }
+}
+
+/// Применяет сделки и транзакции в хронологическом порядке.
+///
+/// При совпадении времени сделки применяются раньше транзакций, чтобы
+/// комиссия относилась к стратегии своей сделки.
+///
+/// # Аргументы
+///
+/// * `engine` - Движок PnL.
+/// * `trades` - Сделки аккаунта.
+/// * `transactions` - Транзакции аккаунта.
+///
+/// # Возвращает
+///
+/// * `Result<(), FinamSdkError>` - Пустой результат или ошибку разбора.
+pub fn apply_history(
+    engine: &mut PnlEngine,
+    trades: &[AccountTrade],
+    transactions: &[Transaction],
+) -> Result<(), FinamSdkError> {
+    let mut trades: Vec<_> = trades.iter().collect();
+    let mut transactions: Vec<_> = transactions.iter().collect();
+    trades.sort_by_key(|trade| sort_key(trade.timestamp.as_ref()));
+    transactions.sort_by_key(|transaction| sort_key(transaction.timestamp.as_ref()));
+
+    let mut transactions = transactions.into_iter().peekable();
+    for trade in trades {
+        let at = sort_key(trade.timestamp.as_ref());
+        while let Some(transaction) =
+            transactions.next_if(|transaction| sort_key(transaction.timestamp.as_ref()) < at)
+        {
+            engine.apply_transaction(transaction);
+        }
+        engine.apply_trade(trade)?;
+    }
+
+    for transaction in transactions {
+        engine.apply_transaction(transaction);
+    }
+
+    Ok(())
+}
+
+fn sort_key(timestamp: Option<&Timestamp>) -> (i64, i32) {
+    timestamp.map_or((0, 0), |timestamp| (timestamp.seconds, timestamp.nanos))
+}
+
+/// Возвращает календарную дату метки времени со смещением от UTC.
+///
+/// Для отсутствующей метки используется текущее время.
+pub(crate) fn day_of(timestamp: Option<&Timestamp>, utc_offset: i64) -> Date {
+    let seconds = timestamp.map_or_else(
+        || Timestamp::from(SystemTime::now()).seconds,
+        |timestamp| timestamp.seconds,
+    );
+
+    date_from_days((seconds + utc_offset).div_euclid(SECONDS_PER_DAY as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decimal::from_decimal, proto::google::r#type::Money};
+
+    fn trade(id: &str, side: Side, size: i64, price: i64, seconds: i64) -> AccountTrade {
+        AccountTrade {
+            trade_id: id.to_string(),
+            symbol: "SBER@MISX".to_string(),
+            price: Some(from_decimal(Decimal::from(price))),
+            size: Some(from_decimal(Decimal::from(size))),
+            side: side as i32,
+            timestamp: Some(Timestamp { seconds, nanos: 0 }),
+            comment: "mean-revert".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn trades() -> Vec<AccountTrade> {
+        vec![
+            trade("t1", Side::Buy, 10, 100, 0),
+            trade("t2", Side::Buy, 10, 110, 60),
+            trade("t3", Side::Sell, 15, 120, SECONDS_PER_DAY as i64),
+        ]
+    }
+
+    #[test]
+    fn test_cost_basis_methods() {
+        let cases = [
+            (CostBasisMethod::Fifo, 250, 110),
+            (CostBasisMethod::Lifo, 200, 100),
+            (CostBasisMethod::AverageCost, 225, 105),
+        ];
+
+        for (method, realized, average_price) in cases {
+            let mut engine = PnlEngine::new(method);
+            apply_history(&mut engine, &trades(), &[]).unwrap();
+
+            assert_eq!(
+                engine.total().realized,
+                Decimal::from(realized),
+                "{method:?}"
+            );
+            assert_eq!(
+                engine.position("SBER@MISX"),
+                Some(OpenPosition {
+                    quantity: Decimal::from(5),
+                    average_price: Decimal::from(average_price),
+                }),
+                "{method:?}"
+            );
+        }
+
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo);
+        apply_history(&mut engine, &trades(), &[]).unwrap();
+
+        assert_eq!(engine.apply_trade(&trades()[0]).unwrap(), None);
+        assert_eq!(
+            engine
+                .apply_trade(&trade("t4", Side::Sell, 10, 90, 120))
+                .unwrap(),
+            Some(Decimal::from(-100))
+        );
+        assert_eq!(
+            engine.position("SBER@MISX").unwrap().quantity,
+            Decimal::from(-5)
+        );
+        assert_eq!(
+            engine.unrealized("SBER@MISX", Decimal::from(80)),
+            Decimal::from(50)
+        );
+    }
+
+    #[test]
+    fn test_commissions_and_reports() {
+        let commission = Transaction {
+            id: "c1".to_string(),
+            symbol: "SBER@MISX".to_string(),
+            timestamp: Some(Timestamp {
+                seconds: SECONDS_PER_DAY as i64,
+                nanos: 0,
+            }),
+            change: Some(Money {
+                currency_code: "RUB".to_string(),
+                units: -3,
+                nanos: -500_000_000,
+            }),
+            transaction_category: TransactionCategory::Commission as i32,
+            ..Default::default()
+        };
+
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo).with_utc_offset(3 * 3600);
+        apply_history(&mut engine, &trades(), std::slice::from_ref(&commission)).unwrap();
+        assert!(!engine.apply_transaction(&commission));
+
+        #[allow(deprecated)]
+        let legacy = Transaction {
+            id: "c2".to_string(),
+            transaction_category: TransactionCategory::Others as i32,
+            category: "COMMISSION".to_string(),
+            ..commission.clone()
+        };
+        assert!(engine.apply_transaction(&legacy));
+
+        let days = engine.by_day();
+        assert_eq!(days.len(), 2);
+        assert_eq!(
+            days[1].0,
+            Date {
+                year: 1970,
+                month: 1,
+                day: 2,
+            }
+        );
+        assert_eq!(days[1].1.net(), Decimal::new(2430, 1));
+        assert_eq!(days[0].1.trades, 2);
+
+        let strategy = engine.by_strategy()["mean-revert"];
+        assert_eq!(strategy.commission, Decimal::new(70, 1));
+        assert_eq!(strategy.trades, 3);
+        assert_eq!(engine.by_symbol()["SBER@MISX"], strategy);
+    }
+}
@@ -0,0 +1,995 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+};
+
+use futures::{
+    StreamExt,
+    future::{join_all, try_join_all},
+};
+use tokio::sync::{broadcast, oneshot};
+
+use crate::{
+    FinamSdk, FinamSdkError, ShutdownGuard,
+    decimal::{Decimal, opt_decimal},
+    money::CurrencyConverter,
+    proto::{
+        google::r#type::Money,
+        grpc::tradeapi::v1::{
+            accounts::{GetAccountRequest, GetAccountResponse, Position, get_account_response},
+            marketdata::QuoteRequest,
+        },
+    },
+    util::{EVENTS_CAPACITY, RECONNECT_DELAY},
+};
+
+/// Портфель аккаунта с разобранными денежными показателями.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Portfolio {
+    /// Счет Московской Биржи: единый или моно счет.
+    Mc {
+        /// Доступные средства, включая маржинальные.
+        available_cash: Decimal,
+        /// Начальная маржа.
+        initial_margin: Decimal,
+        /// Минимальная маржа.
+        maintenance_margin: Decimal,
+    },
+    /// Счет на американских рынках.
+    Mct,
+    /// Счет срочного рынка Московской Биржи.
+    Forts {
+        /// Доступные средства, включая маржинальные.
+        available_cash: Decimal,
+        /// Средства, зарезервированные под открытые позиции.
+        money_reserved: Decimal,
+    },
+}
+
+impl Portfolio {
+    /// Разбирает портфель из информации об аккаунте.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account` - Информация об аккаунте.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Option<Self>, FinamSdkError>` - Портфель, `None` если тип не указан, или ошибку разбора.
+    pub fn from_account(account: &GetAccountResponse) -> Result<Option<Self>, FinamSdkError> {
+        use get_account_response::Portfolio as Proto;
+
+        Ok(match &account.portfolio {
+            Some(Proto::PortfolioMc(mc)) => Some(Self::Mc {
+                available_cash: opt_decimal(mc.available_cash.as_ref())?,
+                initial_margin: opt_decimal(mc.initial_margin.as_ref())?,
+                maintenance_margin: opt_decimal(mc.maintenance_margin.as_ref())?,
+            }),
+            Some(Proto::PortfolioMct(_)) => Some(Self::Mct),
+            Some(Proto::PortfolioForts(forts)) => Some(Self::Forts {
+                available_cash: opt_decimal(forts.available_cash.as_ref())?,
+                money_reserved: opt_decimal(forts.money_reserved.as_ref())?,
+            }),
+            None => None,
+        })
+    }
+
+    /// Возвращает доступные средства, если они известны для типа счета.
+    pub fn available_cash(&self) -> Option<Decimal> {
+        match self {
+            Self::Mc { available_cash, .. } | Self::Forts { available_cash, .. } => {
+                Some(*available_cash)
+            }
+            Self::Mct => None,
+        }
+    }
+
+    /// Возвращает начальную маржу, если она известна для типа счета.
+    pub fn initial_margin(&self) -> Option<Decimal> {
+        match self {
+            Self::Mc { initial_margin, .. } => Some(*initial_margin),
+            _ => None,
+        }
+    }
+
+    /// Возвращает минимальную маржу, если она известна для типа счета.
+    pub fn maintenance_margin(&self) -> Option<Decimal> {
+        match self {
+            Self::Mc {
+                maintenance_margin, ..
+            } => Some(*maintenance_margin),
+            _ => None,
+        }
+    }
+
+    /// Возвращает зарезервированные средства, если они известны для типа счета.
+    pub fn money_reserved(&self) -> Option<Decimal> {
+        match self {
+            Self::Forts { money_reserved, .. } => Some(*money_reserved),
+            _ => None,
+        }
+    }
+}
+
+/// Изменение портфеля между двумя снимками аккаунта.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PortfolioChange {
+    /// Открыта новая позиция.
+    PositionOpened(Position),
+    /// Изменилось количество или средняя цена позиции.
+    PositionChanged {
+        /// Предыдущее состояние позиции.
+        previous: Position,
+        /// Текущее состояние позиции.
+        current: Position,
+    },
+    /// Позиция закрыта. Содержит последнее известное состояние.
+    PositionClosed(Position),
+    /// Изменились денежные средства.
+    CashChanged {
+        /// Предыдущие средства по валютам.
+        previous: Vec<Money>,
+        /// Текущие средства по валютам.
+        current: Vec<Money>,
+    },
+    /// Изменились маржинальные показатели портфеля.
+    MarginChanged {
+        /// Предыдущий портфель.
+        previous: Option<Portfolio>,
+        /// Текущий портфель.
+        current: Option<Portfolio>,
+    },
+}
+
+/// Событие портфеля аккаунта.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortfolioEvent {
+    /// Идентификатор аккаунта.
+    pub account_id: String,
+    /// Изменение портфеля.
+    pub change: PortfolioChange,
+}
+
+/// Отслеживает портфели аккаунтов по потоку `subscribe_account`.
+///
+/// Хранит последний снимок каждого аккаунта и рассылает изменения между
+/// соседними снимками. Изменения текущих цен и PnL позиций событий не порождают,
+/// они доступны в снимке. При обрыве потока трекер переподключается и
+/// загружает аккаунт через `get_account`. Фоновая задача завершается при
+/// уничтожении последнего клона трекера.
+#[derive(Clone, Debug)]
+pub struct PortfolioTracker {
+    snapshots: Arc<RwLock<HashMap<String, GetAccountResponse>>>,
+    events: broadcast::Sender<PortfolioEvent>,
+    updates: broadcast::Sender<GetAccountResponse>,
+    /// Удерживает фоновую задачу подписки. При уничтожении последней ссылки
+    /// на трекер отправляет сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Arc<ShutdownGuard>,
+}
+
+impl PortfolioTracker {
+    /// Запускает отслеживание портфелей аккаунтов.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    /// * `account_ids` - Идентификаторы аккаунтов.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Трекер или ошибку начальной загрузки.
+    pub async fn start(sdk: FinamSdk, account_ids: &[&str]) -> Result<Self, FinamSdkError> {
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        let (updates, _) = broadcast::channel(EVENTS_CAPACITY);
+        let (shutdown_sender, mut shutdown_receiver) = oneshot::channel();
+
+        let tracker = Self {
+            snapshots: Arc::new(RwLock::new(HashMap::new())),
+            events,
+            updates,
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        };
+
+        for account_id in account_ids {
+            tracker.record(sdk.get_account(account_id).await?)?;
+        }
+
+        let runners: Vec<_> = account_ids
+            .iter()
+            .map(|account_id| PortfolioRunner {
+                account_id: account_id.to_string(),
+                snapshots: tracker.snapshots.clone(),
+                events: tracker.events.clone(),
+                updates: tracker.updates.clone(),
+            })
+            .collect();
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = &mut shutdown_receiver => log::info!("Portfolio tracker shutting down"),
+                _ = join_all(runners.into_iter().map(|runner| runner.run(sdk.clone()))) => {}
+            }
+        });
+
+        Ok(tracker)
+    }
+
+    /// Возвращает последний снимок аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    pub fn snapshot(&self, account_id: &str) -> Option<GetAccountResponse> {
+        read_snapshots(&self.snapshots).get(account_id).cloned()
+    }
+
+    /// Возвращает последние снимки всех отслеживаемых аккаунтов.
+    pub fn snapshots(&self) -> Vec<GetAccountResponse> {
+        read_snapshots(&self.snapshots).values().cloned().collect()
+    }
+
+    /// Возвращает разобранный портфель аккаунта по последнему снимку.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Option<Portfolio>, FinamSdkError>` - Портфель, `None` если аккаунт или тип неизвестен, или ошибку разбора.
+    pub fn portfolio(&self, account_id: &str) -> Result<Option<Portfolio>, FinamSdkError> {
+        match read_snapshots(&self.snapshots).get(account_id) {
+            Some(account) => Portfolio::from_account(account),
+            None => Ok(None),
+        }
+    }
+
+    /// Возвращает открытые позиции аккаунта по последнему снимку.
+    pub fn positions(&self, account_id: &str) -> Vec<Position> {
+        read_snapshots(&self.snapshots)
+            .get(account_id)
+            .map(|account| account.positions.clone())
+            .unwrap_or_default()
+    }
+
+    /// Подписывается на события портфелей.
+    ///
+    /// # Возвращает
+    ///
+    /// * `broadcast::Receiver<PortfolioEvent>` - Получатель событий.
+    pub fn subscribe(&self) -> broadcast::Receiver<PortfolioEvent> {
+        self.events.subscribe()
+    }
+
+    /// Подписывается на все снимки аккаунтов, включая изменения цен и PnL.
+    ///
+    /// # Возвращает
+    ///
+    /// * `broadcast::Receiver<GetAccountResponse>` - Получатель снимков.
+    pub fn subscribe_snapshots(&self) -> broadcast::Receiver<GetAccountResponse> {
+        self.updates.subscribe()
+    }
+
+    /// Применяет снимок аккаунта, например ответ `get_account`, и рассылает изменения.
+    ///
+    /// # Аргументы
+    ///
+    /// * `snapshot` - Снимок аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку разбора.
+    pub fn record(&self, snapshot: GetAccountResponse) -> Result<(), FinamSdkError> {
+        apply_snapshot(&self.snapshots, &self.events, &self.updates, snapshot)
+    }
+}
+
+/// Находит изменения портфеля между двумя снимками аккаунта.
+///
+/// Без предыдущего снимка все позиции считаются открытыми, а средства и маржа измененными.
+///
+/// # Аргументы
+///
+/// * `previous` - Предыдущий снимок.
+/// * `current` - Текущий снимок.
+///
+/// # Возвращает
+///
+/// * `Result<Vec<PortfolioChange>, FinamSdkError>` - Изменения или ошибку разбора.
+pub fn diff_snapshots(
+    previous: Option<&GetAccountResponse>,
+    current: &GetAccountResponse,
+) -> Result<Vec<PortfolioChange>, FinamSdkError> {
+    let mut changes = Vec::new();
+    let mut before: HashMap<_, _> = previous
+        .map(|account| {
+            account
+                .positions
+                .iter()
+                .map(|position| (position.symbol.as_str(), position))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for position in &current.positions {
+        let quantity = opt_decimal(position.quantity.as_ref())?;
+
+        match before.remove(position.symbol.as_str()) {
+            Some(old) if quantity.is_zero() => {
+                if !opt_decimal(old.quantity.as_ref())?.is_zero() {
+                    changes.push(PortfolioChange::PositionClosed(position.clone()));
+                }
+            }
+            Some(old) => {
+                if opt_decimal(old.quantity.as_ref())? != quantity
+                    || opt_decimal(old.average_price.as_ref())?
+                        != opt_decimal(position.average_price.as_ref())?
+                {
+                    changes.push(PortfolioChange::PositionChanged {
+                        previous: old.clone(),
+                        current: position.clone(),
+                    });
+                }
+            }
+            None if quantity.is_zero() => {}
+            None => changes.push(PortfolioChange::PositionOpened(position.clone())),
+        }
+    }
+
+    let mut closed: Vec<_> = before.into_values().collect();
+    closed.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    for position in closed {
+        if !opt_decimal(position.quantity.as_ref())?.is_zero() {
+            changes.push(PortfolioChange::PositionClosed(position.clone()));
+        }
+    }
+
+    let previous_cash = previous
+        .map(|account| account.cash.clone())
+        .unwrap_or_default();
+    if previous_cash != current.cash {
+        changes.push(PortfolioChange::CashChanged {
+            previous: previous_cash,
+            current: current.cash.clone(),
+        });
+    }
+
+    let previous_portfolio = previous.map(Portfolio::from_account).transpose()?.flatten();
+    let current_portfolio = Portfolio::from_account(current)?;
+    if previous_portfolio != current_portfolio {
+        changes.push(PortfolioChange::MarginChanged {
+            previous: previous_portfolio,
+            current: current_portfolio,
+        });
+    }
+
+    Ok(changes)
+}
+
+/// Курсы валют для пересчета денежных средств и показателей счетов в базовую валюту.
+///
+/// Курс задается вручную или символом котировки, по последней цене которой
+/// он загружается через [`FxRates::refresh`]. Курс по умолчанию: рубль как
+/// базовая валюта и котировки валют Московской Биржи со сроком расчетов TOM.
+/// Кросс-курсы между небазовыми валютами вычисляются через базовую.
+///
+/// Капитал, PnL и стоимость позиций API возвращает без кода валюты, в валюте
+/// счета. Валюта счета по умолчанию совпадает с базовой и задается через
+/// [`FxRates::with_account_currency`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FxRates {
+    base: String,
+    rates: HashMap<String, Decimal>,
+    quotes: HashMap<String, String>,
+    quoted: HashMap<String, Decimal>,
+    accounts: HashMap<String, String>,
+}
+
+impl Default for FxRates {
+    fn default() -> Self {
+        Self::new("RUB")
+            .with_quote("USD", "USD000UTSTOM@MISX")
+            .with_quote("EUR", "EUR_RUB__TOM@MISX")
+            .with_quote("CNY", "CNYRUB_TOM@MISX")
+    }
+}
+
+impl FxRates {
+    /// Создает набор курсов без заданных валют.
+    ///
+    /// # Аргументы
+    ///
+    /// * `base` - Код базовой валюты ISO 4217.
+    pub fn new(base: &str) -> Self {
+        Self {
+            base: base.to_string(),
+            rates: HashMap::new(),
+            quotes: HashMap::new(),
+            quoted: HashMap::new(),
+            accounts: HashMap::new(),
+        }
+    }
+
+    /// Задает курс валюты: стоимость единицы валюты в базовой валюте.
+    ///
+    /// # Аргументы
+    ///
+    /// * `currency` - Код валюты ISO 4217.
+    /// * `rate` - Курс к базовой валюте.
+    pub fn with_rate(mut self, currency: &str, rate: Decimal) -> Self {
+        self.rates.insert(currency.to_string(), rate);
+        self
+    }
+
+    /// Задает символ котировки, по которой загружается курс валюты.
+    ///
+    /// Заданный вручную курс имеет приоритет над котировкой.
+    ///
+    /// # Аргументы
+    ///
+    /// * `currency` - Код валюты ISO 4217.
+    /// * `symbol` - Символ инструмента, цена которого равна курсу к базовой валюте.
+    pub fn with_quote(mut self, currency: &str, symbol: &str) -> Self {
+        self.quotes.insert(currency.to_string(), symbol.to_string());
+        self
+    }
+
+    /// Задает валюту счета, в которой API возвращает капитал, PnL и стоимость позиций.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `currency` - Код валюты ISO 4217.
+    pub fn with_account_currency(mut self, account_id: &str, currency: &str) -> Self {
+        self.accounts
+            .insert(account_id.to_string(), currency.to_string());
+        self
+    }
+
+    /// Возвращает код базовой валюты.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// Возвращает валюту счета. Если она не задана, возвращается базовая валюта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    pub fn account_currency(&self, account_id: &str) -> &str {
+        self.accounts.get(account_id).unwrap_or(&self.base)
+    }
+
+    /// Возвращает известный курс валюты к базовой.
+    ///
+    /// # Аргументы
+    ///
+    /// * `currency` - Код валюты ISO 4217.
+    pub fn rate(&self, currency: &str) -> Option<Decimal> {
+        if currency == self.base {
+            Some(Decimal::ONE)
+        } else {
+            self.rates
+                .get(currency)
+                .or_else(|| self.quoted.get(currency))
+                .copied()
+        }
+    }
+
+    /// Загружает курсы всех валют, заданных котировками, по последним ценам.
+    ///
+    /// Валюты с заданным вручную курсом пропускаются.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку запроса.
+    pub async fn refresh(&mut self, sdk: &FinamSdk) -> Result<(), FinamSdkError> {
+        let currencies: Vec<_> = self.quotes.keys().cloned().collect();
+        self.refresh_currencies(sdk, currencies.iter().map(String::as_str))
+            .await
+    }
+
+    /// Загружает курсы выбранных валют, заданных котировками.
+    async fn refresh_currencies(
+        &mut self,
+        sdk: &FinamSdk,
+        currencies: impl Iterator<Item = &str>,
+    ) -> Result<(), FinamSdkError> {
+        for currency in currencies {
+            if currency == self.base || self.rates.contains_key(currency) {
+                continue;
+            }
+            let Some(symbol) = self.quotes.get(currency) else {
+                continue;
+            };
+
+            let quote = sdk
+                .market_data()
+                .last_quote(QuoteRequest {
+                    symbol: symbol.clone(),
+                })
+                .await?
+                .into_inner()
+                .quote
+                .unwrap_or_default();
+
+            let price = opt_decimal(quote.last.as_ref())?;
+            if !price.is_zero() {
+                self.quoted.insert(currency.to_string(), price);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CurrencyConverter for FxRates {
+    fn exchange_rate(&self, from: &str, to: &str) -> Option<Decimal> {
+        let to = self.rate(to)?;
+
+        if to.is_zero() {
+            return None;
+        }
+
+        Some(self.rate(from)? / to)
+    }
+}
+
+/// Показатели одного аккаунта в сводном портфеле в базовой валюте.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountSummary {
+    /// Идентификатор аккаунта.
+    pub account_id: String,
+    /// Доступные средства плюс стоимость открытых позиций.
+    pub equity: Decimal,
+    /// Собственные денежные средства в базовой валюте.
+    pub cash: Decimal,
+    /// Нереализованная прибыль.
+    pub unrealized_pnl: Decimal,
+    /// Прибыль или убыток за текущий день по позициям, где он известен.
+    pub daily_pnl: Decimal,
+    /// Суммарная стоимость позиций по модулю.
+    pub gross_exposure: Decimal,
+    /// Суммарная стоимость позиций со знаком.
+    pub net_exposure: Decimal,
+    /// Разобранный портфель аккаунта.
+    pub portfolio: Option<Portfolio>,
+}
+
+/// Позиция по инструменту, объединенная по всем аккаунтам.
+///
+/// Стоимость и PnL пересчитаны в базовую валюту.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsolidatedPosition {
+    /// Символ инструмента.
+    pub symbol: String,
+    /// Суммарное количество в шт. со знаком.
+    pub quantity: Decimal,
+    /// Суммарная стоимость по текущей цене со знаком.
+    pub market_value: Decimal,
+    /// Суммарная нереализованная прибыль.
+    pub unrealized_pnl: Decimal,
+    /// Суммарная прибыль за текущий день.
+    pub daily_pnl: Decimal,
+    /// Количество в шт. по аккаунтам.
+    pub accounts: Vec<(String, Decimal)>,
+}
+
+/// Сводный портфель по нескольким аккаунтам.
+///
+/// Денежные средства пересчитываются в базовую валюту [`FxRates`] по их валютам.
+/// Стоимость позиций, капитал и PnL пересчитываются из валюты счета, заданной
+/// [`FxRates::with_account_currency`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsolidatedPortfolio {
+    /// Код базовой валюты.
+    pub base_currency: String,
+    /// Суммарный капитал.
+    pub equity: Decimal,
+    /// Суммарные денежные средства в базовой валюте.
+    pub cash: Decimal,
+    /// Денежные средства по валютам без пересчета.
+    pub cash_by_currency: HashMap<String, Decimal>,
+    /// Суммарная нереализованная прибыль.
+    pub unrealized_pnl: Decimal,
+    /// Суммарная прибыль за текущий день.
+    pub daily_pnl: Decimal,
+    /// Суммарная стоимость позиций по модулю.
+    pub gross_exposure: Decimal,
+    /// Суммарная стоимость позиций со знаком.
+    pub net_exposure: Decimal,
+    /// Позиции, объединенные по символам и упорядоченные по символу.
+    pub positions: Vec<ConsolidatedPosition>,
+    /// Показатели по аккаунтам.
+    pub accounts: Vec<AccountSummary>,
+}
+
+/// Объединяет снимки аккаунтов в сводный портфель.
+///
+/// # Аргументы
+///
+/// * `accounts` - Снимки аккаунтов.
+/// * `rates` - Курсы и валюты счетов для пересчета в базовую валюту.
+///
+/// # Возвращает
+///
+/// * `Result<ConsolidatedPortfolio, FinamSdkError>` - Сводный портфель или ошибку разбора либо отсутствия курса.
+pub fn consolidate(
+    accounts: &[GetAccountResponse],
+    rates: &FxRates,
+) -> Result<ConsolidatedPortfolio, FinamSdkError> {
+    let mut consolidated = ConsolidatedPortfolio {
+        base_currency: rates.base().to_string(),
+        equity: Decimal::ZERO,
+        cash: Decimal::ZERO,
+        cash_by_currency: HashMap::new(),
+        unrealized_pnl: Decimal::ZERO,
+        daily_pnl: Decimal::ZERO,
+        gross_exposure: Decimal::ZERO,
+        net_exposure: Decimal::ZERO,
+        positions: Vec::new(),
+        accounts: Vec::new(),
+    };
+    let mut positions: HashMap<&str, ConsolidatedPosition> = HashMap::new();
+
+    for account in accounts {
+        let currency = rates.account_currency(&account.account_id);
+        let rate = rates.exchange_rate(currency, rates.base()).ok_or_else(|| {
+            FinamSdkError::InvalidArgument(format!("no FX rate for {currency} to {}", rates.base()))
+        })?;
+
+        let mut summary = AccountSummary {
+            account_id: account.account_id.clone(),
+            equity: opt_decimal(account.equity.as_ref())? * rate,
+            cash: Decimal::ZERO,
+            unrealized_pnl: opt_decimal(account.unrealized_profit.as_ref())? * rate,
+            daily_pnl: Decimal::ZERO,
+            gross_exposure: Decimal::ZERO,
+            net_exposure: Decimal::ZERO,
+            portfolio: Portfolio::from_account(account)?,
+        };
+
+        for money in &account.cash {
+            summary.cash += rates.convert(money, rates.base())?.amount();
+            *consolidated
+                .cash_by_currency
+                .entry(money.currency_code.clone())
+                .or_default() += money.amount();
+        }
+
+        for position in &account.positions {
+            let quantity = opt_decimal(position.quantity.as_ref())?;
+            let value = quantity * opt_decimal(position.current_price.as_ref())? * rate;
+            let daily_pnl = opt_decimal(position.daily_pnl.as_ref())? * rate;
+
+            summary.daily_pnl += daily_pnl;
+            summary.gross_exposure += value.abs();
+            summary.net_exposure += value;
+
+            let merged = positions
+                .entry(position.symbol.as_str())
+                .or_insert_with(|| ConsolidatedPosition {
+                    symbol: position.symbol.clone(),
+                    quantity: Decimal::ZERO,
+                    market_value: Decimal::ZERO,
+                    unrealized_pnl: Decimal::ZERO,
+                    daily_pnl: Decimal::ZERO,
+                    accounts: Vec::new(),
+                });
+            merged.quantity += quantity;
+            merged.market_value += value;
+            merged.unrealized_pnl += opt_decimal(position.unrealized_pnl.as_ref())? * rate;
+            merged.daily_pnl += daily_pnl;
+            merged.accounts.push((account.account_id.clone(), quantity));
+        }
+
+        consolidated.equity += summary.equity;
+        consolidated.cash += summary.cash;
+        consolidated.unrealized_pnl += summary.unrealized_pnl;
+        consolidated.daily_pnl += summary.daily_pnl;
+        consolidated.gross_exposure += summary.gross_exposure;
+        consolidated.net_exposure += summary.net_exposure;
+        consolidated.accounts.push(summary);
+    }
+
+    consolidated.positions = positions.into_values().collect();
+    consolidated
+        .positions
+        .sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+    Ok(consolidated)
+}
+
+impl FinamSdk {
+    /// Получает идентификаторы аккаунтов, доступных по текущему токену.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Vec<String>, FinamSdkError>` - Идентификаторы аккаунтов или ошибку.
+    pub async fn account_ids(&self) -> Result<Vec<String>, FinamSdkError> {
+        Ok(self.token_details().await?.account_ids)
+    }
+
+    /// Загружает все доступные по токену аккаунты и объединяет их в сводный портфель.
+    ///
+    /// Аккаунты загружаются параллельно. Курсы валют без заданного значения
+    /// загружаются по котировкам из `rates`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `rates` - Курсы и валюты счетов для пересчета в базовую валюту.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<ConsolidatedPortfolio, FinamSdkError>` - Сводный портфель или ошибку.
+    pub async fn portfolio_all(
+        &self,
+        rates: &FxRates,
+    ) -> Result<ConsolidatedPortfolio, FinamSdkError> {
+        let account_ids = self.account_ids().await?;
+        let accounts = try_join_all(
+            account_ids
+                .iter()
+                .map(|account_id| self.get_account(account_id)),
+        )
+        .await?;
+
+        let currencies: HashSet<_> = accounts
+            .iter()
+            .flat_map(|account| &account.cash)
+            .map(|money| money.currency_code.as_str())
+            .chain(
+                accounts
+                    .iter()
+                    .map(|account| rates.account_currency(&account.account_id)),
+            )
+            .collect();
+        let mut rates = rates.clone();
+        rates
+            .refresh_currencies(self, currencies.into_iter())
+            .await?;
+
+        consolidate(&accounts, &rates)
+    }
+}
+
+/// Фоновая подписка на поток одного аккаунта.
+struct PortfolioRunner {
+    account_id: String,
+    snapshots: Arc<RwLock<HashMap<String, GetAccountResponse>>>,
+    events: broadcast::Sender<PortfolioEvent>,
+    updates: broadcast::Sender<GetAccountResponse>,
+}
+
+impl PortfolioRunner {
+    /// Поддерживает подписку на аккаунт, переподключаясь после обрывов.
+    async fn run(self, sdk: FinamSdk) {
+        let mut reconnect = false;
+
+        loop {
+            if let Err(error) = self.subscribe(&sdk, reconnect).await {
+                log::error!(
+                    "Account stream for {} failed. Reconnecting in 5 seconds... {:?}",
+                    self.account_id,
+                    error
+                );
+            }
+
+            reconnect = true;
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    /// Подписывается на поток аккаунта и применяет снимки до его завершения.
+    async fn subscribe(&self, sdk: &FinamSdk, reload: bool) -> Result<(), FinamSdkError> {
+        let mut stream = sdk
+            .accounts()
+            .subscribe_account(GetAccountRequest {
+                account_id: self.account_id.clone(),
+            })
+            .await?
+            .into_inner();
+
+        if reload {
+            apply_snapshot(
+                &self.snapshots,
+                &self.events,
+                &self.updates,
+                sdk.get_account(&self.account_id).await?,
+            )?;
+        }
+
+        while let Some(snapshot) = stream.next().await {
+            apply_snapshot(&self.snapshots, &self.events, &self.updates, snapshot?)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Сохраняет снимок аккаунта, рассылает его и изменения относительно предыдущего.
+fn apply_snapshot(
+    snapshots: &RwLock<HashMap<String, GetAccountResponse>>,
+    events: &broadcast::Sender<PortfolioEvent>,
+    updates: &broadcast::Sender<GetAccountResponse>,
+    snapshot: GetAccountResponse,
+) -> Result<(), FinamSdkError> {
+    let changes = {
+        let mut snapshots = snapshots.write().unwrap_or_else(|error| error.into_inner());
+        let changes = diff_snapshots(snapshots.get(&snapshot.account_id), &snapshot)?;
+        snapshots.insert(snapshot.account_id.clone(), snapshot.clone());
+        changes
+    };
+
+    for change in changes {
+        let _ = events.send(PortfolioEvent {
+            account_id: snapshot.account_id.clone(),
+            change,
+        });
+    }
+
+    let _ = updates.send(snapshot);
+
+    Ok(())
+}
+
+fn read_snapshots(
+    snapshots: &RwLock<HashMap<String, GetAccountResponse>>,
+) -> std::sync::RwLockReadGuard<'_, HashMap<String, GetAccountResponse>> {
+    snapshots.read().unwrap_or_else(|error| error.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        decimal::from_decimal,
+        proto::grpc::tradeapi::v1::accounts::{Forts, Mc},
+    };
+
+    fn position(symbol: &str, quantity: i64, price: i64) -> Position {
+        Position {
+            symbol: symbol.to_string(),
+            quantity: Some(from_decimal(Decimal::from(quantity))),
+            average_price: Some(from_decimal(Decimal::from(price))),
+            current_price: Some(from_decimal(Decimal::from(price))),
+            ..Default::default()
+        }
+    }
+
+    fn account(positions: Vec<Position>, cash: i64, margin: i64) -> GetAccountResponse {
+        GetAccountResponse {
+            account_id: "A1".to_string(),
+            positions,
+            cash: vec![Money {
+                currency_code: "RUB".to_string(),
+                units: cash,
+                nanos: 0,
+            }],
+            portfolio: Some(get_account_response::Portfolio::PortfolioMc(Mc {
+                available_cash: Some(from_decimal(Decimal::from(cash))),
+                initial_margin: Some(from_decimal(Decimal::from(margin))),
+                maintenance_margin: Some(from_decimal(Decimal::from(margin / 2))),
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let before = account(
+            vec![
+                position("SBER@MISX", 10, 300),
+                position("GAZP@MISX", 5, 150),
+            ],
+            1000,
+            200,
+        );
+        let mut moved = position("SBER@MISX", 10, 300);
+        moved.current_price = Some(from_decimal(Decimal::from(305)));
+        let after = account(vec![moved, position("LKOH@MISX", 1, 7000)], 1000, 200);
+
+        assert_eq!(
+            diff_snapshots(Some(&before), &after).unwrap(),
+            vec![
+                PortfolioChange::PositionOpened(position("LKOH@MISX", 1, 7000)),
+                PortfolioChange::PositionClosed(position("GAZP@MISX", 5, 150)),
+            ]
+        );
+
+        let after = account(vec![position("SBER@MISX", 20, 310)], 500, 400);
+        let changes = diff_snapshots(Some(&before), &after).unwrap();
+
+        assert!(matches!(
+            changes[0],
+            PortfolioChange::PositionChanged { .. }
+        ));
+        assert!(matches!(changes[2], PortfolioChange::CashChanged { .. }));
+        assert!(matches!(
+            changes[3],
+            PortfolioChange::MarginChanged {
+                current: Some(Portfolio::Mc { initial_margin, .. }),
+                ..
+            } if initial_margin == Decimal::from(400)
+        ));
+    }
+
+    #[test]
+    fn test_consolidate_merges_accounts() {
+        let first = account(vec![position("SBER@MISX", 10, 300)], 1000, 200);
+        let mut second = account(
+            vec![
+                position("SBER@MISX", -4, 300),
+                position("GAZP@MISX", 5, 150),
+            ],
+            500,
+            100,
+        );
+        second.account_id = "A2".to_string();
+        second.cash.push(Money {
+            currency_code: "USD".to_string(),
+            units: 10,
+            nanos: 500_000_000,
+        });
+
+        assert!(matches!(
+            consolidate(&[first.clone(), second.clone()], &FxRates::new("RUB")),
+            Err(FinamSdkError::InvalidArgument(_))
+        ));
+
+        let rates = FxRates::new("RUB").with_rate("USD", Decimal::from(90));
+        let portfolio = consolidate(&[first.clone(), second.clone()], &rates).unwrap();
+
+        assert_eq!(portfolio.cash, Decimal::from(2445));
+        assert_eq!(portfolio.cash_by_currency["USD"], Decimal::new(105, 1));
+        assert_eq!(portfolio.gross_exposure, Decimal::from(4950));
+        assert_eq!(portfolio.net_exposure, Decimal::from(2550));
+        assert_eq!(portfolio.positions[1].symbol, "SBER@MISX");
+        assert_eq!(portfolio.positions[1].quantity, Decimal::from(6));
+        assert_eq!(
+            portfolio.positions[1].accounts,
+            vec![
+                ("A1".to_string(), Decimal::from(10)),
+                ("A2".to_string(), Decimal::from(-4)),
+            ]
+        );
+        assert_eq!(portfolio.accounts[1].cash, Decimal::from(1445));
+
+        let in_usd = rates.clone().with_account_currency("A2", "USD");
+        assert_eq!(in_usd.account_currency("A1"), "RUB");
+        let portfolio = consolidate(&[first, second], &in_usd).unwrap();
+        assert_eq!(portfolio.gross_exposure, Decimal::from(178_500));
+        assert_eq!(portfolio.net_exposure, Decimal::from(-37_500));
+        assert_eq!(portfolio.positions[1].market_value, Decimal::from(-105_000));
+
+        let rates = rates.with_rate("EUR", Decimal::from(100));
+        assert_eq!(
+            rates.exchange_rate("EUR", "USD"),
+            Some(Decimal::from(100) / Decimal::from(90))
+        );
+        assert_eq!(rates.exchange_rate("CNY", "RUB"), None);
+    }
+
+    #[test]
+    fn test_portfolio_accessors() {
+        let forts = GetAccountResponse {
+            portfolio: Some(get_account_response::Portfolio::PortfolioForts(Forts {
+                available_cash: Some(from_decimal(Decimal::from(100))),
+                money_reserved: Some(from_decimal(Decimal::from(40))),
+            })),
+            ..Default::default()
+        };
+        let portfolio = Portfolio::from_account(&forts).unwrap().unwrap();
+
+        assert_eq!(portfolio.available_cash(), Some(Decimal::from(100)));
+        assert_eq!(portfolio.money_reserved(), Some(Decimal::from(40)));
+        assert_eq!(portfolio.initial_margin(), None);
+    }
+}
@@ -0,0 +1,69 @@
+use tokio::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Ограничитель частоты запросов к API Финам.
+///
+/// Выдает разрешения не чаще заданного количества в секунду, равномерно
+/// распределяя запросы во времени. Может разделяться между задачами через `Arc`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Создает ограничитель частоты запросов.
+    ///
+    /// # Аргументы
+    ///
+    /// * `requests_per_second` - Максимальное количество запросов в секунду.
+    ///   Нулевое значение отключает ограничение.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Self` - Новый ограничитель.
+    pub fn new(requests_per_second: u32) -> Self {
+        let interval = if requests_per_second == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(1) / requests_per_second
+        };
+
+        Self {
+            interval,
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Ожидает разрешения на выполнение очередного запроса.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let now = Instant::now();
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + self.interval;
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter_spaces_requests() {
+        let limiter = RateLimiter::new(10);
+        let started = Instant::now();
+
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+
+        assert_eq!(started.elapsed(), Duration::from_millis(400));
+    }
+}
@@ -0,0 +1,465 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use futures::TryStreamExt;
+use prost_types::Timestamp;
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    decimal::{Decimal, opt_decimal},
+    journal::{JournalReader, journal_record::Entry},
+    proto::{
+        google::r#type::Interval,
+        grpc::tradeapi::v1::{AccountTrade, Side, orders::OrderState},
+    },
+};
+
+/// Ожидаемое состояние аккаунта, с которым сверяются данные брокера.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExpectedState {
+    /// Последние известные состояния заявок по идентификатору.
+    pub orders: HashMap<String, OrderState>,
+    /// Идентификаторы учтенных сделок.
+    pub trade_ids: HashSet<String>,
+    /// Ожидаемые позиции по символам: количество в шт. со знаком.
+    pub positions: HashMap<String, Decimal>,
+}
+
+impl ExpectedState {
+    /// Восстанавливает ожидаемое состояние аккаунта из журнала.
+    ///
+    /// Заявки берутся из ответов сервера и событий потока заявок, позиции
+    /// складываются из записанных сделок. Позиции, открытые до начала ведения
+    /// журнала, следует добавить в `positions` отдельно.
+    ///
+    /// # Аргументы
+    ///
+    /// * `reader` - Прочитанный журнал.
+    /// * `account_id` - Идентификатор аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Ожидаемое состояние или ошибку разбора количества.
+    pub fn from_journal(reader: &JournalReader, account_id: &str) -> Result<Self, FinamSdkError> {
+        let mut state = Self::default();
+
+        for record in reader
+            .records()
+            .iter()
+            .filter(|record| record.account_id == account_id)
+        {
+            match &record.entry {
+                Some(Entry::OrderResponse(order) | Entry::OrderUpdate(order)) => {
+                    state.orders.insert(order.order_id.clone(), order.clone());
+                }
+                Some(Entry::Trade(trade)) => state.apply_trade(trade)?,
+                _ => {}
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Учитывает сделку в ожидаемых позициях, если она еще не учтена.
+    ///
+    /// # Аргументы
+    ///
+    /// * `trade` - Сделка аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку разбора количества.
+    pub fn apply_trade(&mut self, trade: &AccountTrade) -> Result<(), FinamSdkError> {
+        if self.trade_ids.insert(trade.trade_id.clone()) {
+            *self.positions.entry(trade.symbol.clone()).or_default() += signed_size(trade)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Действие с неизвестной активной заявкой.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownOrderAction {
+    /// Принять заявку в ожидаемое состояние.
+    Adopt,
+    /// Отменить заявку.
+    Cancel,
+    /// Только сообщить о расхождении.
+    #[default]
+    Alert,
+}
+
+/// Действие с расхождением по сделкам или позициям.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiscrepancyAction {
+    /// Принять данные брокера в ожидаемое состояние.
+    Adopt,
+    /// Только сообщить о расхождении.
+    #[default]
+    Alert,
+}
+
+/// Правила обработки расхождений при сверке.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReconcilePolicy {
+    /// Действие с активными заявками, отсутствующими в ожидаемом состоянии.
+    pub unknown_orders: UnknownOrderAction,
+    /// Действие со сделками, отсутствующими в ожидаемом состоянии.
+    pub missing_fills: DiscrepancyAction,
+    /// Действие с позициями, не объяснимыми сделками.
+    pub position_mismatches: DiscrepancyAction,
+    /// Глубина загрузки сделок.
+    pub trades_lookback: Duration,
+}
+
+impl Default for ReconcilePolicy {
+    fn default() -> Self {
+        Self {
+            unknown_orders: UnknownOrderAction::default(),
+            missing_fills: DiscrepancyAction::default(),
+            position_mismatches: DiscrepancyAction::default(),
+            trades_lookback: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// Расхождение между ожидаемым состоянием и данными брокера.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Discrepancy {
+    /// Активная заявка брокера отсутствует в ожидаемом состоянии.
+    UnknownOrder(OrderState),
+    /// Статус заявки у брокера отличается от ожидаемого.
+    OrderChanged {
+        /// Ожидаемое состояние заявки.
+        expected: Box<OrderState>,
+        /// Состояние заявки у брокера.
+        actual: Box<OrderState>,
+    },
+    /// Ожидаемая активная заявка отсутствует в списке заявок брокера.
+    MissingOrder(OrderState),
+    /// Сделка брокера отсутствует в ожидаемом состоянии.
+    MissingFill(AccountTrade),
+    /// Позиция брокера не совпадает с ожидаемой с учетом неучтенных сделок.
+    PositionMismatch {
+        /// Символ инструмента.
+        symbol: String,
+        /// Ожидаемое количество с учетом неучтенных сделок.
+        expected: Decimal,
+        /// Количество у брокера.
+        actual: Decimal,
+    },
+}
+
+/// Результат сверки.
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    /// Расхождения, принятые в ожидаемое состояние.
+    pub adopted: Vec<Discrepancy>,
+    /// Расхождения, требующие внимания.
+    pub alerts: Vec<Discrepancy>,
+    /// Отмененные неизвестные заявки.
+    pub canceled: Vec<OrderState>,
+    /// Идентификаторы неизвестных заявок, которые не удалось отменить, и причины ошибок.
+    pub cancel_failed: Vec<(String, FinamSdkError)>,
+    /// Ожидаемое состояние после применения правил.
+    pub state: ExpectedState,
+}
+
+impl ReconcileReport {
+    /// Проверяет, совпало ли ожидаемое состояние с данными брокера.
+    pub fn is_clean(&self) -> bool {
+        self.adopted.is_empty()
+            && self.alerts.is_empty()
+            && self.canceled.is_empty()
+            && self.cancel_failed.is_empty()
+    }
+}
+
+impl FinamSdk {
+    /// Сверяет ожидаемое состояние аккаунта с заявками, сделками и позициями брокера.
+    ///
+    /// Изменившиеся статусы известных заявок всегда принимаются, так как брокер
+    /// является источником истины. Остальные расхождения обрабатываются по правилам.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `expected` - Ожидаемое состояние, например восстановленное из журнала.
+    /// * `policy` - Правила обработки расхождений.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<ReconcileReport, FinamSdkError>` - Результат сверки или ошибку загрузки данных.
+    pub async fn reconcile(
+        &self,
+        account_id: &str,
+        expected: &ExpectedState,
+        policy: &ReconcilePolicy,
+    ) -> Result<ReconcileReport, FinamSdkError> {
+        let orders = self.get_orders(account_id).await?;
+
+        let now = self.server_now();
+        let trades: Vec<AccountTrade> = self
+            .trades_history(
+                account_id,
+                Interval {
+                    start_time: Some(Timestamp::from(now - policy.trades_lookback)),
+                    end_time: Some(Timestamp::from(now)),
+                },
+            )?
+            .try_collect()
+            .await?;
+
+        let mut positions = HashMap::new();
+        for position in self.get_account(account_id).await?.positions {
+            positions.insert(
+                position.symbol.clone(),
+                opt_decimal(position.quantity.as_ref())?,
+            );
+        }
+
+        let discrepancies = diff(expected, &orders, &trades, &positions)?;
+        let mut report = ReconcileReport {
+            state: expected.clone(),
+            ..Default::default()
+        };
+
+        for discrepancy in discrepancies {
+            match &discrepancy {
+                Discrepancy::UnknownOrder(state) => match policy.unknown_orders {
+                    UnknownOrderAction::Adopt => report.adopt(discrepancy)?,
+                    UnknownOrderAction::Cancel => {
+                        match self.cancel_order(account_id, &state.order_id).await {
+                            Ok(canceled) => report.canceled.push(canceled),
+                            Err(error) => {
+                                report.cancel_failed.push((state.order_id.clone(), error));
+                            }
+                        }
+                    }
+                    UnknownOrderAction::Alert => report.alert(discrepancy),
+                },
+                Discrepancy::OrderChanged { .. } | Discrepancy::MissingOrder(_) => {
+                    report.adopt(discrepancy)?;
+                }
+                Discrepancy::MissingFill(_) => match policy.missing_fills {
+                    DiscrepancyAction::Adopt => report.adopt(discrepancy)?,
+                    DiscrepancyAction::Alert => report.alert(discrepancy),
+                },
+                Discrepancy::PositionMismatch { .. } => match policy.position_mismatches {
+                    DiscrepancyAction::Adopt => report.adopt(discrepancy)?,
+                    DiscrepancyAction::Alert => report.alert(discrepancy),
+                },
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+impl ReconcileReport {
+    fn adopt(&mut self, discrepancy: Discrepancy) -> Result<(), FinamSdkError> {
+        match &discrepancy {
+            Discrepancy::UnknownOrder(state) => {
+                self.state
+                    .orders
+                    .insert(state.order_id.clone(), state.clone());
+            }
+            Discrepancy::OrderChanged { actual, .. } => {
+                self.state
+                    .orders
+                    .insert(actual.order_id.clone(), (**actual).clone());
+            }
+            Discrepancy::MissingOrder(state) => {
+                self.state.orders.remove(&state.order_id);
+            }
+            Discrepancy::MissingFill(trade) => self.state.apply_trade(trade)?,
+            Discrepancy::PositionMismatch { symbol, actual, .. } => {
+                self.state.positions.insert(symbol.clone(), *actual);
+            }
+        }
+
+        self.adopted.push(discrepancy);
+        Ok(())
+    }
+
+    fn alert(&mut self, discrepancy: Discrepancy) {
+        log::warn!("Reconciliation discrepancy: {:?}", discrepancy);
+        self.alerts.push(discrepancy);
+    }
+}
+
+/// Находит расхождения между ожидаемым состоянием и данными брокера.
+///
+/// Позиция считается расходящейся, только если ее не объясняют неучтенные сделки.
+///
+/// # Аргументы
+///
+/// * `expected` - Ожидаемое состояние.
+/// * `orders` - Заявки брокера.
+/// * `trades` - Сделки брокера.
+/// * `positions` - Позиции брокера по символам.
+///
+/// # Возвращает
+///
+/// * `Result<Vec<Discrepancy>, FinamSdkError>` - Найденные расхождения или ошибку разбора количества.
+pub fn diff(
+    expected: &ExpectedState,
+    orders: &[OrderState],
+    trades: &[AccountTrade],
+    positions: &HashMap<String, Decimal>,
+) -> Result<Vec<Discrepancy>, FinamSdkError> {
+    let mut discrepancies = Vec::new();
+    let actual: HashMap<_, _> = orders
+        .iter()
+        .map(|state| (state.order_id.as_str(), state))
+        .collect();
+
+    for state in orders {
+        match expected.orders.get(&state.order_id) {
+            None if state.status().is_active() => {
+                discrepancies.push(Discrepancy::UnknownOrder(state.clone()));
+            }
+            Some(known) if known.status != state.status => {
+                discrepancies.push(Discrepancy::OrderChanged {
+                    expected: Box::new(known.clone()),
+                    actual: Box::new(state.clone()),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mut missing: Vec<_> = expected
+        .orders
+        .values()
+        .filter(|state| state.status().is_active() && !actual.contains_key(state.order_id.as_str()))
+        .collect();
+    missing.sort_by(|a, b| a.order_id.cmp(&b.order_id));
+    discrepancies.extend(
+        missing
+            .into_iter()
+            .map(|state| Discrepancy::MissingOrder(state.clone())),
+    );
+
+    let mut explained = expected.positions.clone();
+    for trade in trades {
+        if !expected.trade_ids.contains(&trade.trade_id) {
+            *explained.entry(trade.symbol.clone()).or_default() += signed_size(trade)?;
+            discrepancies.push(Discrepancy::MissingFill(trade.clone()));
+        }
+    }
+
+    let mut symbols: Vec<_> = explained.keys().chain(positions.keys()).collect();
+    symbols.sort();
+    symbols.dedup();
+
+    for symbol in symbols {
+        let expected = explained.get(symbol).copied().unwrap_or_default();
+        let actual = positions.get(symbol).copied().unwrap_or_default();
+
+        if expected != actual {
+            discrepancies.push(Discrepancy::PositionMismatch {
+                symbol: symbol.clone(),
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(discrepancies)
+}
+
+fn signed_size(trade: &AccountTrade) -> Result<Decimal, FinamSdkError> {
+    let size = opt_decimal(trade.size.as_ref())?;
+
+    Ok(if trade.side() == Side::Sell {
+        -size
+    } else {
+        size
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decimal::from_decimal, proto::grpc::tradeapi::v1::orders::OrderStatus};
+
+    fn order(order_id: &str, status: OrderStatus) -> OrderState {
+        OrderState {
+            order_id: order_id.to_string(),
+            status: status as i32,
+            ..Default::default()
+        }
+    }
+
+    fn trade(trade_id: &str, side: Side, size: i64) -> AccountTrade {
+        AccountTrade {
+            trade_id: trade_id.to_string(),
+            symbol: "SBER@MISX".to_string(),
+            side: side as i32,
+            size: Some(from_decimal(Decimal::from(size))),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_finds_orders_fills_and_positions() {
+        let mut expected = ExpectedState::default();
+        expected
+            .orders
+            .insert("1".to_string(), order("1", OrderStatus::New));
+        expected
+            .orders
+            .insert("2".to_string(), order("2", OrderStatus::New));
+        expected.apply_trade(&trade("t1", Side::Buy, 10)).unwrap();
+
+        let orders = [
+            order("1", OrderStatus::Filled),
+            order("3", OrderStatus::New),
+        ];
+        let trades = [trade("t1", Side::Buy, 10), trade("t2", Side::Sell, 4)];
+        let positions = HashMap::from([
+            ("SBER@MISX".to_string(), Decimal::from(6)),
+            ("GAZP@MISX".to_string(), Decimal::from(1)),
+        ]);
+
+        let discrepancies = diff(&expected, &orders, &trades, &positions).unwrap();
+
+        assert_eq!(
+            discrepancies,
+            vec![
+                Discrepancy::OrderChanged {
+                    expected: Box::new(order("1", OrderStatus::New)),
+                    actual: Box::new(order("1", OrderStatus::Filled)),
+                },
+                Discrepancy::UnknownOrder(order("3", OrderStatus::New)),
+                Discrepancy::MissingOrder(order("2", OrderStatus::New)),
+                Discrepancy::MissingFill(trade("t2", Side::Sell, 4)),
+                Discrepancy::PositionMismatch {
+                    symbol: "GAZP@MISX".to_string(),
+                    expected: Decimal::ZERO,
+                    actual: Decimal::ONE,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_adopt_updates_expected_state() {
+        let mut report = ReconcileReport::default();
+
+        report
+            .adopt(Discrepancy::MissingFill(trade("t1", Side::Sell, 3)))
+            .unwrap();
+        report
+            .adopt(Discrepancy::UnknownOrder(order("7", OrderStatus::New)))
+            .unwrap();
+
+        assert_eq!(report.state.positions["SBER@MISX"], Decimal::from(-3));
+        assert!(report.state.orders.contains_key("7"));
+        assert!(!report.is_clean());
+    }
+}
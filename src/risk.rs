@@ -0,0 +1,814 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    decimal::{Decimal, opt_decimal, to_decimal},
+    proto::grpc::tradeapi::v1::{
+        Side,
+        accounts::GetAccountResponse,
+        marketdata::{Quote, QuoteRequest},
+        orders::{Order, OrderState, SltpOrder},
+    },
+};
+
+/// Параметры заявки, проверяемые правилами риск-менеджмента.
+///
+/// Единое представление для обычных и SL/TP заявок.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderIntent {
+    /// Идентификатор аккаунта.
+    pub account_id: String,
+    /// Символ инструмента.
+    pub symbol: String,
+    /// Сторона заявки.
+    pub side: Side,
+    /// Количество в шт.
+    pub quantity: Decimal,
+    /// Цена заявки. `None` для рыночных заявок.
+    pub price: Option<Decimal>,
+}
+
+impl OrderIntent {
+    /// Возвращает количество со знаком: положительное для покупки, отрицательное для продажи.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Decimal` - Изменение позиции при полном исполнении заявки.
+    pub fn signed_quantity(&self) -> Decimal {
+        match self.side {
+            Side::Sell => -self.quantity,
+            _ => self.quantity,
+        }
+    }
+
+    /// Разбирает заявку на проверяемые параметры.
+    ///
+    /// Для мультилег заявки возвращает отдельные параметры для каждой ноги:
+    /// количество ноги умножается на количество комбинаций, а при продаже
+    /// комбинации стороны ног меняются на противоположные. Цена комбинации
+    /// к ногам не относится, поэтому ноги проверяются как рыночные заявки.
+    ///
+    /// # Аргументы
+    ///
+    /// * `order` - Параметры заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Vec<OrderIntent>, FinamSdkError>` - Параметры для проверки или ошибку.
+    pub fn from_order(order: &Order) -> Result<Vec<Self>, FinamSdkError> {
+        if order.legs.is_empty() {
+            return Ok(vec![Self::try_from(order)?]);
+        }
+
+        let quantity = opt_decimal(order.quantity.as_ref())?;
+
+        order
+            .legs
+            .iter()
+            .map(|leg| {
+                let side = match (order.side(), leg.side()) {
+                    (Side::Sell, Side::Buy) => Side::Sell,
+                    (Side::Sell, Side::Sell) => Side::Buy,
+                    (_, side) => side,
+                };
+
+                Ok(Self {
+                    account_id: order.account_id.clone(),
+                    symbol: leg.symbol.clone(),
+                    side,
+                    quantity: opt_decimal(leg.quantity.as_ref())? * quantity,
+                    price: None,
+                })
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&Order> for OrderIntent {
+    type Error = FinamSdkError;
+
+    fn try_from(order: &Order) -> Result<Self, Self::Error> {
+        if !order.legs.is_empty() {
+            return Err(FinamSdkError::InvalidArgument(
+                "multi-leg order must be checked per leg with OrderIntent::from_order".to_string(),
+            ));
+        }
+
+        let price = order
+            .limit_price
+            .as_ref()
+            .or(order.stop_price.as_ref())
+            .map(to_decimal)
+            .transpose()?;
+
+        Ok(Self {
+            account_id: order.account_id.clone(),
+            symbol: order.symbol.clone(),
+            side: order.side(),
+            quantity: opt_decimal(order.quantity.as_ref())?,
+            price,
+        })
+    }
+}
+
+impl TryFrom<&SltpOrder> for OrderIntent {
+    type Error = FinamSdkError;
+
+    fn try_from(order: &SltpOrder) -> Result<Self, Self::Error> {
+        let price = order
+            .sl_price
+            .as_ref()
+            .or(order.tp_price.as_ref())
+            .map(to_decimal)
+            .transpose()?;
+
+        Ok(Self {
+            account_id: order.account_id.clone(),
+            symbol: order.symbol.clone(),
+            side: order.side(),
+            quantity: opt_decimal(order.quantity_sl.as_ref())?
+                .max(opt_decimal(order.quantity_tp.as_ref())?),
+            price,
+        })
+    }
+}
+
+/// Данные, которые правило запрашивает перед проверкой заявки.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RiskData {
+    /// Информация об аккаунте (позиции, PnL).
+    pub account: bool,
+    /// Список заявок аккаунта.
+    pub orders: bool,
+    /// Последняя котировка по инструменту заявки.
+    pub quote: bool,
+}
+
+impl RiskData {
+    fn merge(self, other: Self) -> Self {
+        Self {
+            account: self.account || other.account,
+            orders: self.orders || other.orders,
+            quote: self.quote || other.quote,
+        }
+    }
+}
+
+/// Рыночные данные и состояние аккаунта на момент проверки заявки.
+#[derive(Clone, Debug, Default)]
+pub struct RiskContext {
+    /// Информация об аккаунте, если ее запросило хотя бы одно правило.
+    pub account: Option<GetAccountResponse>,
+    /// Заявки аккаунта, если их запросило хотя бы одно правило.
+    pub orders: Vec<OrderState>,
+    /// Последняя котировка, если ее запросило хотя бы одно правило.
+    pub quote: Option<Quote>,
+}
+
+impl RiskContext {
+    /// Возвращает текущую позицию по символу со знаком.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Decimal, FinamSdkError>` - Количество в шт. или ноль при отсутствии позиции.
+    pub fn position(&self, symbol: &str) -> Result<Decimal, FinamSdkError> {
+        self.account
+            .iter()
+            .flat_map(|account| account.positions.iter())
+            .find(|position| position.symbol == symbol)
+            .map_or(Ok(Decimal::ZERO), |position| {
+                opt_decimal(position.quantity.as_ref())
+            })
+    }
+
+    /// Возвращает цену последней сделки из котировки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Option<Decimal>, FinamSdkError>` - Цена или `None`, если она неизвестна.
+    pub fn last_price(&self) -> Result<Option<Decimal>, FinamSdkError> {
+        let last = self
+            .quote
+            .as_ref()
+            .and_then(|quote| quote.last.as_ref())
+            .map(to_decimal)
+            .transpose()?;
+
+        Ok(last.filter(|price| !price.is_zero()))
+    }
+}
+
+/// Причина отклонения заявки правилом риск-менеджмента.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RiskViolation {
+    /// Название сработавшего правила.
+    pub rule: String,
+    /// Описание нарушения.
+    pub reason: String,
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.rule, self.reason)
+    }
+}
+
+/// Правило предторговой проверки заявок.
+///
+/// Правила вызываются SDK перед отправкой каждой заявки. Нарушение любого
+/// правила отклоняет заявку с ошибкой `FinamSdkError::RiskRejected`.
+pub trait RiskRule: fmt::Debug + Send + Sync {
+    /// Название правила для сообщений об ошибках.
+    fn name(&self) -> &str;
+
+    /// Данные, необходимые правилу для проверки.
+    fn required_data(&self) -> RiskData {
+        RiskData::default()
+    }
+
+    /// Проверяет заявку.
+    ///
+    /// # Аргументы
+    ///
+    /// * `intent` - Параметры заявки.
+    /// * `context` - Состояние аккаунта и рыночные данные.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), String>` - Пустой результат или описание нарушения.
+    fn check(&self, intent: &OrderIntent, context: &RiskContext) -> Result<(), String>;
+}
+
+/// Набор правил риск-менеджмента, через который проходят все заявки SDK.
+#[derive(Clone, Debug, Default)]
+pub struct RiskEngine {
+    rules: Vec<Arc<dyn RiskRule>>,
+}
+
+impl RiskEngine {
+    /// Добавляет правило в набор.
+    ///
+    /// # Аргументы
+    ///
+    /// * `rule` - Правило проверки заявок.
+    pub fn add_rule(&mut self, rule: impl RiskRule + 'static) {
+        self.rules.push(Arc::new(rule));
+    }
+
+    /// Проверяет, пуст ли набор правил.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Проверяет заявку всеми правилами набора.
+    ///
+    /// # Аргументы
+    ///
+    /// * `intent` - Параметры заявки.
+    /// * `context` - Состояние аккаунта и рыночные данные.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), RiskViolation>` - Пустой результат или первое найденное нарушение.
+    pub fn evaluate(
+        &self,
+        intent: &OrderIntent,
+        context: &RiskContext,
+    ) -> Result<(), RiskViolation> {
+        for rule in &self.rules {
+            rule.check(intent, context)
+                .map_err(|reason| RiskViolation {
+                    rule: rule.name().to_string(),
+                    reason,
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Загружает данные, необходимые правилам, и проверяет заявку.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK для получения данных.
+    /// * `intent` - Параметры заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку `RiskRejected`.
+    pub(crate) async fn check(
+        &self,
+        sdk: &FinamSdk,
+        intent: &OrderIntent,
+    ) -> Result<(), FinamSdkError> {
+        if self.rules.is_empty() {
+            return Ok(());
+        }
+
+        let required = self.rules.iter().fold(RiskData::default(), |data, rule| {
+            data.merge(rule.required_data())
+        });
+
+        let mut context = RiskContext::default();
+
+        if required.account {
+            context.account = Some(sdk.get_account(&intent.account_id).await?);
+        }
+
+        if required.orders {
+            context.orders = sdk.get_orders(&intent.account_id).await?;
+        }
+
+        if required.quote {
+            context.quote = sdk
+                .market_data()
+                .last_quote(QuoteRequest {
+                    symbol: intent.symbol.clone(),
+                })
+                .await?
+                .into_inner()
+                .quote;
+        }
+
+        self.evaluate(intent, &context).map_err(|violation| {
+            log::warn!("Order rejected by risk check: {}", violation);
+            FinamSdkError::RiskRejected(violation)
+        })
+    }
+}
+
+/// Ограничение максимального объема заявки в деньгах (цена × количество).
+///
+/// Для рыночных заявок используется цена последней сделки.
+#[derive(Clone, Debug)]
+pub struct MaxOrderNotional {
+    limit: Decimal,
+}
+
+impl MaxOrderNotional {
+    /// Создает правило.
+    ///
+    /// # Аргументы
+    ///
+    /// * `limit` - Максимальный объем заявки в валюте инструмента.
+    pub fn new(limit: Decimal) -> Self {
+        Self { limit }
+    }
+}
+
+impl RiskRule for MaxOrderNotional {
+    fn name(&self) -> &str {
+        "max_order_notional"
+    }
+
+    fn required_data(&self) -> RiskData {
+        RiskData {
+            quote: true,
+            ..Default::default()
+        }
+    }
+
+    fn check(&self, intent: &OrderIntent, context: &RiskContext) -> Result<(), String> {
+        let price = match intent.price {
+            Some(price) => price,
+            None => context
+                .last_price()
+                .map_err(|error| error.to_string())?
+                .ok_or_else(|| format!("no price to evaluate {} order", intent.symbol))?,
+        };
+
+        let notional = (price * intent.quantity).abs();
+
+        if notional > self.limit {
+            return Err(format!("order notional {notional} exceeds {}", self.limit));
+        }
+
+        Ok(())
+    }
+}
+
+/// Ограничение максимальной позиции по инструменту в шт.
+///
+/// Учитывает текущую позицию из `get_account` и изменение при полном исполнении заявки.
+#[derive(Clone, Debug)]
+pub struct MaxPosition {
+    default_limit: Decimal,
+    limits: HashMap<String, Decimal>,
+}
+
+impl MaxPosition {
+    /// Создает правило с общим лимитом для всех инструментов.
+    ///
+    /// # Аргументы
+    ///
+    /// * `default_limit` - Максимальная абсолютная позиция в шт.
+    pub fn new(default_limit: Decimal) -> Self {
+        Self {
+            default_limit,
+            limits: HashMap::new(),
+        }
+    }
+
+    /// Задает отдельный лимит для инструмента.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    /// * `limit` - Максимальная абсолютная позиция в шт.
+    pub fn with_symbol_limit(mut self, symbol: &str, limit: Decimal) -> Self {
+        self.limits.insert(symbol.to_string(), limit);
+        self
+    }
+}
+
+impl RiskRule for MaxPosition {
+    fn name(&self) -> &str {
+        "max_position"
+    }
+
+    fn required_data(&self) -> RiskData {
+        RiskData {
+            account: true,
+            ..Default::default()
+        }
+    }
+
+    fn check(&self, intent: &OrderIntent, context: &RiskContext) -> Result<(), String> {
+        let limit = self
+            .limits
+            .get(&intent.symbol)
+            .copied()
+            .unwrap_or(self.default_limit);
+        let current = context
+            .position(&intent.symbol)
+            .map_err(|error| error.to_string())?;
+        let resulting = current + intent.signed_quantity();
+
+        if resulting.abs() > limit && resulting.abs() > current.abs() {
+            return Err(format!(
+                "position in {} would be {resulting}, limit is {limit}",
+                intent.symbol
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Ограничение количества одновременно активных заявок на аккаунте.
+#[derive(Clone, Debug)]
+pub struct MaxOpenOrders {
+    limit: usize,
+}
+
+impl MaxOpenOrders {
+    /// Создает правило.
+    ///
+    /// # Аргументы
+    ///
+    /// * `limit` - Максимальное количество активных заявок.
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl RiskRule for MaxOpenOrders {
+    fn name(&self) -> &str {
+        "max_open_orders"
+    }
+
+    fn required_data(&self) -> RiskData {
+        RiskData {
+            orders: true,
+            ..Default::default()
+        }
+    }
+
+    fn check(&self, _intent: &OrderIntent, context: &RiskContext) -> Result<(), String> {
+        let open = context
+            .orders
+            .iter()
+            .filter(|state| state.status().is_active())
+            .count();
+
+        if open >= self.limit {
+            return Err(format!("{open} open orders, limit is {}", self.limit));
+        }
+
+        Ok(())
+    }
+}
+
+/// Ограничение отклонения цены заявки от цены последней сделки.
+#[derive(Clone, Debug)]
+pub struct PriceBand {
+    max_deviation_percent: Decimal,
+}
+
+impl PriceBand {
+    /// Создает правило.
+    ///
+    /// # Аргументы
+    ///
+    /// * `max_deviation_percent` - Допустимое отклонение в процентах от цены последней сделки.
+    pub fn new(max_deviation_percent: Decimal) -> Self {
+        Self {
+            max_deviation_percent,
+        }
+    }
+}
+
+impl RiskRule for PriceBand {
+    fn name(&self) -> &str {
+        "price_band"
+    }
+
+    fn required_data(&self) -> RiskData {
+        RiskData {
+            quote: true,
+            ..Default::default()
+        }
+    }
+
+    fn check(&self, intent: &OrderIntent, context: &RiskContext) -> Result<(), String> {
+        let (Some(price), Some(last)) = (
+            intent.price,
+            context.last_price().map_err(|error| error.to_string())?,
+        ) else {
+            return Ok(());
+        };
+
+        let deviation = ((price - last) / last * Decimal::ONE_HUNDRED).abs();
+
+        if deviation > self.max_deviation_percent {
+            return Err(format!(
+                "price {price} deviates from last {last} by {}%",
+                deviation.round_dp(2)
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Дневной лимит убытка по аккаунту.
+///
+/// Убыток считается по `daily_pnl` позиций, а для позиций без него
+/// (FORTS) - по `unrealized_pnl`. При достижении лимита новые заявки отклоняются,
+/// кроме заявок, которые только сокращают текущую позицию по инструменту.
+#[derive(Clone, Debug)]
+pub struct DailyLossLimit {
+    limit: Decimal,
+}
+
+impl DailyLossLimit {
+    /// Создает правило.
+    ///
+    /// # Аргументы
+    ///
+    /// * `limit` - Максимальный допустимый убыток за день (положительное число).
+    pub fn new(limit: Decimal) -> Self {
+        Self { limit }
+    }
+}
+
+impl RiskRule for DailyLossLimit {
+    fn name(&self) -> &str {
+        "daily_loss_limit"
+    }
+
+    fn required_data(&self) -> RiskData {
+        RiskData {
+            account: true,
+            ..Default::default()
+        }
+    }
+
+    fn check(&self, intent: &OrderIntent, context: &RiskContext) -> Result<(), String> {
+        let current = context
+            .position(&intent.symbol)
+            .map_err(|error| error.to_string())?;
+        let resulting = current + intent.signed_quantity();
+
+        if resulting.abs() < current.abs() && resulting * current >= Decimal::ZERO {
+            return Ok(());
+        }
+
+        let mut pnl = Decimal::ZERO;
+
+        for position in context
+            .account
+            .iter()
+            .flat_map(|account| &account.positions)
+        {
+            let value = position
+                .daily_pnl
+                .as_ref()
+                .or(position.unrealized_pnl.as_ref());
+            pnl += opt_decimal(value).map_err(|error| error.to_string())?;
+        }
+
+        if pnl <= -self.limit {
+            return Err(format!("daily loss {pnl} reached limit {}", self.limit));
+        }
+
+        Ok(())
+    }
+}
+
+/// Белый или черный список инструментов.
+#[derive(Clone, Debug)]
+pub enum SymbolFilter {
+    /// Разрешены только перечисленные инструменты.
+    Allow(HashSet<String>),
+    /// Запрещены перечисленные инструменты.
+    Deny(HashSet<String>),
+}
+
+impl SymbolFilter {
+    /// Создает белый список инструментов.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbols` - Разрешенные символы.
+    pub fn allow<I, S>(symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::Allow(symbols.into_iter().map(Into::into).collect())
+    }
+
+    /// Создает черный список инструментов.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbols` - Запрещенные символы.
+    pub fn deny<I, S>(symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::Deny(symbols.into_iter().map(Into::into).collect())
+    }
+}
+
+impl RiskRule for SymbolFilter {
+    fn name(&self) -> &str {
+        "symbol_filter"
+    }
+
+    fn check(&self, intent: &OrderIntent, _context: &RiskContext) -> Result<(), String> {
+        let allowed = match self {
+            Self::Allow(symbols) => symbols.contains(&intent.symbol),
+            Self::Deny(symbols) => !symbols.contains(&intent.symbol),
+        };
+
+        if !allowed {
+            return Err(format!("trading {} is not allowed", intent.symbol));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decimal::from_decimal, proto::grpc::tradeapi::v1::accounts::Position};
+
+    fn intent(side: Side, quantity: i64, price: Option<i64>) -> OrderIntent {
+        OrderIntent {
+            account_id: "A1".to_string(),
+            symbol: "SBER@MISX".to_string(),
+            side,
+            quantity: Decimal::from(quantity),
+            price: price.map(Decimal::from),
+        }
+    }
+
+    fn context(position: i64, daily_pnl: i64, last: i64) -> RiskContext {
+        RiskContext {
+            account: Some(GetAccountResponse {
+                positions: vec![Position {
+                    symbol: "SBER@MISX".to_string(),
+                    quantity: Some(from_decimal(Decimal::from(position))),
+                    daily_pnl: Some(from_decimal(Decimal::from(daily_pnl))),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            orders: Vec::new(),
+            quote: Some(Quote {
+                last: Some(from_decimal(Decimal::from(last))),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_max_order_notional_uses_last_price_for_market_orders() {
+        let rule = MaxOrderNotional::new(Decimal::from(1000));
+        let context = context(0, 0, 100);
+
+        assert!(rule.check(&intent(Side::Buy, 10, None), &context).is_ok());
+        assert!(rule.check(&intent(Side::Buy, 11, None), &context).is_err());
+        assert!(
+            rule.check(&intent(Side::Buy, 5, Some(300)), &context)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_max_position_allows_reducing_orders() {
+        let rule = MaxPosition::new(Decimal::from(10));
+        let context = context(8, 0, 100);
+
+        assert!(rule.check(&intent(Side::Buy, 2, None), &context).is_ok());
+        assert!(rule.check(&intent(Side::Buy, 3, None), &context).is_err());
+        assert!(rule.check(&intent(Side::Sell, 18, None), &context).is_ok());
+        assert!(rule.check(&intent(Side::Sell, 19, None), &context).is_err());
+    }
+
+    #[test]
+    fn test_price_band_and_daily_loss() {
+        let band = PriceBand::new(Decimal::from(5));
+        let loss = DailyLossLimit::new(Decimal::from(500));
+
+        assert!(
+            band.check(&intent(Side::Buy, 1, Some(104)), &context(0, 0, 100))
+                .is_ok()
+        );
+        assert!(
+            band.check(&intent(Side::Buy, 1, Some(106)), &context(0, 0, 100))
+                .is_err()
+        );
+        assert!(
+            loss.check(&intent(Side::Buy, 1, None), &context(1, -499, 100))
+                .is_ok()
+        );
+        assert!(
+            loss.check(&intent(Side::Buy, 1, None), &context(1, -500, 100))
+                .is_err()
+        );
+        assert!(
+            loss.check(&intent(Side::Sell, 1, None), &context(1, -500, 100))
+                .is_ok()
+        );
+        assert!(
+            loss.check(&intent(Side::Sell, 2, None), &context(1, -500, 100))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_multi_leg_order_is_checked_per_leg() {
+        use crate::proto::grpc::tradeapi::v1::orders::Leg;
+
+        let leg = |symbol: &str, side: Side, ratio: i64| Leg {
+            symbol: symbol.to_string(),
+            quantity: Some(from_decimal(Decimal::from(ratio))),
+            side: side as i32,
+        };
+        let order = Order {
+            account_id: "A1".to_string(),
+            quantity: Some(from_decimal(Decimal::from(3))),
+            side: Side::Sell as i32,
+            limit_price: Some(from_decimal(Decimal::from(5))),
+            legs: vec![leg("C100", Side::Buy, 1), leg("C110", Side::Sell, 2)],
+            ..Default::default()
+        };
+
+        assert!(OrderIntent::try_from(&order).is_err());
+
+        let intents = OrderIntent::from_order(&order).unwrap();
+
+        assert_eq!(intents.len(), 2);
+        assert_eq!(intents[0].symbol, "C100");
+        assert_eq!(intents[0].side, Side::Sell);
+        assert_eq!(intents[0].quantity, Decimal::from(3));
+        assert_eq!(intents[1].side, Side::Buy);
+        assert_eq!(intents[1].quantity, Decimal::from(6));
+        assert_eq!(intents[1].price, None);
+    }
+
+    #[test]
+    fn test_engine_reports_violated_rule() {
+        let mut engine = RiskEngine::default();
+        engine.add_rule(SymbolFilter::deny(["SBER@MISX"]));
+
+        let violation = engine
+            .evaluate(&intent(Side::Buy, 1, None), &RiskContext::default())
+            .unwrap_err();
+
+        assert_eq!(violation.rule, "symbol_filter");
+    }
+}
@@ -0,0 +1,411 @@
+use crate::proto::grpc::tradeapi::v1::assets::{Asset, ExchangesResponse};
+
+/// Максимальное количество результатов поиска по умолчанию.
+const DEFAULT_LIMIT: usize = 20;
+
+/// Фильтр инструментов по признаку архивности.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AssetStatus {
+    /// Любые инструменты.
+    #[default]
+    Any,
+    /// Только активные (неархивные) инструменты.
+    Active,
+    /// Только архивные инструменты.
+    Archived,
+}
+
+impl AssetStatus {
+    fn matches(self, asset: &Asset) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Active => !asset.is_archived,
+            Self::Archived => asset.is_archived,
+        }
+    }
+}
+
+/// Параметры поиска инструментов в каталоге.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// MIC идентификаторы бирж. Пустой список означает любую биржу.
+    pub mics: Vec<String>,
+    /// Фильтр по признаку архивности.
+    pub status: AssetStatus,
+    /// Тип инструмента, например `EQUITIES`. `None` означает любой тип.
+    pub asset_type: Option<String>,
+    /// Максимальное количество результатов.
+    pub limit: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            mics: Vec::new(),
+            status: AssetStatus::Any,
+            asset_type: None,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Добавляет биржу в фильтр.
+    ///
+    /// # Аргументы
+    ///
+    /// * `mic` - MIC идентификатор биржи.
+    pub fn mic(mut self, mic: &str) -> Self {
+        self.mics.push(mic.trim().to_ascii_uppercase());
+        self
+    }
+
+    /// Добавляет в фильтр все биржи из ответа `exchanges`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `exchanges` - Список бирж.
+    pub fn exchanges(self, exchanges: &ExchangesResponse) -> Self {
+        exchanges
+            .exchanges
+            .iter()
+            .fold(self, |options, exchange| options.mic(&exchange.mic))
+    }
+
+    /// Задает фильтр по признаку архивности.
+    ///
+    /// # Аргументы
+    ///
+    /// * `status` - Фильтр.
+    pub fn status(mut self, status: AssetStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Задает тип инструмента.
+    ///
+    /// # Аргументы
+    ///
+    /// * `asset_type` - Тип инструмента, например `EQUITIES`.
+    pub fn asset_type(mut self, asset_type: &str) -> Self {
+        self.asset_type = Some(asset_type.to_string());
+        self
+    }
+
+    /// Задает максимальное количество результатов.
+    ///
+    /// # Аргументы
+    ///
+    /// * `limit` - Количество результатов.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn matches(&self, asset: &Asset) -> bool {
+        self.status.matches(asset)
+            && (self.mics.is_empty()
+                || self
+                    .mics
+                    .iter()
+                    .any(|mic| mic.eq_ignore_ascii_case(&asset.mic)))
+            && self
+                .asset_type
+                .as_ref()
+                .is_none_or(|asset_type| asset_type.eq_ignore_ascii_case(&asset.r#type))
+    }
+}
+
+/// Найденный инструмент.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Инструмент.
+    pub asset: Asset,
+    /// Релевантность: чем больше, тем точнее совпадение.
+    pub score: u32,
+}
+
+/// Нормализованные поля инструмента для поиска.
+///
+/// Строится вместе с индексом каталога, чтобы поиск на каждое нажатие клавиши
+/// не нормализовал названия заново.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SearchKey {
+    ticker: String,
+    isin: String,
+    name: String,
+    words: Vec<String>,
+}
+
+impl SearchKey {
+    pub(crate) fn new(asset: &Asset) -> Self {
+        let words = asset
+            .name
+            .split(|c: char| !c.is_alphanumeric())
+            .map(normalize)
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        Self {
+            ticker: normalize(&asset.ticker),
+            isin: normalize(&asset.isin),
+            name: normalize(&asset.name),
+            words,
+        }
+    }
+
+    fn score(&self, query: &str) -> Option<u32> {
+        if self.ticker == query {
+            return Some(1000);
+        }
+        if self.ticker.starts_with(query) {
+            return Some(900 - (self.ticker.len() - query.len()).min(100) as u32);
+        }
+        if self.name == query {
+            return Some(800);
+        }
+        if self.name.starts_with(query) {
+            return Some(700);
+        }
+        if self.words.iter().any(|word| word.starts_with(query)) {
+            return Some(600);
+        }
+        if self.isin == query {
+            return Some(550);
+        }
+        if query.len() >= 4 && self.isin.starts_with(query) {
+            return Some(500);
+        }
+        if self.ticker.contains(query) || self.name.contains(query) {
+            return Some(400);
+        }
+        if query.len() >= 3 && within_one_edit(&self.ticker, query) {
+            return Some(300);
+        }
+
+        subsequence_gaps(&self.name, query).map(|gaps| 200u32.saturating_sub(gaps as u32).max(1))
+    }
+}
+
+/// Ранжирует инструменты по текстовому запросу.
+///
+/// Запрос и поля инструмента приводятся к нижнему регистру и транслитерируются
+/// с кириллицы, поэтому `сбер`, `SBER` и `sber` находят и тикер `SBER`, и
+/// название «Сбербанк».
+pub(crate) fn rank(
+    assets: &[Asset],
+    keys: &[SearchKey],
+    query: &str,
+    options: &SearchOptions,
+) -> Vec<SearchMatch> {
+    let query = normalize(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    collect(assets, keys, options, |key| key.score(&query))
+}
+
+/// Ранжирует инструменты по полному ISIN или его началу.
+pub(crate) fn rank_isin(
+    assets: &[Asset],
+    keys: &[SearchKey],
+    isin: &str,
+    options: &SearchOptions,
+) -> Vec<SearchMatch> {
+    let isin = normalize(isin);
+    if isin.is_empty() {
+        return Vec::new();
+    }
+
+    collect(assets, keys, options, |key| {
+        if key.isin == isin {
+            Some(1000)
+        } else if key.isin.starts_with(&isin) {
+            Some(500)
+        } else {
+            None
+        }
+    })
+}
+
+fn collect(
+    assets: &[Asset],
+    keys: &[SearchKey],
+    options: &SearchOptions,
+    score: impl Fn(&SearchKey) -> Option<u32>,
+) -> Vec<SearchMatch> {
+    let mut matches: Vec<_> = assets
+        .iter()
+        .zip(keys)
+        .filter(|(asset, _)| options.matches(asset))
+        .filter_map(|(asset, key)| score(key).map(|score| (score, asset)))
+        .collect();
+
+    matches.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then(a.is_archived.cmp(&b.is_archived))
+            .then(a.ticker.len().cmp(&b.ticker.len()))
+            .then_with(|| a.symbol.cmp(&b.symbol))
+    });
+
+    matches
+        .into_iter()
+        .take(options.limit)
+        .map(|(score, asset)| SearchMatch {
+            asset: asset.clone(),
+            score,
+        })
+        .collect()
+}
+
+/// Приводит строку к нижнему регистру, оставляет только буквы и цифры и
+/// транслитерирует кириллицу в латиницу.
+fn normalize(value: &str) -> String {
+    let mut normalized = String::with_capacity(value.len());
+
+    for c in value.chars().flat_map(char::to_lowercase) {
+        match transliterate(c) {
+            Some(latin) => normalized.push_str(latin),
+            None if c.is_alphanumeric() => normalized.push(c),
+            None => {}
+        }
+    }
+
+    normalized
+}
+
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' | 'ё' | 'э' => "e",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'й' => "i",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ы' => "y",
+        'ю' => "yu",
+        'я' => "ya",
+        _ => return None,
+    })
+}
+
+/// Проверяет, отличаются ли строки не более чем на одну вставку, удаление или замену.
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let (a, b): (Vec<_>, Vec<_>) = (a.chars().collect(), b.chars().collect());
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+
+    let prefix = short.iter().zip(&long).take_while(|(x, y)| x == y).count();
+    if prefix == short.len() {
+        true
+    } else if short.len() == long.len() {
+        short[prefix + 1..] == long[prefix + 1..]
+    } else {
+        short[prefix..] == long[prefix + 1..]
+    }
+}
+
+/// Возвращает количество пропущенных символов, если `query` входит в `value`
+/// как подпоследовательность.
+fn subsequence_gaps(value: &str, query: &str) -> Option<usize> {
+    let mut chars = value.chars().enumerate();
+    let mut first = None;
+    let mut last = 0;
+
+    for expected in query.chars() {
+        let (position, _) = chars.find(|(_, c)| *c == expected)?;
+        first.get_or_insert(position);
+        last = position;
+    }
+
+    Some(last + 1 - first.unwrap_or(0) - query.chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(ticker: &str, mic: &str, name: &str, r#type: &str, is_archived: bool) -> Asset {
+        Asset {
+            symbol: format!("{ticker}@{mic}"),
+            ticker: ticker.to_string(),
+            mic: mic.to_string(),
+            isin: format!("RU000{ticker}"),
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+            is_archived,
+            ..Default::default()
+        }
+    }
+
+    fn search(assets: &[Asset], query: &str, options: &SearchOptions) -> Vec<String> {
+        let keys: Vec<_> = assets.iter().map(SearchKey::new).collect();
+        rank(assets, &keys, query, options)
+            .into_iter()
+            .map(|found| found.asset.symbol)
+            .collect()
+    }
+
+    #[test]
+    fn test_search_ranking_is_cyrillic_aware() {
+        let assets = vec![
+            asset("SBERP", "MISX", "Сбербанк ао-п", "EQUITIES", false),
+            asset("SBER", "MISX", "Сбербанк", "EQUITIES", false),
+            asset("SBER", "RUSX", "Сбербанк", "EQUITIES", true),
+            asset("GAZP", "MISX", "Газпром", "EQUITIES", false),
+            asset("SRM5", "RTSX", "SBRF-6.25 Сбербанк", "FUTURES", false),
+        ];
+        let options = SearchOptions::default();
+
+        assert_eq!(
+            search(&assets, "сбер", &options),
+            ["SBER@MISX", "SBER@RUSX", "SBERP@MISX", "SRM5@RTSX"]
+        );
+        assert_eq!(search(&assets, "ГАЗПРОМ", &options), ["GAZP@MISX"]);
+        assert_eq!(search(&assets, "gazp", &options), ["GAZP@MISX"]);
+        assert_eq!(search(&assets, "GAZR", &options), ["GAZP@MISX"]);
+        assert!(search(&assets, " - ", &options).is_empty());
+
+        let filtered = SearchOptions::default()
+            .mic("misx")
+            .status(AssetStatus::Active)
+            .asset_type("equities")
+            .limit(1);
+        assert_eq!(search(&assets, "сбербанк", &filtered), ["SBER@MISX"]);
+    }
+
+    #[test]
+    fn test_search_helpers() {
+        assert!(within_one_edit("gazp", "gazr"));
+        assert!(within_one_edit("gazp", "gaz"));
+        assert!(within_one_edit("sber", "sbesr"));
+        assert!(!within_one_edit("sber", "gazp"));
+        assert_eq!(subsequence_gaps("gazprom", "gzp"), Some(1));
+        assert_eq!(subsequence_gaps("gazprom", "mg"), None);
+        assert_eq!(normalize("Щука-Ёж 2"), "shchukaezh2");
+    }
+}
@@ -0,0 +1,658 @@
+use std::{fmt, time::SystemTime};
+
+use prost_types::Timestamp;
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    decimal::{Decimal, RoundingStrategy, from_decimal, opt_decimal, round_to_step, to_decimal},
+    proto::grpc::tradeapi::v1::{
+        Side,
+        assets::{GetAssetRequest, GetAssetResponse},
+        marketdata::QuoteRequest,
+        orders::{OrderState, OrderStatus, SltpOrder, TpSpreadMeasure, ValidBefore},
+    },
+};
+
+/// Максимальная длина `client_order_id`.
+const MAX_CLIENT_ORDER_ID_LEN: usize = 20;
+
+/// Максимальная длина метки заявки.
+const MAX_COMMENT_LEN: usize = 128;
+
+/// Защитный спред для цены исполнения TP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TpGuardSpread {
+    /// Спред в единицах цены.
+    Value(Decimal),
+    /// Спред в процентах, с точностью до сотых процента.
+    Percent(Decimal),
+}
+
+impl TpGuardSpread {
+    /// Читает защитный спред из SL/TP заявки.
+    ///
+    /// # Аргументы
+    ///
+    /// * `order` - SL/TP заявка.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Option<Self>, FinamSdkError>` - Спред, `None` если не задан, или ошибку разбора.
+    pub fn from_order(order: &SltpOrder) -> Result<Option<Self>, FinamSdkError> {
+        let Some(spread) = order.tp_guard_spread.as_ref() else {
+            return Ok(None);
+        };
+        let value = to_decimal(spread)?;
+
+        match order.tp_spread_measure() {
+            TpSpreadMeasure::Value => Ok(Some(Self::Value(value))),
+            TpSpreadMeasure::Percent => Ok(Some(Self::Percent(value))),
+            TpSpreadMeasure::Undefined => Err(FinamSdkError::InvalidArgument(
+                "tp_guard_spread requires tp_spread_measure".to_string(),
+            )),
+        }
+    }
+
+    /// Возвращает значение спреда и единицу измерения для заявки.
+    pub fn into_parts(self) -> (Decimal, TpSpreadMeasure) {
+        match self {
+            Self::Value(value) => (value, TpSpreadMeasure::Value),
+            Self::Percent(value) => (value, TpSpreadMeasure::Percent),
+        }
+    }
+}
+
+/// Рыночные параметры для проверки и округления цен SL/TP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SltpMarket {
+    /// Текущая рыночная цена инструмента.
+    pub price: Decimal,
+    /// Минимальный шаг цены. Ноль отключает округление.
+    pub price_step: Decimal,
+}
+
+impl SltpMarket {
+    /// Создает параметры по данным инструмента и текущей цене.
+    ///
+    /// # Аргументы
+    ///
+    /// * `asset` - Информация об инструменте.
+    /// * `price` - Текущая рыночная цена.
+    pub fn new(asset: &GetAssetResponse, price: Decimal) -> Self {
+        Self {
+            price,
+            price_step: asset.price_step(),
+        }
+    }
+
+    fn round(&self, price: Decimal) -> Decimal {
+        round_to_step(
+            price,
+            self.price_step,
+            RoundingStrategy::MidpointAwayFromZero,
+        )
+    }
+}
+
+/// Построитель SL/TP заявки с проверкой согласованности полей.
+///
+/// Сторона заявки относится к закрывающим заявкам: `Side::Sell` закрывает
+/// длинную позицию (SL ниже рынка, TP выше), `Side::Buy` - короткую
+/// (SL выше рынка, TP ниже).
+///
+/// # Пример
+///
+/// ```no_run
+/// use finam::{FinamSdk, decimal::Decimal, proto::grpc::tradeapi::v1::Side, sltp::SltpOrderBuilder};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let sdk = FinamSdk::new("your_secret_key").await?;
+///     let order = SltpOrderBuilder::new("account_id", "SBER@MISX", Side::Sell)
+///         .stop_loss(Decimal::from(10), Decimal::from(290))
+///         .take_profit(Decimal::from(10), Decimal::from(330))
+///         .build_for_market(&sdk)
+///         .await?;
+///     sdk.place_sltp_order(order).await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SltpOrderBuilder {
+    account_id: String,
+    symbol: String,
+    side: Side,
+    stop_loss: Option<(Decimal, Decimal)>,
+    stop_loss_limit: Option<Decimal>,
+    take_profit: Option<(Decimal, Decimal)>,
+    guard_spread: Option<TpGuardSpread>,
+    valid_before: ValidBefore,
+    valid_expiry_time: Option<Timestamp>,
+    client_order_id: String,
+    comment: String,
+}
+
+impl SltpOrderBuilder {
+    /// Создает построитель SL/TP заявки.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `symbol` - Символ инструмента.
+    /// * `side` - Сторона закрывающих заявок.
+    pub fn new(account_id: &str, symbol: impl AsRef<str>, side: Side) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            symbol: symbol.as_ref().to_string(),
+            side,
+            stop_loss: None,
+            stop_loss_limit: None,
+            take_profit: None,
+            guard_spread: None,
+            valid_before: ValidBefore::GoodTillCancel,
+            valid_expiry_time: None,
+            client_order_id: String::new(),
+            comment: String::new(),
+        }
+    }
+
+    /// Задает часть SL.
+    ///
+    /// # Аргументы
+    ///
+    /// * `quantity` - Количество в шт.
+    /// * `price` - Цена срабатывания SL.
+    pub fn stop_loss(mut self, quantity: Decimal, price: Decimal) -> Self {
+        self.stop_loss = Some((quantity, price));
+        self
+    }
+
+    /// Задает цену лимитной заявки, выставляемой после срабатывания SL.
+    ///
+    /// # Аргументы
+    ///
+    /// * `price` - Цена лимитной заявки.
+    pub fn stop_loss_limit(mut self, price: Decimal) -> Self {
+        self.stop_loss_limit = Some(price);
+        self
+    }
+
+    /// Задает часть TP.
+    ///
+    /// # Аргументы
+    ///
+    /// * `quantity` - Количество в шт.
+    /// * `price` - Цена срабатывания TP.
+    pub fn take_profit(mut self, quantity: Decimal, price: Decimal) -> Self {
+        self.take_profit = Some((quantity, price));
+        self
+    }
+
+    /// Задает защитный спред для цены исполнения TP.
+    ///
+    /// # Аргументы
+    ///
+    /// * `spread` - Защитный спред в единицах цены или процентах.
+    pub fn guard_spread(mut self, spread: TpGuardSpread) -> Self {
+        self.guard_spread = Some(spread);
+        self
+    }
+
+    /// Задает срок действия до конца дня или до отмены.
+    ///
+    /// Для срока до указанного времени используйте [`SltpOrderBuilder::valid_until`].
+    ///
+    /// # Аргументы
+    ///
+    /// * `valid_before` - Срок действия заявки.
+    pub fn valid_before(mut self, valid_before: ValidBefore) -> Self {
+        self.valid_before = valid_before;
+        self
+    }
+
+    /// Задает срок действия до указанного времени (`GoodTillDate`).
+    ///
+    /// Время сверяется с часами сервера при выставлении заявки
+    /// через [`FinamSdk::place_sltp_order`].
+    ///
+    /// # Аргументы
+    ///
+    /// * `expiry` - Время прекращения действия заявки.
+    pub fn valid_until(mut self, expiry: Timestamp) -> Self {
+        self.valid_before = ValidBefore::GoodTillDate;
+        self.valid_expiry_time = Some(expiry);
+        self
+    }
+
+    /// Задает клиентский идентификатор заявки (не более 20 символов).
+    pub fn client_order_id(mut self, client_order_id: &str) -> Self {
+        self.client_order_id = client_order_id.to_string();
+        self
+    }
+
+    /// Задает метку заявки (не более 128 символов).
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = comment.to_string();
+        self
+    }
+
+    /// Проверяет согласованность полей и формирует заявку без проверки цен относительно рынка.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<SltpOrder, FinamSdkError>` - Заявка или ошибку `InvalidArgument`.
+    pub fn build(self) -> Result<SltpOrder, FinamSdkError> {
+        self.validate(None)
+    }
+
+    /// Проверяет заявку относительно текущей цены и округляет цены до шага цены.
+    ///
+    /// # Аргументы
+    ///
+    /// * `market` - Текущая цена и шаг цены инструмента.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<SltpOrder, FinamSdkError>` - Заявка или ошибку `InvalidArgument`.
+    pub fn build_with_market(self, market: &SltpMarket) -> Result<SltpOrder, FinamSdkError> {
+        self.validate(Some(market))
+    }
+
+    /// Загружает текущую цену и параметры инструмента, затем проверяет заявку.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<SltpOrder, FinamSdkError>` - Заявка или ошибку.
+    pub async fn build_for_market(self, sdk: &FinamSdk) -> Result<SltpOrder, FinamSdkError> {
+        let asset = sdk
+            .assets()
+            .get_asset(GetAssetRequest {
+                symbol: self.symbol.clone(),
+                account_id: self.account_id.clone(),
+            })
+            .await?
+            .into_inner();
+        let quote = sdk
+            .market_data()
+            .last_quote(QuoteRequest {
+                symbol: self.symbol.clone(),
+            })
+            .await?
+            .into_inner()
+            .quote
+            .unwrap_or_default();
+
+        let market = SltpMarket::new(&asset, opt_decimal(quote.last.as_ref())?);
+
+        self.build_with_market(&market)
+    }
+
+    fn validate(self, market: Option<&SltpMarket>) -> Result<SltpOrder, FinamSdkError> {
+        let invalid = |message: &str| Err(FinamSdkError::InvalidArgument(message.to_string()));
+        let round = |price: Decimal| market.map_or(price, |market| market.round(price));
+
+        if self.side == Side::Unspecified {
+            return invalid("side must be specified");
+        }
+
+        if self.stop_loss.is_none() && self.take_profit.is_none() {
+            return invalid("either stop loss or take profit must be set");
+        }
+
+        if self.stop_loss.is_none() && self.stop_loss_limit.is_some() {
+            return invalid("stop loss limit price requires stop loss");
+        }
+
+        if self.take_profit.is_none() && self.guard_spread.is_some() {
+            return invalid("guard spread requires take profit");
+        }
+
+        for (quantity, price) in self.stop_loss.iter().chain(self.take_profit.iter()) {
+            if *quantity <= Decimal::ZERO || *price <= Decimal::ZERO {
+                return invalid("quantities and prices must be positive");
+            }
+        }
+
+        let stop_loss = self
+            .stop_loss
+            .map(|(quantity, price)| (quantity, round(price)));
+        let take_profit = self
+            .take_profit
+            .map(|(quantity, price)| (quantity, round(price)));
+        let stop_loss_limit = self.stop_loss_limit.map(round);
+
+        // Closing a long position (sell) stops below and takes profit above the market
+        let sells = self.side == Side::Sell;
+
+        if let (Some((_, sl)), Some((_, tp))) = (stop_loss, take_profit)
+            && ((sells && sl >= tp) || (!sells && sl <= tp))
+        {
+            return invalid("stop loss and take profit prices are on the wrong sides");
+        }
+
+        if let Some(market) = market.filter(|market| !market.price.is_zero()) {
+            if let Some((_, sl)) = stop_loss
+                && ((sells && sl >= market.price) || (!sells && sl <= market.price))
+            {
+                return invalid("stop loss price is on the wrong side of the market");
+            }
+
+            if let Some((_, tp)) = take_profit
+                && ((sells && tp <= market.price) || (!sells && tp >= market.price))
+            {
+                return invalid("take profit price is on the wrong side of the market");
+            }
+        }
+
+        let guard_spread = match self.guard_spread {
+            Some(TpGuardSpread::Value(value)) if value <= Decimal::ZERO => {
+                return invalid("guard spread must be positive");
+            }
+            Some(TpGuardSpread::Value(value)) => Some(TpGuardSpread::Value(round(value))),
+            Some(TpGuardSpread::Percent(value))
+                if value <= Decimal::ZERO || value >= Decimal::ONE_HUNDRED =>
+            {
+                return invalid("guard spread percent must be between 0 and 100");
+            }
+            Some(TpGuardSpread::Percent(value)) if value.normalize().scale() > 2 => {
+                return invalid("guard spread percent precision is limited to hundredths");
+            }
+            other => other,
+        };
+
+        match (self.valid_before, self.valid_expiry_time) {
+            (ValidBefore::GoodTillDate, None) => {
+                return invalid("good till date requires expiry time");
+            }
+            (ValidBefore::GoodTillDate, Some(_)) => {}
+            (_, Some(_)) => return invalid("expiry time requires good till date"),
+            _ => {}
+        }
+
+        if self.client_order_id.chars().count() > MAX_CLIENT_ORDER_ID_LEN {
+            return invalid("client order id is limited to 20 characters");
+        }
+
+        if self.comment.chars().count() > MAX_COMMENT_LEN {
+            return invalid("comment is limited to 128 characters");
+        }
+
+        let (tp_guard_spread, tp_spread_measure) = guard_spread
+            .map(TpGuardSpread::into_parts)
+            .map_or((None, TpSpreadMeasure::Undefined), |(value, measure)| {
+                (Some(from_decimal(value)), measure)
+            });
+
+        Ok(SltpOrder {
+            account_id: self.account_id,
+            symbol: self.symbol,
+            side: self.side as i32,
+            quantity_sl: stop_loss.map(|(quantity, _)| from_decimal(quantity)),
+            sl_price: stop_loss.map(|(_, price)| from_decimal(price)),
+            limit_price: stop_loss_limit.map(from_decimal),
+            quantity_tp: take_profit.map(|(quantity, _)| from_decimal(quantity)),
+            tp_price: take_profit.map(|(_, price)| from_decimal(price)),
+            tp_guard_spread,
+            tp_spread_measure: tp_spread_measure as i32,
+            client_order_id: self.client_order_id,
+            valid_before: self.valid_before as i32,
+            valid_expiry_time: self.valid_expiry_time,
+            comment: self.comment,
+        })
+    }
+}
+
+/// Проверяет, что срок действия заявки `GoodTillDate` еще не истек.
+///
+/// # Аргументы
+///
+/// * `order` - SL/TP заявка.
+/// * `now` - Текущее время сервера.
+///
+/// # Возвращает
+///
+/// * `Result<(), FinamSdkError>` - Пустой результат или ошибку `InvalidArgument`.
+pub(crate) fn check_expiry(order: &SltpOrder, now: SystemTime) -> Result<(), FinamSdkError> {
+    if order.valid_before() == ValidBefore::GoodTillDate
+        && let Some(expiry) = order.valid_expiry_time
+        && SystemTime::try_from(expiry).is_ok_and(|expiry| expiry <= now)
+    {
+        return Err(FinamSdkError::InvalidArgument(
+            "expiry time must be in the future".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Состояние SL/TP заявки в понятном виде.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SltpState {
+    /// Заявка принята и ожидает условий срабатывания.
+    Watching,
+    /// Действует защитное время SL.
+    StopLossGuardTime,
+    /// Сработал SL, заявка пересылается на биржу.
+    StopLossForwarding,
+    /// Исполнена по SL.
+    StopLossExecuted,
+    /// Действует защитное время TP.
+    TakeProfitGuardTime,
+    /// Цена TP корректируется вслед за рынком.
+    TakeProfitCorrection,
+    /// Коррекция TP в защитное время.
+    TakeProfitCorrectionGuardTime,
+    /// Сработал TP, заявка пересылается на биржу.
+    TakeProfitForwarding,
+    /// Исполнена по TP.
+    TakeProfitExecuted,
+    /// Отменена или истек срок действия.
+    Canceled,
+    /// Отклонена брокером или биржей.
+    Rejected,
+    /// Прочий статус заявки.
+    Other(OrderStatus),
+}
+
+impl SltpState {
+    /// Проверяет, завершена ли SL/TP заявка.
+    pub fn is_final(&self) -> bool {
+        match self {
+            Self::StopLossExecuted | Self::TakeProfitExecuted | Self::Canceled | Self::Rejected => {
+                true
+            }
+            Self::Other(status) => status.is_final(),
+            _ => false,
+        }
+    }
+
+    /// Возвращает описание состояния на русском языке.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Watching => "Ожидает срабатывания",
+            Self::StopLossGuardTime => "Защитное время SL",
+            Self::StopLossForwarding => "Сработал SL, пересылка на биржу",
+            Self::StopLossExecuted => "Исполнена по SL",
+            Self::TakeProfitGuardTime => "Защитное время TP",
+            Self::TakeProfitCorrection => "Коррекция TP",
+            Self::TakeProfitCorrectionGuardTime => "Коррекция TP в защитное время",
+            Self::TakeProfitForwarding => "Сработал TP, пересылка на биржу",
+            Self::TakeProfitExecuted => "Исполнена по TP",
+            Self::Canceled => "Отменена",
+            Self::Rejected => "Отклонена",
+            Self::Other(_) => "Прочий статус",
+        }
+    }
+}
+
+impl From<OrderStatus> for SltpState {
+    fn from(status: OrderStatus) -> Self {
+        match status {
+            OrderStatus::New
+            | OrderStatus::PendingNew
+            | OrderStatus::Watching
+            | OrderStatus::Wait
+            | OrderStatus::LinkWait => Self::Watching,
+            OrderStatus::SlGuardTime => Self::StopLossGuardTime,
+            OrderStatus::SlForwarding => Self::StopLossForwarding,
+            OrderStatus::SlExecuted => Self::StopLossExecuted,
+            OrderStatus::TpGuardTime => Self::TakeProfitGuardTime,
+            OrderStatus::TpCorrection => Self::TakeProfitCorrection,
+            OrderStatus::TpCorrGuardTime => Self::TakeProfitCorrectionGuardTime,
+            OrderStatus::TpForwarding => Self::TakeProfitForwarding,
+            OrderStatus::TpExecuted => Self::TakeProfitExecuted,
+            OrderStatus::Canceled | OrderStatus::Expired | OrderStatus::Disabled => Self::Canceled,
+            OrderStatus::Rejected
+            | OrderStatus::RejectedByExchange
+            | OrderStatus::DeniedByBroker
+            | OrderStatus::Failed => Self::Rejected,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl OrderState {
+    /// Возвращает состояние SL/TP заявки в понятном виде.
+    ///
+    /// # Возвращает
+    ///
+    /// * `SltpState` - Состояние, соответствующее статусу заявки.
+    pub fn sltp_state(&self) -> SltpState {
+        self.status().into()
+    }
+}
+
+impl fmt::Display for SltpState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Other(status) => write!(f, "{} ({})", self.description(), status.as_str_name()),
+            _ => f.write_str(self.description()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market() -> SltpMarket {
+        SltpMarket {
+            price: Decimal::from(300),
+            price_step: Decimal::new(1, 1),
+        }
+    }
+
+    #[test]
+    fn test_build_rounds_prices_to_step() {
+        let order = SltpOrderBuilder::new("A1", "SBER@MISX", Side::Sell)
+            .stop_loss(Decimal::from(10), Decimal::new(29012, 2))
+            .take_profit(Decimal::from(10), Decimal::new(33049, 2))
+            .guard_spread(TpGuardSpread::Percent(Decimal::new(5, 1)))
+            .build_with_market(&market())
+            .unwrap();
+
+        assert_eq!(order.sl_price, Some(from_decimal(Decimal::new(2901, 1))));
+        assert_eq!(order.tp_price, Some(from_decimal(Decimal::new(3305, 1))));
+        assert_eq!(order.tp_spread_measure(), TpSpreadMeasure::Percent);
+        assert_eq!(
+            TpGuardSpread::from_order(&order).unwrap(),
+            Some(TpGuardSpread::Percent(Decimal::new(5, 1)))
+        );
+    }
+
+    #[test]
+    fn test_build_rejects_prices_on_wrong_side() {
+        let long_exit = SltpOrderBuilder::new("A1", "SBER@MISX", Side::Sell)
+            .stop_loss(Decimal::from(10), Decimal::from(310))
+            .build_with_market(&market());
+        assert!(long_exit.is_err());
+
+        let short_exit = SltpOrderBuilder::new("A1", "SBER@MISX", Side::Buy)
+            .stop_loss(Decimal::from(10), Decimal::from(310))
+            .take_profit(Decimal::from(10), Decimal::from(280))
+            .build_with_market(&market());
+        assert!(short_exit.is_ok());
+    }
+
+    #[test]
+    fn test_build_checks_field_combinations() {
+        let builder = SltpOrderBuilder::new("A1", "SBER@MISX", Side::Sell);
+
+        assert!(builder.clone().build().is_err());
+        assert!(
+            builder
+                .clone()
+                .stop_loss_limit(Decimal::from(280))
+                .take_profit(Decimal::from(1), Decimal::from(320))
+                .build()
+                .is_err()
+        );
+        assert!(
+            builder
+                .clone()
+                .take_profit(Decimal::from(1), Decimal::from(320))
+                .guard_spread(TpGuardSpread::Percent(Decimal::new(125, 3)))
+                .build()
+                .is_err()
+        );
+        assert!(
+            builder
+                .take_profit(Decimal::from(1), Decimal::from(320))
+                .valid_before(ValidBefore::GoodTillDate)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_build_good_till_date() {
+        let expiry = Timestamp {
+            seconds: 1_900_000_000,
+            nanos: 0,
+        };
+        let order = SltpOrderBuilder::new("A1", "SBER@MISX", Side::Sell)
+            .take_profit(Decimal::from(1), Decimal::from(320))
+            .valid_until(expiry)
+            .build()
+            .unwrap();
+
+        assert_eq!(order.valid_before(), ValidBefore::GoodTillDate);
+        assert_eq!(order.valid_expiry_time, Some(expiry));
+    }
+
+    #[test]
+    fn test_check_expiry_against_server_time() {
+        let now = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_800_000_000);
+        let order = |seconds: i64| {
+            SltpOrderBuilder::new("A1", "SBER@MISX", Side::Sell)
+                .take_profit(Decimal::from(1), Decimal::from(320))
+                .valid_until(Timestamp { seconds, nanos: 0 })
+                .build()
+                .unwrap()
+        };
+
+        assert!(check_expiry(&order(1_800_000_060), now).is_ok());
+        assert!(matches!(
+            check_expiry(&order(1_799_999_940), now),
+            Err(FinamSdkError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_sltp_state_mapping() {
+        assert_eq!(
+            SltpState::from(OrderStatus::SlGuardTime),
+            SltpState::StopLossGuardTime
+        );
+        assert_eq!(
+            SltpState::from(OrderStatus::TpCorrection),
+            SltpState::TakeProfitCorrection
+        );
+        assert!(SltpState::from(OrderStatus::TpExecuted).is_final());
+        assert!(!SltpState::from(OrderStatus::Watching).is_final());
+    }
+}
@@ -0,0 +1,430 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use futures::TryStreamExt;
+use serde::Serialize;
+
+use crate::{
+    FinamSdk, FinamSdkError,
+    decimal::{Decimal, money_amount, opt_decimal, to_decimal},
+    pnl::day_of,
+    proto::{
+        google::r#type::{Date, Interval},
+        grpc::tradeapi::v1::accounts::{Transaction, transaction::TransactionCategory},
+    },
+};
+
+impl Transaction {
+    /// Возвращает категорию транзакции с учетом устаревшего строкового поля `category`.
+    ///
+    /// Если числовое поле `transaction_category` не заполнено, категория
+    /// разбирается из `category` по имени (`"COMMISSION"`) или номеру (`"7"`).
+    ///
+    /// # Возвращает
+    ///
+    /// * `TransactionCategory` - Категория, `Others` если ее не удалось определить.
+    pub fn resolved_category(&self) -> TransactionCategory {
+        if let Ok(category) = TransactionCategory::try_from(self.transaction_category)
+            && category != TransactionCategory::Others
+        {
+            return category;
+        }
+
+        #[allow(deprecated)]
+        let legacy = self.category.trim();
+
+        TransactionCategory::from_str_name(&legacy.to_ascii_uppercase())
+            .or_else(|| {
+                legacy
+                    .parse::<i32>()
+                    .ok()
+                    .and_then(|value| TransactionCategory::try_from(value).ok())
+            })
+            .unwrap_or(TransactionCategory::Others)
+    }
+}
+
+/// Детали сделки, к которой относится транзакция.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TradeDetails {
+    /// Количество в шт.
+    pub size: Decimal,
+    /// Цена сделки за штуку.
+    pub price: Decimal,
+    /// НКД, если он есть в сделке.
+    pub accrued_interest: Option<Decimal>,
+}
+
+/// Строка брокерской выписки.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StatementEntry {
+    /// Дата транзакции в формате `YYYY-MM-DD`.
+    pub date: String,
+    /// Идентификатор транзакции.
+    pub id: String,
+    /// Категория транзакции.
+    pub category: &'static str,
+    /// Наименование транзакции.
+    pub name: String,
+    /// Символ инструмента.
+    pub symbol: String,
+    /// Валюта изменения.
+    pub currency: String,
+    /// Изменение в деньгах.
+    pub amount: Decimal,
+    /// Изменение в штуках для переводов бумаг.
+    pub quantity: Option<Decimal>,
+    /// Детали сделки для торговых транзакций.
+    pub trade: Option<TradeDetails>,
+}
+
+/// Итог выписки за период по категории и валюте.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StatementSummary {
+    /// Период: дата `YYYY-MM-DD` или месяц `YYYY-MM`.
+    pub period: String,
+    /// Категория транзакций.
+    pub category: &'static str,
+    /// Валюта.
+    pub currency: String,
+    /// Сумма изменений.
+    pub amount: Decimal,
+    /// Сумма изменений в штуках.
+    pub quantity: Decimal,
+    /// Сумма НКД по сделкам.
+    pub accrued_interest: Decimal,
+    /// Количество транзакций.
+    pub count: u32,
+}
+
+/// Брокерская выписка по транзакциям аккаунта.
+///
+/// Строки упорядочены по времени и группируются в итоги по категориям
+/// [`TransactionCategory`] и валютам за день или месяц. Выписку и итоги можно
+/// выгрузить в CSV и JSON.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Statement {
+    /// Строки выписки.
+    pub entries: Vec<StatementEntry>,
+}
+
+impl Statement {
+    /// Строит выписку из транзакций.
+    ///
+    /// # Аргументы
+    ///
+    /// * `transactions` - Транзакции аккаунта, например из [`FinamSdk::transactions_history`].
+    /// * `utc_offset` - Смещение от UTC в секундах, по которому определяется дата.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Выписку или ошибку разбора чисел.
+    pub fn from_transactions(
+        transactions: &[Transaction],
+        utc_offset: i64,
+    ) -> Result<Self, FinamSdkError> {
+        let mut sorted: Vec<_> = transactions.iter().collect();
+        sorted.sort_by_key(|transaction| {
+            transaction
+                .timestamp
+                .map(|timestamp| (timestamp.seconds, timestamp.nanos))
+        });
+
+        let entries = sorted
+            .into_iter()
+            .map(|transaction| {
+                let trade = match &transaction.trade {
+                    Some(trade) => Some(TradeDetails {
+                        size: opt_decimal(trade.size.as_ref())?,
+                        price: opt_decimal(trade.price.as_ref())?,
+                        accrued_interest: trade
+                            .accrued_interest
+                            .as_ref()
+                            .map(to_decimal)
+                            .transpose()?,
+                    }),
+                    None => None,
+                };
+
+                Ok(StatementEntry {
+                    date: format_day(&day_of(transaction.timestamp.as_ref(), utc_offset)),
+                    id: transaction.id.clone(),
+                    category: transaction.resolved_category().as_str_name(),
+                    name: transaction.transaction_name.clone(),
+                    symbol: transaction.symbol.clone(),
+                    currency: transaction
+                        .change
+                        .as_ref()
+                        .map(|change| change.currency_code.clone())
+                        .unwrap_or_default(),
+                    amount: transaction
+                        .change
+                        .as_ref()
+                        .map_or(Decimal::ZERO, money_amount),
+                    quantity: transaction
+                        .change_qty
+                        .as_ref()
+                        .map(to_decimal)
+                        .transpose()?,
+                    trade,
+                })
+            })
+            .collect::<Result<_, FinamSdkError>>()?;
+
+        Ok(Self { entries })
+    }
+
+    /// Возвращает итоги по дням, категориям и валютам.
+    pub fn daily(&self) -> Vec<StatementSummary> {
+        self.summarize(|entry| entry.date.clone())
+    }
+
+    /// Возвращает итоги по месяцам, категориям и валютам.
+    pub fn monthly(&self) -> Vec<StatementSummary> {
+        self.summarize(|entry| entry.date[..7].to_string())
+    }
+
+    /// Возвращает строки выбранной категории.
+    ///
+    /// # Аргументы
+    ///
+    /// * `category` - Категория транзакций.
+    pub fn by_category(&self, category: TransactionCategory) -> Vec<&StatementEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.category == category.as_str_name())
+            .collect()
+    }
+
+    /// Выгружает строки выписки в CSV с заголовком.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "date,id,category,name,symbol,currency,amount,quantity,trade_size,trade_price,accrued_interest\n",
+        );
+
+        for entry in &self.entries {
+            let trade = entry.trade.as_ref();
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                entry.date,
+                csv_field(&entry.id),
+                entry.category,
+                csv_field(&entry.name),
+                csv_field(&entry.symbol),
+                csv_field(&entry.currency),
+                entry.amount,
+                optional(entry.quantity),
+                optional(trade.map(|trade| trade.size)),
+                optional(trade.map(|trade| trade.price)),
+                optional(trade.and_then(|trade| trade.accrued_interest)),
+            );
+        }
+
+        csv
+    }
+
+    /// Выгружает строки выписки в JSON.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<String, FinamSdkError>` - JSON или ошибку сериализации.
+    pub fn to_json(&self) -> Result<String, FinamSdkError> {
+        Ok(serde_json::to_string_pretty(&self.entries)?)
+    }
+
+    fn summarize(&self, period: impl Fn(&StatementEntry) -> String) -> Vec<StatementSummary> {
+        let mut groups: BTreeMap<(String, &'static str, String), StatementSummary> =
+            BTreeMap::new();
+
+        for entry in &self.entries {
+            let summary = groups
+                .entry((period(entry), entry.category, entry.currency.clone()))
+                .or_insert_with_key(|(period, category, currency)| StatementSummary {
+                    period: period.clone(),
+                    category,
+                    currency: currency.clone(),
+                    amount: Decimal::ZERO,
+                    quantity: Decimal::ZERO,
+                    accrued_interest: Decimal::ZERO,
+                    count: 0,
+                });
+
+            summary.amount += entry.amount;
+            summary.quantity += entry.quantity.unwrap_or_default();
+            summary.accrued_interest += entry
+                .trade
+                .and_then(|trade| trade.accrued_interest)
+                .unwrap_or_default();
+            summary.count += 1;
+        }
+
+        groups.into_values().collect()
+    }
+}
+
+impl FinamSdk {
+    /// Загружает транзакции аккаунта за период и строит по ним выписку.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `interval` - Период выписки с заданными началом и окончанием.
+    /// * `utc_offset` - Смещение от UTC в секундах, по которому определяется дата.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Statement, FinamSdkError>` - Выписку или ошибку загрузки данных.
+    pub async fn statement(
+        &self,
+        account_id: &str,
+        interval: Interval,
+        utc_offset: i64,
+    ) -> Result<Statement, FinamSdkError> {
+        let transactions: Vec<_> = self
+            .transactions_history(account_id, interval)?
+            .try_collect()
+            .await?;
+
+        Statement::from_transactions(&transactions, utc_offset)
+    }
+}
+
+/// Выгружает итоги выписки в CSV с заголовком.
+///
+/// # Аргументы
+///
+/// * `summaries` - Итоги, например из [`Statement::monthly`].
+pub fn summaries_to_csv(summaries: &[StatementSummary]) -> String {
+    let mut csv = String::from("period,category,currency,amount,quantity,accrued_interest,count\n");
+
+    for summary in summaries {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            summary.period,
+            summary.category,
+            csv_field(&summary.currency),
+            summary.amount,
+            summary.quantity,
+            summary.accrued_interest,
+            summary.count,
+        );
+    }
+
+    csv
+}
+
+/// Выгружает итоги выписки в JSON.
+///
+/// # Аргументы
+///
+/// * `summaries` - Итоги, например из [`Statement::daily`].
+///
+/// # Возвращает
+///
+/// * `Result<String, FinamSdkError>` - JSON или ошибку сериализации.
+pub fn summaries_to_json(summaries: &[StatementSummary]) -> Result<String, FinamSdkError> {
+    Ok(serde_json::to_string_pretty(summaries)?)
+}
+
+fn format_day(day: &Date) -> String {
+    format!("{:04}-{:02}-{:02}", day.year, day.month, day.day)
+}
+
+fn optional(value: Option<Decimal>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Экранирует поле CSV, содержащее разделитель, кавычки или перевод строки.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::Timestamp;
+
+    use super::*;
+    use crate::{
+        decimal::from_decimal,
+        proto::{google::r#type::Money, grpc::tradeapi::v1::accounts::transaction::Trade},
+    };
+
+    fn transaction(id: &str, category: TransactionCategory, day: i64, units: i64) -> Transaction {
+        Transaction {
+            id: id.to_string(),
+            timestamp: Some(Timestamp {
+                seconds: day * 86_400,
+                nanos: 0,
+            }),
+            change: Some(Money {
+                currency_code: "RUB".to_string(),
+                units,
+                nanos: 0,
+            }),
+            transaction_category: category as i32,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_resolved_category_falls_back_to_legacy_field() {
+        let mut legacy = transaction("1", TransactionCategory::Others, 0, -5);
+        legacy.category = "commission".to_string();
+        assert_eq!(legacy.resolved_category(), TransactionCategory::Commission);
+
+        legacy.category = "8".to_string();
+        assert_eq!(legacy.resolved_category(), TransactionCategory::Tax);
+
+        legacy.category = "unknown".to_string();
+        assert_eq!(legacy.resolved_category(), TransactionCategory::Others);
+    }
+
+    #[test]
+    fn test_statement_summaries_and_export() {
+        let mut coupon = transaction("3", TransactionCategory::Income, 1, 120);
+        coupon.symbol = "SU26238RMFS4@MISX".to_string();
+        coupon.transaction_name = "Купон, НКД".to_string();
+        coupon.trade = Some(Trade {
+            size: Some(from_decimal(Decimal::from(2))),
+            price: Some(from_decimal(Decimal::from(950))),
+            accrued_interest: Some(from_decimal(Decimal::new(155, 1))),
+        });
+
+        let statement = Statement::from_transactions(
+            &[
+                coupon,
+                transaction("1", TransactionCategory::Deposit, 0, 1000),
+                transaction("2", TransactionCategory::Commission, 0, -3),
+                transaction("4", TransactionCategory::Commission, 40, -2),
+            ],
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(statement.entries[0].id, "1");
+        assert_eq!(statement.daily().len(), 4);
+
+        let monthly = statement.monthly();
+        assert_eq!(monthly.len(), 4);
+        assert_eq!(monthly[0].period, "1970-01");
+        assert_eq!(monthly[0].category, "COMMISSION");
+        assert_eq!(monthly[0].amount, Decimal::from(-3));
+        assert_eq!(monthly[2].accrued_interest, Decimal::new(155, 1));
+        assert_eq!(monthly[3].period, "1970-02");
+
+        let csv = statement.to_csv();
+        assert!(csv.contains(
+            "1970-01-02,3,INCOME,\"Купон, НКД\",SU26238RMFS4@MISX,RUB,120,,2,950,15.5\n"
+        ));
+        assert!(summaries_to_csv(&monthly).starts_with("period,category"));
+
+        let json: serde_json::Value = serde_json::from_str(&statement.to_json().unwrap()).unwrap();
+        assert_eq!(json[2]["trade"]["accrued_interest"], "15.5");
+    }
+}
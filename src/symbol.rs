@@ -0,0 +1,423 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    FinamSdkError,
+    decimal::{Decimal, from_decimal},
+    proto::{
+        google::r#type::Interval,
+        grpc::tradeapi::v1::{
+            Side,
+            assets::{
+                GetAssetParamsRequest, GetAssetRequest, OptionsChainRequest, ScheduleRequest,
+            },
+            marketdata::{
+                BarsRequest, LatestTradesRequest, OrderBookRequest, QuoteRequest,
+                SubscribeLatestTradesRequest, SubscribeOrderBookRequest, SubscribeQuoteRequest,
+                TimeFrame,
+            },
+            orders::{Order, OrderType, SltpOrder, TimeInForce, ValidBefore},
+        },
+    },
+};
+
+/// Символ инструмента в форме `ticker@mic`, например `SBER@MISX`.
+///
+/// Тикер сохраняется как есть, поскольку тикеры срочного рынка содержат
+/// строчные буквы (`SiM5@RTSX`). MIC приводится к верхнему регистру.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Symbol {
+    value: String,
+    separator: usize,
+}
+
+impl Symbol {
+    /// Создает символ из тикера и MIC.
+    ///
+    /// # Аргументы
+    ///
+    /// * `ticker` - Тикер инструмента.
+    /// * `mic` - MIC идентификатор биржи.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Символ или ошибку, если тикер или MIC некорректны.
+    pub fn new(ticker: &str, mic: &str) -> Result<Self, FinamSdkError> {
+        let ticker = ticker.trim();
+        let mic = mic.trim();
+
+        if ticker.is_empty() || ticker.contains(|c: char| c == '@' || c.is_whitespace()) {
+            return Err(FinamSdkError::InvalidSymbol(format!("{ticker}@{mic}")));
+        }
+
+        if mic.is_empty() || !mic.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(FinamSdkError::InvalidSymbol(format!("{ticker}@{mic}")));
+        }
+
+        Ok(Self {
+            value: format!("{ticker}@{}", mic.to_ascii_uppercase()),
+            separator: ticker.len(),
+        })
+    }
+
+    /// Возвращает тикер инструмента.
+    pub fn ticker(&self) -> &str {
+        &self.value[..self.separator]
+    }
+
+    /// Возвращает MIC идентификатор биржи.
+    pub fn mic(&self) -> &str {
+        &self.value[self.separator + 1..]
+    }
+
+    /// Возвращает символ в форме `ticker@mic`.
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl FromStr for Symbol {
+    type Err = FinamSdkError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (ticker, mic) = value
+            .split_once('@')
+            .ok_or_else(|| FinamSdkError::InvalidSymbol(value.to_string()))?;
+
+        Self::new(ticker, mic)
+    }
+}
+
+impl TryFrom<&str> for Symbol {
+    type Error = FinamSdkError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for Symbol {
+    type Error = FinamSdkError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(value: Symbol) -> Self {
+        value.value
+    }
+}
+
+impl From<&Symbol> for Symbol {
+    fn from(value: &Symbol) -> Self {
+        value.clone()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Symbol").field(&self.value).finish()
+    }
+}
+
+impl QuoteRequest {
+    /// Создает запрос последней котировки.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    pub fn new(symbol: impl Into<Symbol>) -> Self {
+        Self {
+            symbol: symbol.into().into(),
+        }
+    }
+}
+
+impl OrderBookRequest {
+    /// Создает запрос текущего стакана.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    pub fn new(symbol: impl Into<Symbol>) -> Self {
+        Self {
+            symbol: symbol.into().into(),
+        }
+    }
+}
+
+impl LatestTradesRequest {
+    /// Создает запрос последних сделок по инструменту.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    pub fn new(symbol: impl Into<Symbol>) -> Self {
+        Self {
+            symbol: symbol.into().into(),
+        }
+    }
+}
+
+impl BarsRequest {
+    /// Создает запрос исторических свечей.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    /// * `timeframe` - Таймфрейм свечей.
+    /// * `interval` - Период запроса.
+    pub fn new(symbol: impl Into<Symbol>, timeframe: TimeFrame, interval: Interval) -> Self {
+        Self {
+            symbol: symbol.into().into(),
+            timeframe: timeframe as i32,
+            interval: Some(interval),
+        }
+    }
+}
+
+impl SubscribeQuoteRequest {
+    /// Создает подписку на котировки нескольких инструментов.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbols` - Символы инструментов.
+    pub fn new(symbols: impl IntoIterator<Item = impl Into<Symbol>>) -> Self {
+        Self {
+            symbols: symbols
+                .into_iter()
+                .map(|symbol| symbol.into().into())
+                .collect(),
+        }
+    }
+}
+
+impl SubscribeOrderBookRequest {
+    /// Создает подписку на стакан.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    pub fn new(symbol: impl Into<Symbol>) -> Self {
+        Self {
+            symbol: symbol.into().into(),
+        }
+    }
+}
+
+impl SubscribeLatestTradesRequest {
+    /// Создает подписку на сделки по инструменту.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    pub fn new(symbol: impl Into<Symbol>) -> Self {
+        Self {
+            symbol: symbol.into().into(),
+        }
+    }
+}
+
+impl GetAssetRequest {
+    /// Создает запрос информации об инструменте.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    /// * `account_id` - Идентификатор аккаунта.
+    pub fn new(symbol: impl Into<Symbol>, account_id: &str) -> Self {
+        Self {
+            symbol: symbol.into().into(),
+            account_id: account_id.to_string(),
+        }
+    }
+}
+
+impl GetAssetParamsRequest {
+    /// Создает запрос торговых параметров инструмента.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    /// * `account_id` - Идентификатор аккаунта.
+    pub fn new(symbol: impl Into<Symbol>, account_id: &str) -> Self {
+        Self {
+            symbol: symbol.into().into(),
+            account_id: account_id.to_string(),
+        }
+    }
+}
+
+impl ScheduleRequest {
+    /// Создает запрос расписания торгов инструмента.
+    ///
+    /// # Аргументы
+    ///
+    /// * `symbol` - Символ инструмента.
+    pub fn new(symbol: impl Into<Symbol>) -> Self {
+        Self {
+            symbol: symbol.into().into(),
+        }
+    }
+}
+
+impl OptionsChainRequest {
+    /// Создает запрос цепочки опционов с ближайшей датой экспирации.
+    ///
+    /// # Аргументы
+    ///
+    /// * `underlying_symbol` - Символ базового актива.
+    pub fn new(underlying_symbol: impl Into<Symbol>) -> Self {
+        Self {
+            underlying_symbol: underlying_symbol.into().into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Order {
+    /// Создает рыночную заявку, действующую до конца дня.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `symbol` - Символ инструмента.
+    /// * `side` - Сторона заявки.
+    /// * `quantity` - Количество в шт.
+    pub fn market(
+        account_id: &str,
+        symbol: impl Into<Symbol>,
+        side: Side,
+        quantity: Decimal,
+    ) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            symbol: symbol.into().into(),
+            quantity: Some(from_decimal(quantity)),
+            side: side as i32,
+            r#type: OrderType::Market as i32,
+            time_in_force: TimeInForce::Day as i32,
+            ..Default::default()
+        }
+    }
+
+    /// Создает лимитную заявку, действующую до конца дня.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `symbol` - Символ инструмента.
+    /// * `side` - Сторона заявки.
+    /// * `quantity` - Количество в шт.
+    /// * `price` - Лимитная цена.
+    pub fn limit(
+        account_id: &str,
+        symbol: impl Into<Symbol>,
+        side: Side,
+        quantity: Decimal,
+        price: Decimal,
+    ) -> Self {
+        Self {
+            r#type: OrderType::Limit as i32,
+            limit_price: Some(from_decimal(price)),
+            ..Self::market(account_id, symbol, side, quantity)
+        }
+    }
+}
+
+impl SltpOrder {
+    /// Создает SL/TP заявку без заданных частей, действующую до отмены.
+    ///
+    /// Части SL и TP задаются полями заявки. Для проверки цен относительно
+    /// рынка используйте [`crate::sltp::SltpOrderBuilder`].
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `symbol` - Символ инструмента.
+    /// * `side` - Сторона закрывающих заявок.
+    pub fn new(account_id: &str, symbol: impl Into<Symbol>, side: Side) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            symbol: symbol.into().into(),
+            side: side as i32,
+            valid_before: ValidBefore::GoodTillCancel as i32,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    #[test]
+    fn test_symbol_parsing_and_accessors() {
+        let symbol: Symbol = " SiM5@rtsx".parse().unwrap();
+        assert_eq!(symbol.ticker(), "SiM5");
+        assert_eq!(symbol.mic(), "RTSX");
+        assert_eq!(symbol.to_string(), "SiM5@RTSX");
+
+        for invalid in ["SBER", "@MISX", "SBER@", "SB ER@MISX", "SBER@MI-X", "A@B@C"] {
+            assert!(
+                matches!(
+                    invalid.parse::<Symbol>(),
+                    Err(FinamSdkError::InvalidSymbol(_))
+                ),
+                "{invalid}"
+            );
+        }
+
+        let sorted: BTreeSet<Symbol> = ["SBER@MISX", "GAZP@MISX"]
+            .into_iter()
+            .map(|value| value.parse().unwrap())
+            .collect();
+        assert_eq!(sorted.first().unwrap().ticker(), "GAZP");
+    }
+
+    #[test]
+    fn test_symbol_serde_and_requests() {
+        let symbol = Symbol::new("SBER", "MISX").unwrap();
+        let json = serde_json::to_string(&symbol).unwrap();
+
+        assert_eq!(json, "\"SBER@MISX\"");
+        assert_eq!(serde_json::from_str::<Symbol>(&json).unwrap(), symbol);
+        assert!(serde_json::from_str::<Symbol>("\"SBER\"").is_err());
+
+        assert_eq!(QuoteRequest::new(&symbol).symbol, "SBER@MISX");
+        assert_eq!(
+            SubscribeQuoteRequest::new([symbol.clone(), "GAZP@MISX".parse().unwrap()]).symbols,
+            vec!["SBER@MISX", "GAZP@MISX"]
+        );
+        assert_eq!(
+            OptionsChainRequest::new(&symbol).underlying_symbol,
+            "SBER@MISX"
+        );
+
+        let order = Order::limit("A1", &symbol, Side::Buy, Decimal::ONE, Decimal::TEN);
+        assert_eq!(order.symbol, "SBER@MISX");
+        assert_eq!(order.r#type(), OrderType::Limit);
+        assert_eq!(order.limit_price, Some(from_decimal(Decimal::TEN)));
+        assert_eq!(SltpOrder::new("A1", &symbol, Side::Sell).side(), Side::Sell);
+
+        assert_eq!(GetAssetRequest::new(symbol, "A1").account_id, "A1");
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use futures::StreamExt;
+use tokio::{
+    sync::{broadcast, oneshot},
+    time::Duration,
+};
+
+use crate::{
+    FinamSdk, FinamSdkError, ShutdownGuard, proto::grpc::tradeapi::v1::orders::OrderState,
+    trading::OrderStream, util::RECONNECT_DELAY,
+};
+
+/// Емкость канала рассылки обновлений заявок.
+const UPDATES_CAPACITY: usize = 1024;
+
+/// Отслеживает состояния заявок аккаунта в реальном времени.
+///
+/// При создании подписывается на поток `subscribe_orders` и затем загружает
+/// текущие заявки через `get_orders`. При обрыве потока переподключается и повторно
+/// загружает заявки, чтобы не пропустить изменения. Фоновая задача завершается
+/// при уничтожении последнего клона трекера.
+#[derive(Clone, Debug)]
+pub struct OrderTracker {
+    account_id: String,
+    orders: Arc<RwLock<HashMap<String, OrderState>>>,
+    updates: broadcast::Sender<OrderState>,
+    /// Удерживает фоновую задачу подписки. При уничтожении последней ссылки
+    /// на трекер отправляет сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Arc<ShutdownGuard>,
+}
+
+impl OrderTracker {
+    /// Запускает отслеживание заявок аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    /// * `account_id` - Идентификатор аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Трекер заявок или ошибку начальной загрузки.
+    pub async fn start(sdk: FinamSdk, account_id: &str) -> Result<Self, FinamSdkError> {
+        let (updates, _) = broadcast::channel(UPDATES_CAPACITY);
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+
+        let tracker = Self {
+            account_id: account_id.to_string(),
+            orders: Arc::new(RwLock::new(HashMap::new())),
+            updates,
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        };
+
+        let stream = sdk.subscribe_orders(account_id).await?;
+        tracker.reload(&sdk).await?;
+
+        let state = TrackerState {
+            account_id: tracker.account_id.clone(),
+            orders: tracker.orders.clone(),
+            updates: tracker.updates.clone(),
+        };
+        tokio::spawn(state.run(sdk, stream, shutdown_receiver));
+
+        Ok(tracker)
+    }
+
+    /// Возвращает идентификатор отслеживаемого аккаунта.
+    pub fn account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    /// Возвращает последнее известное состояние заявки.
+    ///
+    /// # Аргументы
+    ///
+    /// * `order_id` - Идентификатор заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Option<OrderState>` - Состояние заявки или `None`, если заявка неизвестна.
+    pub fn get(&self, order_id: &str) -> Option<OrderState> {
+        read_orders(&self.orders).get(order_id).cloned()
+    }
+
+    /// Возвращает последние известные состояния всех заявок аккаунта.
+    pub fn orders(&self) -> Vec<OrderState> {
+        read_orders(&self.orders).values().cloned().collect()
+    }
+
+    /// Возвращает активные заявки аккаунта.
+    pub fn active_orders(&self) -> Vec<OrderState> {
+        read_orders(&self.orders)
+            .values()
+            .filter(|state| state.status().is_active())
+            .cloned()
+            .collect()
+    }
+
+    /// Подписывается на обновления состояний заявок.
+    ///
+    /// # Возвращает
+    ///
+    /// * `broadcast::Receiver<OrderState>` - Получатель обновлений.
+    pub fn subscribe(&self) -> broadcast::Receiver<OrderState> {
+        self.updates.subscribe()
+    }
+
+    /// Добавляет известное состояние заявки, например ответ `place_order`.
+    ///
+    /// # Аргументы
+    ///
+    /// * `state` - Состояние заявки.
+    pub fn record(&self, state: OrderState) {
+        apply_update(&self.orders, &self.updates, state);
+    }
+
+    /// Ожидает, пока состояние заявки не удовлетворит условию.
+    ///
+    /// # Аргументы
+    ///
+    /// * `order_id` - Идентификатор заявки.
+    /// * `timeout` - Максимальное время ожидания.
+    /// * `predicate` - Условие на состояние заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OrderState, FinamSdkError>` - Состояние заявки или ошибку `Timeout`.
+    pub async fn wait_for<F>(
+        &self,
+        order_id: &str,
+        timeout: Duration,
+        predicate: F,
+    ) -> Result<OrderState, FinamSdkError>
+    where
+        F: Fn(&OrderState) -> bool,
+    {
+        let mut receiver = self.subscribe();
+
+        let wait = async {
+            if let Some(state) = self.get(order_id).filter(|state| predicate(state)) {
+                return Some(state);
+            }
+
+            loop {
+                match receiver.recv().await {
+                    Ok(state) if state.order_id == order_id && predicate(&state) => {
+                        return Some(state);
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        if let Some(state) = self.get(order_id).filter(|state| predicate(state)) {
+                            return Some(state);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .ok()
+            .flatten()
+            .ok_or_else(|| FinamSdkError::Timeout(format!("order {order_id} state")))
+    }
+
+    /// Ожидает перехода заявки в конечный статус.
+    ///
+    /// # Аргументы
+    ///
+    /// * `order_id` - Идентификатор заявки.
+    /// * `timeout` - Максимальное время ожидания.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OrderState, FinamSdkError>` - Конечное состояние заявки или ошибку `Timeout`.
+    pub async fn wait_for_final(
+        &self,
+        order_id: &str,
+        timeout: Duration,
+    ) -> Result<OrderState, FinamSdkError> {
+        self.wait_for(order_id, timeout, |state| state.status().is_final())
+            .await
+    }
+
+    /// Повторно загружает заявки аккаунта и рассылает изменившиеся состояния.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<(), FinamSdkError>` - Пустой результат или ошибку загрузки.
+    pub async fn reload(&self, sdk: &FinamSdk) -> Result<(), FinamSdkError> {
+        for state in sdk.get_orders(&self.account_id).await? {
+            apply_update(&self.orders, &self.updates, state);
+        }
+
+        Ok(())
+    }
+}
+
+/// Общее состояние трекера, используемое фоновой задачей подписки.
+struct TrackerState {
+    account_id: String,
+    orders: Arc<RwLock<HashMap<String, OrderState>>>,
+    updates: broadcast::Sender<OrderState>,
+}
+
+impl TrackerState {
+    /// Поддерживает подписку на заявки до получения сигнала завершения.
+    ///
+    /// Первым используется поток, открытый при запуске трекера. После обрыва
+    /// подписка возобновляется до перезагрузки заявок, чтобы не пропустить обновления.
+    async fn run(
+        self,
+        sdk: FinamSdk,
+        stream: OrderStream,
+        mut shutdown_receiver: oneshot::Receiver<()>,
+    ) {
+        let mut stream = Some(stream);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_receiver => {
+                    log::info!("Order tracker for {} shutting down", self.account_id);
+                    return;
+                }
+                result = self.follow(&sdk, stream.take()) => {
+                    if let Err(error) = result {
+                        log::error!(
+                            "Order stream for {} failed. Reconnecting in 5 seconds... {:?}",
+                            self.account_id,
+                            error
+                        );
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = &mut shutdown_receiver => {
+                    log::info!("Order tracker for {} shutting down during reconnect", self.account_id);
+                    return;
+                }
+                _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+            }
+        }
+    }
+
+    /// Применяет обновления из потока заявок до его завершения.
+    ///
+    /// Без открытого потока подписывается заново и перезагружает заявки.
+    async fn follow(
+        &self,
+        sdk: &FinamSdk,
+        stream: Option<OrderStream>,
+    ) -> Result<(), FinamSdkError> {
+        let mut stream = match stream {
+            Some(stream) => stream,
+            None => {
+                let stream = sdk.subscribe_orders(&self.account_id).await?;
+
+                for state in sdk.get_orders(&self.account_id).await? {
+                    apply_update(&self.orders, &self.updates, state);
+                }
+
+                stream
+            }
+        };
+
+        while let Some(state) = stream.next().await {
+            apply_update(&self.orders, &self.updates, state?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Сохраняет состояние заявки и рассылает его подписчикам, если оно изменилось.
+fn apply_update(
+    orders: &RwLock<HashMap<String, OrderState>>,
+    updates: &broadcast::Sender<OrderState>,
+    state: OrderState,
+) {
+    {
+        let mut orders = orders.write().unwrap_or_else(|error| error.into_inner());
+
+        if orders.get(&state.order_id) == Some(&state) {
+            return;
+        }
+
+        orders.insert(state.order_id.clone(), state.clone());
+    }
+
+    let _ = updates.send(state);
+}
+
+fn read_orders(
+    orders: &RwLock<HashMap<String, OrderState>>,
+) -> std::sync::RwLockReadGuard<'_, HashMap<String, OrderState>> {
+    orders.read().unwrap_or_else(|error| error.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::grpc::tradeapi::v1::orders::OrderStatus;
+
+    fn tracker() -> OrderTracker {
+        let (updates, _) = broadcast::channel(16);
+        let (shutdown_sender, _) = oneshot::channel();
+
+        OrderTracker {
+            account_id: "A1".to_string(),
+            orders: Arc::new(RwLock::new(HashMap::new())),
+            updates,
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        }
+    }
+
+    fn state(order_id: &str, status: OrderStatus) -> OrderState {
+        OrderState {
+            order_id: order_id.to_string(),
+            status: status as i32,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_final_receives_update() {
+        let tracker = tracker();
+        tracker.record(state("1", OrderStatus::New));
+
+        let waiter = tracker.clone();
+        let handle = tokio::spawn(async move {
+            waiter
+                .wait_for_final("1", Duration::from_secs(1))
+                .await
+                .map(|state| state.status())
+        });
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        tracker.record(state("1", OrderStatus::Canceled));
+
+        assert_eq!(handle.await.unwrap().unwrap(), OrderStatus::Canceled);
+        assert_eq!(tracker.active_orders().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_states_are_not_broadcast() {
+        let tracker = tracker();
+        let mut receiver = tracker.subscribe();
+
+        tracker.record(state("1", OrderStatus::New));
+        tracker.record(state("1", OrderStatus::New));
+
+        assert!(receiver.try_recv().is_ok());
+        assert!(receiver.try_recv().is_err());
+    }
+}
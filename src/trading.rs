@@ -0,0 +1,843 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, atomic::Ordering},
+};
+
+use futures::{StreamExt, stream::BoxStream};
+use tokio::{
+    sync::Semaphore,
+    task::JoinSet,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    FinamOrdersServiceClient, FinamSdk, FinamSdkError,
+    decimal::{Decimal, from_decimal, opt_decimal},
+    proto::grpc::tradeapi::v1::{
+        AccountTrade, Side,
+        accounts::{GetAccountRequest, GetAccountResponse},
+        orders::{
+            CancelOrderRequest, GetOrderRequest, Order, OrderState, OrderStatus, OrderType,
+            OrdersRequest, SltpOrder, SubscribeOrdersRequest, SubscribeTradesRequest, TimeInForce,
+        },
+    },
+    rate_limit::RateLimiter,
+    risk::OrderIntent,
+    sltp,
+};
+
+/// Интервал опроса состояния заявки при ожидании ее отмены.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Максимальное время ожидания подтверждения отмены заявки.
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Максимальное количество одновременно выполняемых запросов при массовых операциях.
+const BULK_CONCURRENCY: usize = 8;
+
+/// Максимальная частота запросов в секунду при массовых операциях.
+const BULK_RATE_LIMIT: u32 = 20;
+
+/// Метка заявок, выставленных аварийным выключателем.
+const KILL_SWITCH_COMMENT: &str = "kill switch";
+
+/// Поток обновлений состояний заявок аккаунта.
+pub type OrderStream = BoxStream<'static, Result<OrderState, FinamSdkError>>;
+
+/// Поток сделок аккаунта.
+pub type TradeStream = BoxStream<'static, Result<AccountTrade, FinamSdkError>>;
+
+type OrderRequestFuture = Pin<Box<dyn Future<Output = Result<OrderState, FinamSdkError>> + Send>>;
+
+impl OrderStatus {
+    /// Проверяет, является ли статус конечным, после которого заявка больше не изменяется.
+    ///
+    /// # Возвращает
+    ///
+    /// * `bool` - `true`, если заявка исполнена, отменена, отклонена или истекла.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            Self::Filled
+                | Self::DoneForDay
+                | Self::Canceled
+                | Self::Replaced
+                | Self::Rejected
+                | Self::Expired
+                | Self::Failed
+                | Self::DeniedByBroker
+                | Self::RejectedByExchange
+                | Self::Executed
+                | Self::Disabled
+                | Self::SlExecuted
+                | Self::TpExecuted
+        )
+    }
+
+    /// Проверяет, является ли заявка активной (ожидает исполнения или отмены).
+    ///
+    /// # Возвращает
+    ///
+    /// * `bool` - `true`, если статус известен и не является конечным.
+    pub fn is_active(&self) -> bool {
+        *self != Self::Unspecified && !self.is_final()
+    }
+
+    /// Проверяет, исполнена ли заявка полностью.
+    ///
+    /// # Возвращает
+    ///
+    /// * `bool` - `true` для статусов полного исполнения, в том числе по SL/TP.
+    pub fn is_filled(&self) -> bool {
+        matches!(
+            self,
+            Self::Filled | Self::Executed | Self::SlExecuted | Self::TpExecuted
+        )
+    }
+}
+
+/// Результат замены заявки через отмену и повторное выставление.
+#[derive(Clone, Debug)]
+pub struct ReplaceOrderResult {
+    /// Конечное состояние исходной (отмененной) заявки.
+    pub canceled: OrderState,
+    /// Количество, исполненное по исходной заявке до ее отмены.
+    pub executed_quantity: Decimal,
+    /// Состояние новой заявки. `None`, если исполненное количество уже покрывает новый объем.
+    pub placed: Option<OrderState>,
+}
+
+/// Фильтр заявок для массовой отмены.
+///
+/// Незаполненные поля не ограничивают выборку.
+#[derive(Clone, Debug, Default)]
+pub struct OrderFilter {
+    /// Символ инструмента.
+    pub symbol: Option<String>,
+    /// Сторона заявки.
+    pub side: Option<Side>,
+    /// Статус заявки.
+    pub status: Option<OrderStatus>,
+}
+
+impl OrderFilter {
+    /// Проверяет, подходит ли заявка под фильтр.
+    ///
+    /// # Аргументы
+    ///
+    /// * `state` - Состояние заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `bool` - `true`, если заявка удовлетворяет всем заданным условиям.
+    pub fn matches(&self, state: &OrderState) -> bool {
+        let order = state.order.as_ref();
+
+        self.symbol
+            .as_ref()
+            .is_none_or(|symbol| order.is_some_and(|order| &order.symbol == symbol))
+            && self
+                .side
+                .is_none_or(|side| order.is_some_and(|order| order.side() == side))
+            && self.status.is_none_or(|status| state.status() == status)
+    }
+}
+
+/// Отчет о массовой отмене заявок.
+#[derive(Debug, Default)]
+pub struct CancelAllReport {
+    /// Состояния заявок, по которым отмена принята.
+    pub canceled: Vec<OrderState>,
+    /// Идентификаторы заявок, которые не удалось отменить, и причины ошибок.
+    pub failed: Vec<(String, FinamSdkError)>,
+}
+
+/// Отчет о срабатывании аварийного выключателя.
+#[derive(Debug, Default)]
+pub struct KillSwitchReport {
+    /// Результат отмены активных заявок.
+    pub cancel: CancelAllReport,
+    /// Ошибка загрузки активных заявок для отмены. Позиции закрываются и в этом случае.
+    pub cancel_error: Option<FinamSdkError>,
+    /// Ошибка загрузки позиций аккаунта. В этом случае позиции не закрываются.
+    pub positions_error: Option<FinamSdkError>,
+    /// Рыночные заявки, выставленные для закрытия позиций.
+    pub closed: Vec<OrderState>,
+    /// Символы позиций, которые не удалось закрыть, и причины ошибок.
+    pub close_failed: Vec<(String, FinamSdkError)>,
+}
+
+impl FinamSdk {
+    /// Выставляет биржевую заявку.
+    ///
+    /// Перед отправкой заявка проходит предторговые проверки риск-менеджмента.
+    /// Если торговля остановлена аварийным выключателем, заявка не отправляется.
+    /// Ноги мультилег заявки проверяются по отдельности.
+    ///
+    /// # Аргументы
+    ///
+    /// * `order` - Параметры заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OrderState, FinamSdkError>` - Состояние выставленной заявки или ошибку.
+    pub async fn place_order(&self, order: Order) -> Result<OrderState, FinamSdkError> {
+        if self.is_trading_halted() {
+            return Err(FinamSdkError::TradingHalted);
+        }
+
+        for intent in OrderIntent::from_order(&order)? {
+            self.risk.check(self, &intent).await?;
+        }
+
+        self.send_order(order).await
+    }
+
+    /// Выставляет SL/TP заявку.
+    ///
+    /// Перед отправкой заявка проходит предторговые проверки риск-менеджмента.
+    /// Если торговля остановлена аварийным выключателем, заявка не отправляется.
+    /// Срок действия заявки `GoodTillDate` сверяется с [`FinamSdk::server_now`].
+    ///
+    /// # Аргументы
+    ///
+    /// * `order` - Параметры SL/TP заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OrderState, FinamSdkError>` - Состояние выставленной заявки или ошибку.
+    pub async fn place_sltp_order(&self, order: SltpOrder) -> Result<OrderState, FinamSdkError> {
+        if self.paper.is_some() {
+            return Err(FinamSdkError::InvalidArgument(
+                "SL/TP orders are not supported in paper trading".to_string(),
+            ));
+        }
+
+        if self.is_trading_halted() {
+            return Err(FinamSdkError::TradingHalted);
+        }
+
+        sltp::check_expiry(&order, self.server_now())?;
+
+        self.risk
+            .check(self, &OrderIntent::try_from(&order)?)
+            .await?;
+
+        if let Some(journal) = &self.journal {
+            journal.record_sltp_order(&order);
+        }
+
+        let account_id = order.account_id.clone();
+        let symbol = order.symbol.clone();
+        self.journaled(&account_id, &symbol, async {
+            Ok(self.orders().place_sltp_order(order).await?.into_inner())
+        })
+        .await
+    }
+
+    /// Отправляет заявку без проверки аварийного выключателя.
+    ///
+    /// # Аргументы
+    ///
+    /// * `order` - Параметры заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OrderState, FinamSdkError>` - Состояние выставленной заявки или ошибку.
+    async fn send_order(&self, order: Order) -> Result<OrderState, FinamSdkError> {
+        if let Some(journal) = &self.journal {
+            journal.record_order(&order);
+        }
+
+        let account_id = order.account_id.clone();
+        let symbol = order.symbol.clone();
+        self.journaled(&account_id, &symbol, async {
+            match &self.paper {
+                Some(paper) => paper.place_order(order).await,
+                None => Ok(self.orders().place_order(order).await?.into_inner()),
+            }
+        })
+        .await
+    }
+
+    /// Отменяет биржевую заявку.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `order_id` - Идентификатор заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OrderState, FinamSdkError>` - Состояние заявки после запроса отмены или ошибку.
+    pub async fn cancel_order(
+        &self,
+        account_id: &str,
+        order_id: &str,
+    ) -> Result<OrderState, FinamSdkError> {
+        let request = CancelOrderRequest {
+            account_id: account_id.to_string(),
+            order_id: order_id.to_string(),
+        };
+
+        if let Some(journal) = &self.journal {
+            journal.record_cancel(&request);
+        }
+
+        self.journaled(account_id, "", async {
+            match &self.paper {
+                Some(paper) => paper.cancel_order(account_id, order_id),
+                None => Ok(self.orders().cancel_order(request).await?.into_inner()),
+            }
+        })
+        .await
+    }
+
+    /// Получает текущее состояние заявки.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `order_id` - Идентификатор заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OrderState, FinamSdkError>` - Состояние заявки или ошибку.
+    pub async fn get_order(
+        &self,
+        account_id: &str,
+        order_id: &str,
+    ) -> Result<OrderState, FinamSdkError> {
+        if let Some(paper) = &self.paper {
+            return paper.get_order(account_id, order_id);
+        }
+
+        Ok(self
+            .orders()
+            .get_order(GetOrderRequest {
+                account_id: account_id.to_string(),
+                order_id: order_id.to_string(),
+            })
+            .await?
+            .into_inner())
+    }
+
+    /// Получает список заявок аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Vec<OrderState>, FinamSdkError>` - Заявки аккаунта или ошибку.
+    pub async fn get_orders(&self, account_id: &str) -> Result<Vec<OrderState>, FinamSdkError> {
+        if let Some(paper) = &self.paper {
+            return Ok(paper.get_orders(account_id));
+        }
+
+        Ok(self
+            .orders()
+            .get_orders(OrdersRequest {
+                account_id: account_id.to_string(),
+            })
+            .await?
+            .into_inner()
+            .orders)
+    }
+
+    /// Подписывается на обновления состояний заявок аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OrderStream, FinamSdkError>` - Поток состояний заявок или ошибку.
+    pub async fn subscribe_orders(&self, account_id: &str) -> Result<OrderStream, FinamSdkError> {
+        match &self.paper {
+            Some(paper) => Ok(paper.subscribe_orders(account_id)),
+            None => api_order_stream(self.orders(), account_id).await,
+        }
+    }
+
+    /// Подписывается на сделки аккаунта.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<TradeStream, FinamSdkError>` - Поток сделок или ошибку.
+    pub async fn subscribe_trades(&self, account_id: &str) -> Result<TradeStream, FinamSdkError> {
+        match &self.paper {
+            Some(paper) => Ok(paper.subscribe_trades(account_id)),
+            None => api_trade_stream(self.orders(), account_id).await,
+        }
+    }
+
+    /// Получает информацию об аккаунте, включая позиции и денежные средства.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<GetAccountResponse, FinamSdkError>` - Информация об аккаунте или ошибку.
+    pub async fn get_account(&self, account_id: &str) -> Result<GetAccountResponse, FinamSdkError> {
+        if let Some(paper) = &self.paper {
+            return paper.get_account(account_id);
+        }
+
+        Ok(self
+            .accounts()
+            .get_account(GetAccountRequest {
+                account_id: account_id.to_string(),
+            })
+            .await?
+            .into_inner())
+    }
+
+    /// Заменяет заявку новой с другой ценой и количеством.
+    ///
+    /// API не поддерживает изменение заявки, поэтому замена выполняется отменой
+    /// исходной заявки, ожиданием подтверждения отмены и выставлением новой заявки
+    /// на остаток с учетом частичного исполнения.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `order_id` - Идентификатор заменяемой заявки.
+    /// * `new_price` - Новая цена. Для лимитных заявок заменяет `limit_price`,
+    ///   для стоп заявок - `stop_price`. `None` оставляет цену без изменений.
+    /// * `new_quantity` - Новый общий объем заявки, включая уже исполненную часть.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<ReplaceOrderResult, FinamSdkError>` - Результат замены или ошибку.
+    ///   Если заявка исполнилась до отмены, возвращается `FinamSdkError::OrderFilled`.
+    pub async fn replace_order(
+        &self,
+        account_id: &str,
+        order_id: &str,
+        new_price: Option<Decimal>,
+        new_quantity: Decimal,
+    ) -> Result<ReplaceOrderResult, FinamSdkError> {
+        let current = self.get_order(account_id, order_id).await?;
+        let status = current.status();
+
+        if status.is_filled() {
+            return Err(FinamSdkError::OrderFilled(order_id.to_string()));
+        }
+
+        if !status.is_active() {
+            return Err(FinamSdkError::OrderNotActive {
+                order_id: order_id.to_string(),
+                status,
+            });
+        }
+
+        let mut order = current.order.clone().ok_or_else(|| {
+            FinamSdkError::InvalidArgument(format!("order {order_id} has no parameters"))
+        })?;
+
+        if order.r#type() == OrderType::Market || order.r#type() == OrderType::MultiLeg {
+            return Err(FinamSdkError::InvalidArgument(format!(
+                "order {order_id} of type {} can't be replaced",
+                order.r#type().as_str_name()
+            )));
+        }
+
+        if status != OrderStatus::PendingCancel {
+            self.cancel_order(account_id, order_id).await?;
+        }
+
+        let canceled = self.wait_for_cancel(account_id, order_id).await?;
+        let executed_quantity = opt_decimal(canceled.executed_quantity.as_ref())?;
+        let remaining = new_quantity - executed_quantity;
+
+        if remaining <= Decimal::ZERO {
+            return Ok(ReplaceOrderResult {
+                canceled,
+                executed_quantity,
+                placed: None,
+            });
+        }
+
+        order.quantity = Some(from_decimal(remaining));
+        order.client_order_id = String::new();
+
+        if let Some(price) = new_price {
+            match order.r#type() {
+                OrderType::Stop => order.stop_price = Some(from_decimal(price)),
+                _ => order.limit_price = Some(from_decimal(price)),
+            }
+        }
+
+        match self.place_order(order).await {
+            Ok(placed) => Ok(ReplaceOrderResult {
+                canceled,
+                executed_quantity,
+                placed: Some(placed),
+            }),
+            Err(error) => Err(FinamSdkError::ReplaceFailed {
+                canceled: Box::new(canceled),
+                source: Box::new(error),
+            }),
+        }
+    }
+
+    /// Отменяет все активные заявки аккаунта, подходящие под фильтр.
+    ///
+    /// Запросы отмены выполняются параллельно с ограничением частоты.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `filter` - Фильтр заявок по символу, стороне или статусу.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<CancelAllReport, FinamSdkError>` - Отчет об отмене или ошибку получения списка заявок.
+    pub async fn cancel_all(
+        &self,
+        account_id: &str,
+        filter: &OrderFilter,
+    ) -> Result<CancelAllReport, FinamSdkError> {
+        let requests = self
+            .get_orders(account_id)
+            .await?
+            .into_iter()
+            .filter(|state| state.status().is_active() && filter.matches(state))
+            .map(|state| {
+                let sdk = self.clone();
+                let account_id = account_id.to_string();
+                let order_id = state.order_id.clone();
+                let future: OrderRequestFuture =
+                    Box::pin(async move { sdk.cancel_order(&account_id, &order_id).await });
+                (state.order_id, future)
+            })
+            .collect();
+
+        let mut report = CancelAllReport::default();
+
+        for (order_id, result) in execute_limited(requests).await {
+            match result {
+                Ok(state) => report.canceled.push(state),
+                Err(error) => report.failed.push((order_id, error)),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Аварийно останавливает торговлю по аккаунту.
+    ///
+    /// Блокирует выставление новых заявок через SDK, отменяет все активные заявки
+    /// и закрывает открытые позиции рыночными заявками. Ошибки отмены заявок и
+    /// загрузки позиций записываются в отчет. Блокировка действует до
+    /// вызова [`FinamSdk::resume_trading`] и распространяется на все клоны SDK.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<KillSwitchReport, FinamSdkError>` - Подробный отчет о срабатывании.
+    pub async fn kill_switch(&self, account_id: &str) -> Result<KillSwitchReport, FinamSdkError> {
+        self.trading_halted.store(true, Ordering::SeqCst);
+        log::warn!("Kill switch activated for account {}", account_id);
+
+        let mut report = KillSwitchReport::default();
+
+        match self.cancel_all(account_id, &OrderFilter::default()).await {
+            Ok(cancel) => report.cancel = cancel,
+            Err(error) => {
+                log::error!(
+                    "Kill switch failed to cancel orders for {}: {:?}",
+                    account_id,
+                    error
+                );
+                report.cancel_error = Some(error);
+            }
+        }
+
+        let account = match self.get_account(account_id).await {
+            Ok(account) => account,
+            Err(error) => {
+                log::error!(
+                    "Kill switch failed to load positions for {}: {:?}",
+                    account_id,
+                    error
+                );
+                report.positions_error = Some(error);
+                return Ok(report);
+            }
+        };
+        let mut requests = Vec::new();
+
+        for position in account.positions {
+            let quantity = match opt_decimal(position.quantity.as_ref()) {
+                Ok(quantity) => quantity,
+                Err(error) => {
+                    report.close_failed.push((position.symbol, error));
+                    continue;
+                }
+            };
+
+            if quantity.is_zero() {
+                continue;
+            }
+
+            let order = Order {
+                account_id: account_id.to_string(),
+                symbol: position.symbol.clone(),
+                quantity: Some(from_decimal(quantity.abs())),
+                side: if quantity.is_sign_positive() {
+                    Side::Sell
+                } else {
+                    Side::Buy
+                } as i32,
+                r#type: OrderType::Market as i32,
+                time_in_force: TimeInForce::Day as i32,
+                comment: KILL_SWITCH_COMMENT.to_string(),
+                ..Default::default()
+            };
+
+            let sdk = self.clone();
+            let future: OrderRequestFuture = Box::pin(async move { sdk.send_order(order).await });
+            requests.push((position.symbol, future));
+        }
+
+        for (symbol, result) in execute_limited(requests).await {
+            match result {
+                Ok(state) => report.closed.push(state),
+                Err(error) => report.close_failed.push((symbol, error)),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Снимает блокировку торговли, установленную аварийным выключателем.
+    pub fn resume_trading(&self) {
+        self.trading_halted.store(false, Ordering::SeqCst);
+        log::info!("Trading resumed");
+    }
+
+    /// Проверяет, остановлена ли торговля аварийным выключателем.
+    ///
+    /// # Возвращает
+    ///
+    /// * `bool` - `true`, если выставление новых заявок заблокировано.
+    pub fn is_trading_halted(&self) -> bool {
+        self.trading_halted.load(Ordering::SeqCst)
+    }
+
+    /// Проверяет, включен ли режим бумажной торговли.
+    ///
+    /// # Возвращает
+    ///
+    /// * `bool` - `true`, если заявки исполняются симулятором.
+    pub fn is_paper_trading(&self) -> bool {
+        self.paper.is_some()
+    }
+
+    /// Выполняет запрос и записывает в журнал ответ сервера и время ответа.
+    async fn journaled<F>(
+        &self,
+        account_id: &str,
+        symbol: &str,
+        request: F,
+    ) -> Result<OrderState, FinamSdkError>
+    where
+        F: Future<Output = Result<OrderState, FinamSdkError>>,
+    {
+        let started = Instant::now();
+        let result = request.await;
+
+        if let Some(journal) = &self.journal {
+            journal.record_response(account_id, symbol, &result, started.elapsed());
+        }
+
+        result
+    }
+
+    /// Ожидает подтверждения отмены заявки, периодически опрашивая ее состояние.
+    ///
+    /// # Аргументы
+    ///
+    /// * `account_id` - Идентификатор аккаунта.
+    /// * `order_id` - Идентификатор заявки.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<OrderState, FinamSdkError>` - Состояние отмененной заявки или ошибку.
+    async fn wait_for_cancel(
+        &self,
+        account_id: &str,
+        order_id: &str,
+    ) -> Result<OrderState, FinamSdkError> {
+        let deadline = Instant::now() + CANCEL_TIMEOUT;
+
+        loop {
+            let state = self.get_order(account_id, order_id).await?;
+            let status = state.status();
+
+            if status.is_filled() {
+                return Err(FinamSdkError::OrderFilled(order_id.to_string()));
+            }
+
+            if status.is_final() {
+                return Ok(state);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(FinamSdkError::Timeout(format!(
+                    "order {order_id} cancellation was not confirmed"
+                )));
+            }
+
+            tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Выполняет запросы к API параллельно с ограничением количества и частоты.
+///
+/// # Аргументы
+///
+/// * `requests` - Пары из ключа запроса и самого запроса.
+///
+/// # Возвращает
+///
+/// * `Vec<(String, Result<OrderState, FinamSdkError>)>` - Результаты в порядке завершения.
+async fn execute_limited(
+    requests: Vec<(String, OrderRequestFuture)>,
+) -> Vec<(String, Result<OrderState, FinamSdkError>)> {
+    let limiter = Arc::new(RateLimiter::new(BULK_RATE_LIMIT));
+    let semaphore = Arc::new(Semaphore::new(BULK_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+
+    for (key, request) in requests {
+        let limiter = limiter.clone();
+        let semaphore = semaphore.clone();
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            limiter.acquire().await;
+            (key, request.await)
+        });
+    }
+
+    let mut results = Vec::new();
+
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(error) => log::error!("Bulk request task failed: {:?}", error),
+        }
+    }
+
+    results
+}
+
+/// Подписывается на обновления заявок аккаунта через API.
+pub(crate) async fn api_order_stream(
+    mut orders: FinamOrdersServiceClient,
+    account_id: &str,
+) -> Result<OrderStream, FinamSdkError> {
+    let stream = orders
+        .subscribe_orders(SubscribeOrdersRequest {
+            account_id: account_id.to_string(),
+        })
+        .await?
+        .into_inner();
+
+    Ok(stream
+        .flat_map(|message| {
+            let items: Vec<_> = match message {
+                Ok(response) => response.orders.into_iter().map(Ok).collect(),
+                Err(status) => vec![Err(status.into())],
+            };
+            futures::stream::iter(items)
+        })
+        .boxed())
+}
+
+/// Подписывается на сделки аккаунта через API.
+pub(crate) async fn api_trade_stream(
+    mut orders: FinamOrdersServiceClient,
+    account_id: &str,
+) -> Result<TradeStream, FinamSdkError> {
+    let stream = orders
+        .subscribe_trades(SubscribeTradesRequest {
+            account_id: account_id.to_string(),
+        })
+        .await?
+        .into_inner();
+
+    Ok(stream
+        .flat_map(|message| {
+            let items: Vec<_> = match message {
+                Ok(response) => response.trades.into_iter().map(Ok).collect(),
+                Err(status) => vec![Err(status.into())],
+            };
+            futures::stream::iter(items)
+        })
+        .boxed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_status_classification() {
+        assert!(OrderStatus::New.is_active());
+        assert!(OrderStatus::PartiallyFilled.is_active());
+        assert!(OrderStatus::PendingCancel.is_active());
+        assert!(!OrderStatus::Unspecified.is_active());
+
+        assert!(OrderStatus::Canceled.is_final());
+        assert!(!OrderStatus::Canceled.is_filled());
+        assert!(OrderStatus::Filled.is_filled());
+        assert!(OrderStatus::TpExecuted.is_filled());
+    }
+
+    #[test]
+    fn test_order_filter_matches() {
+        let state = OrderState {
+            order_id: "1".to_string(),
+            status: OrderStatus::New as i32,
+            order: Some(Order {
+                symbol: "SBER@MISX".to_string(),
+                side: Side::Buy as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(OrderFilter::default().matches(&state));
+        assert!(
+            OrderFilter {
+                symbol: Some("SBER@MISX".to_string()),
+                side: Some(Side::Buy),
+                status: Some(OrderStatus::New),
+            }
+            .matches(&state)
+        );
+        assert!(
+            !OrderFilter {
+                side: Some(Side::Sell),
+                ..Default::default()
+            }
+            .matches(&state)
+        );
+    }
+}
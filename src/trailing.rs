@@ -0,0 +1,795 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Mutex, broadcast, oneshot},
+    task::JoinHandle,
+};
+
+use crate::{
+    FinamSdk, FinamSdkError, ShutdownGuard,
+    decimal::{Decimal, RoundingStrategy, from_decimal, round_to_step, to_decimal},
+    proto::grpc::tradeapi::v1::{
+        Side,
+        assets::GetAssetRequest,
+        marketdata::{SubscribeLatestTradesRequest, SubscribeQuoteRequest},
+        orders::{Order, OrderState, OrderType, TimeInForce},
+    },
+    util::{RECONNECT_DELAY, next_id},
+};
+
+/// Емкость канала событий трейлинг-стопов.
+const EVENTS_CAPACITY: usize = 256;
+
+/// Отступ стопа от лучшей цены.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrailingOffset {
+    /// Отступ в единицах цены.
+    Absolute(Decimal),
+    /// Отступ в процентах от лучшей цены.
+    Percent(Decimal),
+}
+
+impl TrailingOffset {
+    /// Вычисляет отступ в единицах цены от заданной цены.
+    ///
+    /// # Аргументы
+    ///
+    /// * `price` - Цена, от которой считается отступ.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Decimal` - Отступ в единицах цены.
+    pub fn amount(&self, price: Decimal) -> Decimal {
+        match self {
+            Self::Absolute(value) => *value,
+            Self::Percent(percent) => price * *percent / Decimal::ONE_HUNDRED,
+        }
+    }
+}
+
+/// Источник цен для сопровождения стопа.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrailPriceSource {
+    /// Цена последней сделки из потока котировок `subscribe_quote`.
+    Quote,
+    /// Цены сделок из потока `subscribe_latest_trades`.
+    Trades,
+}
+
+/// Заявка, выставляемая при срабатывании стопа.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrailExecution {
+    /// Рыночная заявка.
+    Market,
+    /// Лимитная заявка по цене стопа, сдвинутой на допустимое проскальзывание.
+    Limit {
+        /// Допустимое проскальзывание от цены стопа.
+        slippage: TrailingOffset,
+    },
+}
+
+/// Параметры нового трейлинг-стопа.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrailingStopRequest {
+    /// Идентификатор аккаунта.
+    pub account_id: String,
+    /// Символ инструмента.
+    pub symbol: String,
+    /// Сторона закрывающей заявки: `Side::Sell` защищает длинную позицию, `Side::Buy` - короткую.
+    pub side: Side,
+    /// Количество в шт.
+    pub quantity: Decimal,
+    /// Отступ стопа от лучшей цены.
+    pub offset: TrailingOffset,
+    /// Источник цен.
+    pub source: TrailPriceSource,
+    /// Заявка, выставляемая при срабатывании.
+    pub execution: TrailExecution,
+}
+
+/// Результат обработки новой цены трейлинг-стопом.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrailUpdate {
+    /// Стоп не изменился.
+    Unchanged,
+    /// Стоп подтянут вслед за ценой.
+    Moved,
+    /// Цена достигла стопа.
+    Triggered,
+}
+
+/// Состояние трейлинг-стопа.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrailState {
+    /// Стоп сопровождает цену.
+    #[default]
+    Active,
+    /// Стоп сработал, закрывающая заявка выставляется и еще не подтверждена.
+    Triggered,
+}
+
+/// Трейлинг-стоп.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrailingStop {
+    /// Идентификатор трейлинг-стопа.
+    pub id: String,
+    /// Идентификатор аккаунта.
+    pub account_id: String,
+    /// Символ инструмента.
+    pub symbol: String,
+    /// Сторона закрывающей заявки.
+    #[serde(with = "side_name")]
+    pub side: Side,
+    /// Количество в шт.
+    pub quantity: Decimal,
+    /// Отступ стопа от лучшей цены.
+    pub offset: TrailingOffset,
+    /// Источник цен.
+    pub source: TrailPriceSource,
+    /// Заявка, выставляемая при срабатывании.
+    pub execution: TrailExecution,
+    /// Минимальный шаг цены инструмента.
+    pub price_step: Decimal,
+    /// Лучшая цена с момента создания: максимум для продажи, минимум для покупки.
+    pub best_price: Option<Decimal>,
+    /// Текущая цена стопа.
+    pub stop_price: Option<Decimal>,
+    /// Состояние трейлинг-стопа.
+    #[serde(default)]
+    pub state: TrailState,
+}
+
+impl TrailingStop {
+    /// Обрабатывает новую цену: подтягивает стоп и проверяет срабатывание.
+    ///
+    /// Стоп двигается только в сторону прибыли: для продажи вверх, для покупки вниз.
+    /// Сработавший стоп переходит в состояние `TrailState::Triggered` и больше
+    /// не реагирует на цены.
+    ///
+    /// # Аргументы
+    ///
+    /// * `price` - Новая рыночная цена.
+    ///
+    /// # Возвращает
+    ///
+    /// * `TrailUpdate` - Результат обработки цены.
+    pub fn update(&mut self, price: Decimal) -> TrailUpdate {
+        if price <= Decimal::ZERO || self.state == TrailState::Triggered {
+            return TrailUpdate::Unchanged;
+        }
+
+        let sells = self.side == Side::Sell;
+
+        if let Some(stop) = self.stop_price
+            && ((sells && price <= stop) || (!sells && price >= stop))
+        {
+            self.state = TrailState::Triggered;
+            return TrailUpdate::Triggered;
+        }
+
+        let improved = self
+            .best_price
+            .is_none_or(|best| (sells && price > best) || (!sells && price < best));
+
+        if !improved {
+            return TrailUpdate::Unchanged;
+        }
+
+        self.best_price = Some(price);
+
+        let offset = self.offset.amount(price);
+        let stop = if sells {
+            round_to_step(
+                price - offset,
+                self.price_step,
+                RoundingStrategy::ToNegativeInfinity,
+            )
+        } else {
+            round_to_step(
+                price + offset,
+                self.price_step,
+                RoundingStrategy::ToPositiveInfinity,
+            )
+        };
+
+        if self.stop_price == Some(stop) {
+            return TrailUpdate::Unchanged;
+        }
+
+        self.stop_price = Some(stop);
+        TrailUpdate::Moved
+    }
+
+    /// Формирует заявку, выставляемую при срабатывании стопа.
+    pub fn exit_order(&self) -> Order {
+        let mut order = Order {
+            account_id: self.account_id.clone(),
+            symbol: self.symbol.clone(),
+            quantity: Some(from_decimal(self.quantity)),
+            side: self.side as i32,
+            r#type: OrderType::Market as i32,
+            time_in_force: TimeInForce::Day as i32,
+            comment: self.exit_comment(),
+            ..Default::default()
+        };
+
+        if let (TrailExecution::Limit { slippage }, Some(stop)) = (self.execution, self.stop_price)
+        {
+            let amount = slippage.amount(stop);
+            let price = if self.side == Side::Sell {
+                round_to_step(
+                    stop - amount,
+                    self.price_step,
+                    RoundingStrategy::ToNegativeInfinity,
+                )
+            } else {
+                round_to_step(
+                    stop + amount,
+                    self.price_step,
+                    RoundingStrategy::ToPositiveInfinity,
+                )
+            };
+
+            order.r#type = OrderType::Limit as i32;
+            order.limit_price = Some(from_decimal(price));
+        }
+
+        order
+    }
+
+    /// Возвращает комментарий закрывающей заявки, по которому она находится после перезапуска.
+    pub fn exit_comment(&self) -> String {
+        format!("trailing stop {}", self.id)
+    }
+}
+
+/// Событие трейлинг-стопа.
+#[derive(Clone, Debug)]
+pub enum TrailingStopEvent {
+    /// Стоп подтянут вслед за ценой.
+    Moved(TrailingStop),
+    /// Стоп сработал, закрывающая заявка выставлена.
+    Triggered {
+        /// Сработавший трейлинг-стоп.
+        trail: TrailingStop,
+        /// Состояние выставленной заявки.
+        order: Box<OrderState>,
+    },
+    /// Стоп сработал, но выставить заявку не удалось. Трейлинг-стоп возвращен
+    /// в активное состояние и сработает снова при следующей цене за стопом.
+    Failed {
+        /// Сработавший трейлинг-стоп.
+        trail: TrailingStop,
+        /// Описание ошибки.
+        error: String,
+    },
+}
+
+/// Клиентский движок трейлинг-стопов.
+///
+/// Следит за ценами из потока котировок или сделок, подтягивает виртуальный стоп
+/// и при его достижении выставляет рыночную или лимитную заявку через
+/// `FinamSdk::place_order`. Трейлинг-стопы сохраняются в JSON файл
+/// и загружаются из него при следующем запуске.
+///
+/// Сработавший стоп сохраняется в состоянии `TrailState::Triggered` и снимается
+/// только после подтверждения закрывающей заявки. Если заявку выставить не
+/// удалось, стоп возвращается в активное состояние.
+#[derive(Clone, Debug)]
+pub struct TrailingStopEngine {
+    inner: Arc<TrailingInner>,
+    /// Удерживает подписки на рыночные данные. При уничтожении последней
+    /// ссылки на движок отправляет сигнал завершения.
+    #[allow(dead_code)]
+    shutdown_guard: Arc<ShutdownGuard>,
+}
+
+#[derive(Debug)]
+struct TrailingInner {
+    sdk: FinamSdk,
+    path: Option<PathBuf>,
+    trails: Mutex<HashMap<String, TrailingStop>>,
+    feeds: Mutex<HashMap<(String, TrailPriceSource), JoinHandle<()>>>,
+    events: broadcast::Sender<TrailingStopEvent>,
+}
+
+impl TrailingStopEngine {
+    /// Запускает движок и восстанавливает сохраненные трейлинг-стопы.
+    ///
+    /// Для стопов, сработавших до перезапуска, закрывающая заявка ищется среди
+    /// заявок аккаунта по комментарию. Если она не найдена, заявка выставляется снова.
+    ///
+    /// # Аргументы
+    ///
+    /// * `sdk` - Клиент SDK.
+    /// * `path` - Путь к файлу хранения активных трейлинг-стопов. `None` отключает сохранение.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Self, FinamSdkError>` - Запущенный движок или ошибку чтения файла.
+    pub async fn start(sdk: FinamSdk, path: Option<PathBuf>) -> Result<Self, FinamSdkError> {
+        let trails = match &path {
+            Some(path) if tokio::fs::try_exists(path).await? => {
+                let content = tokio::fs::read(path).await?;
+                serde_json::from_slice::<Vec<TrailingStop>>(&content)?
+            }
+            _ => Vec::new(),
+        };
+
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        let inner = Arc::new(TrailingInner {
+            sdk,
+            path,
+            trails: Mutex::new(
+                trails
+                    .into_iter()
+                    .map(|trail| (trail.id.clone(), trail))
+                    .collect(),
+            ),
+            feeds: Mutex::new(HashMap::new()),
+            events,
+        });
+
+        inner.clone().sync_feeds().await;
+        inner.clone().resume_triggered().await;
+
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+        let supervisor = inner.clone();
+        tokio::spawn(async move {
+            let _ = shutdown_receiver.await;
+            log::info!("Trailing stop engine shutting down");
+            for (_, feed) in supervisor.feeds.lock().await.drain() {
+                feed.abort();
+            }
+        });
+
+        Ok(Self {
+            inner,
+            shutdown_guard: Arc::new(ShutdownGuard {
+                sender: Some(shutdown_sender),
+            }),
+        })
+    }
+
+    /// Добавляет трейлинг-стоп.
+    ///
+    /// Шаг цены загружается из параметров инструмента для округления цены стопа.
+    ///
+    /// # Аргументы
+    ///
+    /// * `request` - Параметры трейлинг-стопа.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<TrailingStop, FinamSdkError>` - Созданный трейлинг-стоп или ошибку.
+    pub async fn add(&self, request: TrailingStopRequest) -> Result<TrailingStop, FinamSdkError> {
+        if request.side == Side::Unspecified || request.quantity <= Decimal::ZERO {
+            return Err(FinamSdkError::InvalidArgument(
+                "trailing stop requires side and positive quantity".to_string(),
+            ));
+        }
+
+        let asset = self
+            .inner
+            .sdk
+            .assets()
+            .get_asset(GetAssetRequest {
+                symbol: request.symbol.clone(),
+                account_id: request.account_id.clone(),
+            })
+            .await?
+            .into_inner();
+
+        let trail = TrailingStop {
+            id: next_id("trail-"),
+            account_id: request.account_id,
+            symbol: request.symbol,
+            side: request.side,
+            quantity: request.quantity,
+            offset: request.offset,
+            source: request.source,
+            execution: request.execution,
+            price_step: asset.price_step(),
+            best_price: None,
+            stop_price: None,
+            state: TrailState::Active,
+        };
+
+        self.inner
+            .trails
+            .lock()
+            .await
+            .insert(trail.id.clone(), trail.clone());
+        self.inner.persist().await?;
+        self.inner.clone().sync_feeds().await;
+
+        Ok(trail)
+    }
+
+    /// Снимает трейлинг-стоп.
+    ///
+    /// # Аргументы
+    ///
+    /// * `id` - Идентификатор трейлинг-стопа.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Result<Option<TrailingStop>, FinamSdkError>` - Снятый трейлинг-стоп, если он был активен.
+    pub async fn remove(&self, id: &str) -> Result<Option<TrailingStop>, FinamSdkError> {
+        let removed = self.inner.trails.lock().await.remove(id);
+
+        if removed.is_some() {
+            self.inner.persist().await?;
+            self.inner.clone().sync_feeds().await;
+        }
+
+        Ok(removed)
+    }
+
+    /// Возвращает трейлинг-стопы, включая сработавшие и еще не подтвержденные.
+    pub async fn trails(&self) -> Vec<TrailingStop> {
+        self.inner.trails.lock().await.values().cloned().collect()
+    }
+
+    /// Подписывается на события трейлинг-стопов.
+    pub fn subscribe(&self) -> broadcast::Receiver<TrailingStopEvent> {
+        self.inner.events.subscribe()
+    }
+}
+
+impl TrailingInner {
+    /// Запускает недостающие подписки на рыночные данные и останавливает лишние.
+    async fn sync_feeds(self: Arc<Self>) {
+        let needed = self.prune_feeds().await;
+        let mut feeds = self.feeds.lock().await;
+
+        for key in needed {
+            if let Entry::Vacant(entry) = feeds.entry(key) {
+                let (symbol, source) = entry.key().clone();
+                entry.insert(tokio::spawn(self.clone().run_feed(symbol, source)));
+            }
+        }
+    }
+
+    /// Останавливает подписки, не нужные трейлинг-стопам.
+    ///
+    /// Подписки сработавших стопов сохраняются, чтобы стоп мог сработать снова,
+    /// если закрывающую заявку выставить не удалось.
+    ///
+    /// # Возвращает
+    ///
+    /// * `Vec<(String, TrailPriceSource)>` - Подписки, нужные трейлинг-стопам.
+    async fn prune_feeds(&self) -> Vec<(String, TrailPriceSource)> {
+        let needed: Vec<(String, TrailPriceSource)> = {
+            let trails = self.trails.lock().await;
+            let mut keys: Vec<_> = trails
+                .values()
+                .map(|trail| (trail.symbol.clone(), trail.source))
+                .collect();
+            keys.sort_by(|a, b| a.0.cmp(&b.0));
+            keys.dedup();
+            keys
+        };
+
+        let mut feeds = self.feeds.lock().await;
+
+        feeds.retain(|key, feed| {
+            let keep = needed.contains(key) && !feed.is_finished();
+            if !keep {
+                feed.abort();
+            }
+            keep
+        });
+
+        needed
+    }
+
+    /// Поддерживает подписку на цены инструмента с переподключением.
+    async fn run_feed(self: Arc<Self>, symbol: String, source: TrailPriceSource) {
+        loop {
+            if let Err(error) = self.stream_prices(&symbol, source).await {
+                log::error!(
+                    "Price stream for {} failed. Reconnecting in 5 seconds... {:?}",
+                    symbol,
+                    error
+                );
+            }
+
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn stream_prices(
+        self: &Arc<Self>,
+        symbol: &str,
+        source: TrailPriceSource,
+    ) -> Result<(), FinamSdkError> {
+        match source {
+            TrailPriceSource::Quote => {
+                let mut stream = self
+                    .sdk
+                    .market_data()
+                    .subscribe_quote(SubscribeQuoteRequest {
+                        symbols: vec![symbol.to_string()],
+                    })
+                    .await?
+                    .into_inner();
+
+                while let Some(response) = stream.next().await {
+                    let response = response?;
+
+                    if let Some(error) = response.error {
+                        log::warn!("Quote stream error for {}: {:?}", symbol, error);
+                    }
+
+                    for quote in response.quote {
+                        if let Some(last) = quote.last.as_ref() {
+                            self.on_price(symbol, source, to_decimal(last)?).await;
+                        }
+                    }
+                }
+            }
+            TrailPriceSource::Trades => {
+                let mut stream = self
+                    .sdk
+                    .market_data()
+                    .subscribe_latest_trades(SubscribeLatestTradesRequest {
+                        symbol: symbol.to_string(),
+                    })
+                    .await?
+                    .into_inner();
+
+                while let Some(response) = stream.next().await {
+                    for trade in response?.trades {
+                        if let Some(price) = trade.price.as_ref() {
+                            self.on_price(symbol, source, to_decimal(price)?).await;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Применяет новую цену ко всем трейлинг-стопам инструмента.
+    ///
+    /// Сработавшие стопы сохраняются в состоянии `TrailState::Triggered`
+    /// до выставления закрывающих заявок.
+    async fn on_price(self: &Arc<Self>, symbol: &str, source: TrailPriceSource, price: Decimal) {
+        let mut moved = Vec::new();
+        let mut triggered = Vec::new();
+
+        {
+            let mut trails = self.trails.lock().await;
+
+            for trail in trails
+                .values_mut()
+                .filter(|trail| trail.symbol == symbol && trail.source == source)
+            {
+                match trail.update(price) {
+                    TrailUpdate::Unchanged => {}
+                    TrailUpdate::Moved => moved.push(trail.clone()),
+                    TrailUpdate::Triggered => triggered.push(trail.clone()),
+                }
+            }
+        }
+
+        if moved.is_empty() && triggered.is_empty() {
+            return;
+        }
+
+        for trail in moved {
+            let _ = self.events.send(TrailingStopEvent::Moved(trail));
+        }
+
+        if let Err(error) = self.persist().await {
+            log::error!("Failed to persist trailing stops: {:?}", error);
+        }
+
+        for trail in triggered {
+            tokio::spawn(self.clone().fire(trail));
+        }
+    }
+
+    /// Завершает стопы, сработавшие до перезапуска.
+    ///
+    /// Стоп снимается, если его закрывающая заявка уже есть среди заявок аккаунта,
+    /// иначе заявка выставляется снова.
+    async fn resume_triggered(self: Arc<Self>) {
+        let triggered: Vec<_> = self
+            .trails
+            .lock()
+            .await
+            .values()
+            .filter(|trail| trail.state == TrailState::Triggered)
+            .cloned()
+            .collect();
+
+        for trail in triggered {
+            let placed = match self.sdk.get_orders(&trail.account_id).await {
+                Ok(orders) => orders.into_iter().find(|state| {
+                    state
+                        .order
+                        .as_ref()
+                        .is_some_and(|order| order.comment == trail.exit_comment())
+                }),
+                Err(error) => {
+                    log::error!(
+                        "Failed to check trailing stop {} order, keeping it triggered: {:?}",
+                        trail.id,
+                        error
+                    );
+                    continue;
+                }
+            };
+
+            match placed {
+                Some(order) => self.complete(trail, order).await,
+                None => self.clone().fire(trail).await,
+            }
+        }
+    }
+
+    /// Выставляет закрывающую заявку сработавшего трейлинг-стопа.
+    ///
+    /// После подтверждения заявки стоп снимается. При ошибке стоп возвращается
+    /// в активное состояние, чтобы сработать снова при следующей цене за стопом.
+    async fn fire(self: Arc<Self>, trail: TrailingStop) {
+        log::info!(
+            "Trailing stop {} for {} triggered at {:?}",
+            trail.id,
+            trail.symbol,
+            trail.stop_price
+        );
+
+        match self.sdk.place_order(trail.exit_order()).await {
+            Ok(order) => self.complete(trail, order).await,
+            Err(error) => {
+                log::error!(
+                    "Failed to place trailing stop {} order: {:?}",
+                    trail.id,
+                    error
+                );
+
+                if let Some(stored) = self.trails.lock().await.get_mut(&trail.id) {
+                    stored.state = TrailState::Active;
+                }
+                if let Err(error) = self.persist().await {
+                    log::error!("Failed to persist trailing stops: {:?}", error);
+                }
+
+                let _ = self.events.send(TrailingStopEvent::Failed {
+                    trail: TrailingStop {
+                        state: TrailState::Active,
+                        ..trail
+                    },
+                    error: error.to_string(),
+                });
+            }
+        }
+    }
+
+    /// Снимает стоп, закрывающая заявка которого подтверждена.
+    async fn complete(&self, trail: TrailingStop, order: OrderState) {
+        self.trails.lock().await.remove(&trail.id);
+
+        if let Err(error) = self.persist().await {
+            log::error!("Failed to persist trailing stops: {:?}", error);
+        }
+        self.prune_feeds().await;
+
+        let _ = self.events.send(TrailingStopEvent::Triggered {
+            trail,
+            order: Box::new(order),
+        });
+    }
+
+    /// Атомарно сохраняет активные трейлинг-стопы в файл.
+    async fn persist(&self) -> Result<(), FinamSdkError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let content = {
+            let trails = self.trails.lock().await;
+            let mut trails: Vec<_> = trails.values().collect();
+            trails.sort_by(|a, b| a.id.cmp(&b.id));
+            serde_json::to_vec_pretty(&trails)?
+        };
+
+        let temporary = path.with_extension("tmp");
+        tokio::fs::write(&temporary, content).await?;
+        tokio::fs::rename(&temporary, path).await?;
+
+        Ok(())
+    }
+}
+
+/// Сериализация стороны сделки по имени из ProtoBuf определения.
+mod side_name {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use crate::proto::grpc::tradeapi::v1::Side;
+
+    pub fn serialize<S: Serializer>(side: &Side, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(side.as_str_name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Side, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Side::from_str_name(&name).ok_or_else(|| D::Error::custom(format!("unknown side {name}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trail(side: Side, offset: TrailingOffset) -> TrailingStop {
+        TrailingStop {
+            id: "t1".to_string(),
+            account_id: "A1".to_string(),
+            symbol: "SBER@MISX".to_string(),
+            side,
+            quantity: Decimal::from(10),
+            offset,
+            source: TrailPriceSource::Quote,
+            execution: TrailExecution::Limit {
+                slippage: TrailingOffset::Absolute(Decimal::ONE),
+            },
+            price_step: Decimal::new(1, 1),
+            best_price: None,
+            stop_price: None,
+            state: TrailState::Active,
+        }
+    }
+
+    #[test]
+    fn test_sell_trail_ratchets_up_and_triggers() {
+        let mut trail = trail(Side::Sell, TrailingOffset::Absolute(Decimal::from(5)));
+
+        assert_eq!(trail.update(Decimal::from(100)), TrailUpdate::Moved);
+        assert_eq!(trail.stop_price, Some(Decimal::from(95)));
+        assert_eq!(trail.update(Decimal::from(98)), TrailUpdate::Unchanged);
+        assert_eq!(trail.update(Decimal::from(110)), TrailUpdate::Moved);
+        assert_eq!(trail.stop_price, Some(Decimal::from(105)));
+        assert_eq!(trail.update(Decimal::from(105)), TrailUpdate::Triggered);
+        assert_eq!(trail.state, TrailState::Triggered);
+        assert_eq!(trail.update(Decimal::from(90)), TrailUpdate::Unchanged);
+
+        let order = trail.exit_order();
+        assert_eq!(order.r#type(), OrderType::Limit);
+        assert_eq!(order.limit_price, Some(from_decimal(Decimal::from(104))));
+    }
+
+    #[test]
+    fn test_buy_trail_with_percent_offset_rounds_to_step() {
+        let mut trail = trail(Side::Buy, TrailingOffset::Percent(Decimal::ONE));
+
+        assert_eq!(trail.update(Decimal::new(1003, 1)), TrailUpdate::Moved);
+        assert_eq!(trail.stop_price, Some(Decimal::new(1014, 1)));
+        assert_eq!(trail.update(Decimal::from(101)), TrailUpdate::Unchanged);
+        assert_eq!(trail.update(Decimal::new(1014, 1)), TrailUpdate::Triggered);
+    }
+
+    #[test]
+    fn test_trail_serialization_roundtrip() {
+        let mut trail = trail(Side::Sell, TrailingOffset::Percent(Decimal::new(15, 1)));
+        trail.update(Decimal::from(250));
+
+        let json = serde_json::to_string(&trail).unwrap();
+        assert!(json.contains("SIDE_SELL"));
+        assert_eq!(serde_json::from_str::<TrailingStop>(&json).unwrap(), trail);
+
+        trail.state = TrailState::Triggered;
+        let json = serde_json::to_string(&trail).unwrap();
+        assert_eq!(serde_json::from_str::<TrailingStop>(&json).unwrap(), trail);
+    }
+}
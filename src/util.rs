@@ -0,0 +1,117 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::proto::google::r#type::Date;
+
+/// Количество секунд в сутках.
+pub(crate) const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Задержка перед повторной подпиской после обрыва потока.
+pub(crate) const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Емкость каналов рассылки событий фоновых задач.
+pub(crate) const EVENTS_CAPACITY: usize = 1024;
+
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Создает уникальный в пределах процесса идентификатор из времени в миллисекундах и счетчика.
+///
+/// # Аргументы
+///
+/// * `prefix` - Префикс идентификатора.
+///
+/// # Возвращает
+///
+/// * `String` - Идентификатор вида `{prefix}{millis:x}{counter:x}`.
+pub(crate) fn next_id(prefix: &str) -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    let counter = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{prefix}{millis:x}{counter:x}")
+}
+
+/// Переводит номер дня от 1970-01-01 в дату пролептического григорианского календаря.
+///
+/// # Аргументы
+///
+/// * `days` - Количество дней от 1970-01-01, отрицательное для более ранних дат.
+///
+/// # Возвращает
+///
+/// * `Date` - Календарная дата.
+pub(crate) fn date_from_days(days: i64) -> Date {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    Date {
+        year: year as i32,
+        month: month as i32,
+        day: day as i32,
+    }
+}
+
+/// Переводит дату пролептического григорианского календаря в номер дня от 1970-01-01.
+///
+/// # Аргументы
+///
+/// * `date` - Календарная дата.
+///
+/// # Возвращает
+///
+/// * `Option<i64>` - Количество дней или `None` для незаполненной или некорректной даты.
+pub(crate) fn days_from_date(date: &Date) -> Option<i64> {
+    if date.year <= 0 || !(1..=12).contains(&date.month) || !(1..=31).contains(&date.day) {
+        return None;
+    }
+
+    let year = i64::from(date.year) - i64::from(date.month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(date.month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(date.day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_id_is_unique_and_prefixed() {
+        let first = next_id("trail-");
+        let second = next_id("trail-");
+
+        assert!(first.starts_with("trail-"));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_date_conversion_round_trip() {
+        let date = |year, month, day| Date { year, month, day };
+
+        assert_eq!(days_from_date(&date(1970, 1, 1)), Some(0));
+        assert_eq!(date_from_days(-1), date(1969, 12, 31));
+        assert_eq!(days_from_date(&date(2024, 3, 1)), Some(19_783));
+        assert_eq!(days_from_date(&date(2024, 13, 1)), None);
+
+        for days in [-1, 0, 59, 11_016, 19_782, 2_932_896] {
+            assert_eq!(days_from_date(&date_from_days(days)), Some(days));
+        }
+    }
+}